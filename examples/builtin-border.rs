@@ -21,7 +21,7 @@ fn main() -> anyhow::Result<()> {
             height: Some(1000),
         },
         mode: types::FitMode::Image,
-        margin: 0.05,
+        margin: types::sides::percent::Sides::uniform(0.05),
         frame_width: types::SidesPercent::uniform(0.01),
         background_color: Some(types::Color::rgba(200, 255, 255, 255)),
         frame_color: types::Color::black(),
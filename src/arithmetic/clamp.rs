@@ -21,7 +21,6 @@ where
     Self: Sized + PartialOrd<Self>,
 {
     #[inline]
-    #[must_use]
     fn clamp<MIN, MAX>(self, min: MIN, max: MAX) -> Self
     where
         MIN: Into<Self>,
@@ -36,7 +35,6 @@ where
     Self: Sized + PartialOrd<Self>,
 {
     #[inline]
-    #[must_use]
     fn clamp_min<MIN>(self, min: MIN) -> Self
     where
         MIN: Into<Self>,
@@ -124,23 +124,21 @@ where
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.0.kind {
-            Some(kind) => {
-                let kind = match kind {
-                    arithmetic::error::Kind::DivideByZero => {
-                        write!(f, "dividing {} by {} is undefined", self.0.lhs, self.0.rhs)
-                    }
-                    other => {
-                        write!(
-                            f,
-                            "dividing {} by {} would {} {}",
-                            self.0.lhs,
-                            self.0.rhs,
-                            other,
-                            std::any::type_name::<Lhs>(),
-                        )
-                    }
-                };
-            }
+            Some(kind) => match kind {
+                arithmetic::error::Kind::DivideByZero => {
+                    write!(f, "dividing {} by {} is undefined", self.0.lhs, self.0.rhs)
+                }
+                other => {
+                    write!(
+                        f,
+                        "dividing {} by {} would {} {}",
+                        self.0.lhs,
+                        self.0.rhs,
+                        other,
+                        std::any::type_name::<Lhs>(),
+                    )
+                }
+            },
             None => write!(f, "cannot divide {} by {}", self.0.lhs, self.0.rhs),
         }
     }
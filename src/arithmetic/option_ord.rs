@@ -24,7 +24,6 @@ where
     }
 
     #[inline]
-    #[must_use]
     fn min(self, other: Self) -> Self
     where
         Self: Sized,
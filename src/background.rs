@@ -0,0 +1,356 @@
+use super::arithmetic::{self, Cast, Round};
+use super::types::{CropMode, Point, ResizeMode, Size};
+use super::{error, imageops, img};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum Mode {
+    /// Flat background color
+    Color,
+    /// Blurred and darkened copy of the primary image
+    Blur,
+    /// Background image covering the canvas
+    Image,
+    /// Background image repeated as tiles
+    Texture,
+}
+
+impl Default for Mode {
+    #[inline]
+    fn default() -> Self {
+        Mode::Color
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = error::ParseEnum;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "color" => Ok(Mode::Color),
+            "blur" => Ok(Mode::Blur),
+            "image" => Ok(Mode::Image),
+            "texture" | "tile" => Ok(Mode::Texture),
+            _ => Err(error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+/// Background of the canvas behind the framed content
+///
+/// All modes are drawn on top of the background color.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(default)]
+pub struct Background {
+    pub mode: Mode,
+    /// Blur radius relative to the shorter side of the canvas
+    pub blur: f32,
+    /// Darkening of the blurred image between 0 and 1
+    pub darken: f32,
+    /// Zoom of the background image or size of the texture tiles
+    /// relative to the texture
+    pub scale: f32,
+    /// Opacity of the background image or texture
+    pub opacity: f32,
+}
+
+impl Default for Background {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            mode: Mode::default(),
+            blur: 0.05,
+            darken: 0.2,
+            scale: 1.0,
+            opacity: 1.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Background {
+    #[wasm_bindgen(constructor)]
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Background {
+    /// Draws the background onto `image`
+    ///
+    /// The `Image` and `Texture` modes require a background `texture`.
+    #[inline]
+    pub fn draw(
+        &self,
+        image: &mut img::Image,
+        primary: &img::Image,
+        texture: Option<&img::Image>,
+    ) -> Result<(), Error> {
+        match self.mode {
+            Mode::Color => Ok(()),
+            Mode::Blur => self.draw_blur(image, primary),
+            Mode::Image => {
+                let texture = texture.ok_or(Error::MissingImage(self.mode))?;
+                self.draw_image(image, texture)
+            }
+            Mode::Texture => {
+                let texture = texture.ok_or(Error::MissingImage(self.mode))?;
+                self.draw_texture(image, texture)
+            }
+        }
+    }
+
+    #[inline]
+    fn draw_blur(&self, image: &mut img::Image, primary: &img::Image) -> Result<(), Error> {
+        let size = image.size();
+        let sigma = f64::from(self.blur.max(0.0)) * f64::from(size.min_dim());
+        // blurring a downscaled copy is much faster and looks the same
+        let downscale = (sigma / 4.0).max(1.0);
+        let small_size =
+            size.scale_by::<_, Round>(1.0 / downscale)
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to compute size of blurred background".to_string(),
+                    source: err.into(),
+                })?;
+        let small_size = Size {
+            width: small_size.width.max(1),
+            height: small_size.height.max(1),
+        };
+        let mut background = primary.clone();
+        background.resize_and_crop(small_size, ResizeMode::Cover, CropMode::Center)?;
+        let sigma = (sigma / downscale)
+            .cast::<f32>()
+            .map_err(|err| error::Arithmetic {
+                msg: "failed to compute blur of background".to_string(),
+                source: err.into(),
+            })?;
+        let blurred = if sigma > 0.0 {
+            imageops::blur(&*background, sigma)
+        } else {
+            background.inner
+        };
+        let mut blurred = imageops::resize(
+            &blurred,
+            size.width,
+            size.height,
+            imageops::FilterType::Triangle,
+        );
+        let brightness = 1.0 - self.darken.clamp(0.0, 1.0);
+        for pixel in blurred.pixels_mut() {
+            for channel in &mut pixel.0[..3] {
+                *channel = (f32::from(*channel) * brightness)
+                    .round()
+                    .clamp(0.0, 255.0)
+                    .cast::<u8>()
+                    .map_err(|err| error::Arithmetic {
+                        msg: "failed to darken blurred background".to_string(),
+                        source: err.into(),
+                    })?;
+            }
+            pixel.0[3] = 255;
+        }
+        image.inner = blurred;
+        Ok(())
+    }
+
+    #[inline]
+    fn draw_image(&self, image: &mut img::Image, texture: &img::Image) -> Result<(), Error> {
+        let size = image.size();
+        let zoomed_size = size
+            .scale_by::<_, Round>(self.scale.max(0.0))
+            .map_err(|err| error::Arithmetic {
+                msg: "failed to compute size of background image".to_string(),
+                source: err.into(),
+            })?;
+        if zoomed_size.width == 0 || zoomed_size.height == 0 {
+            return Ok(());
+        }
+        let mut background = texture.clone();
+        background.resize_and_crop(zoomed_size, ResizeMode::Cover, CropMode::Center)?;
+        let offset = size
+            .center(background.size())
+            .map_err(|err| error::Arithmetic {
+                msg: "failed to center background image".to_string(),
+                source: err.into(),
+            })?
+            .top_left();
+        image.overlay(&self.with_opacity(background)?, offset);
+        Ok(())
+    }
+
+    #[inline]
+    fn draw_texture(&self, image: &mut img::Image, texture: &img::Image) -> Result<(), Error> {
+        let tile_size = texture
+            .size()
+            .scale_by::<_, Round>(self.scale.max(0.0))
+            .map_err(|err| error::Arithmetic {
+                msg: "failed to compute size of texture tiles".to_string(),
+                source: err.into(),
+            })?;
+        if tile_size.width == 0 || tile_size.height == 0 {
+            return Ok(());
+        }
+        let mut tile = texture.clone();
+        tile.resize(tile_size, ResizeMode::Fill)?;
+        let tile = self.with_opacity(tile)?;
+        let size = image.size();
+        let (step_x, step_y) = (|| {
+            Ok::<_, arithmetic::Error>((
+                tile_size.width.cast::<usize>()?,
+                tile_size.height.cast::<usize>()?,
+            ))
+        })()
+        .map_err(|err| error::Arithmetic {
+            msg: "failed to compute step of texture tiles".to_string(),
+            source: err,
+        })?;
+        for y in (0..size.height).step_by(step_y) {
+            for x in (0..size.width).step_by(step_x) {
+                let offset = Point {
+                    x: i64::from(x),
+                    y: i64::from(y),
+                };
+                image.overlay(&tile, offset);
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn with_opacity(&self, mut image: img::Image) -> Result<img::Image, Error> {
+        let opacity = self.opacity.clamp(0.0, 1.0);
+        if opacity < 1.0 {
+            for pixel in image.pixels_mut() {
+                pixel.0[3] = (f32::from(pixel.0[3]) * opacity)
+                    .round()
+                    .cast::<u8>()
+                    .map_err(|err| error::Arithmetic {
+                        msg: "failed to apply opacity of background".to_string(),
+                        source: err.into(),
+                    })?;
+            }
+        }
+        Ok(image)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("background mode {0:?} requires a background image")]
+    MissingImage(Mode),
+
+    #[error("failed to resize background")]
+    Resize(
+        #[from]
+        #[source]
+        img::ResizeError,
+    ),
+
+    #[error("failed to resize background")]
+    ResizeAndCrop(
+        #[from]
+        #[source]
+        img::ResizeAndCropError,
+    ),
+
+    #[error(transparent)]
+    Arithmetic(#[from] error::Arithmetic),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Background, Error, Mode};
+    use crate::img::Image;
+    use crate::types::Color;
+    use crate::FillMode;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn image_with_color(width: u32, height: u32, color: [u8; 4]) -> Image {
+        let image = image::RgbaImage::from_pixel(width, height, image::Rgba(color));
+        Image::from_image(&image::DynamicImage::ImageRgba8(image))
+    }
+
+    #[test]
+    fn test_blur_background() -> Result<()> {
+        let mut canvas = Image::new(200, 100);
+        // left half is red, right half is blue
+        let primary = image::RgbaImage::from_fn(100, 100, |x, _| {
+            if x < 50 {
+                image::Rgba([200, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 200, 255])
+            }
+        });
+        let primary = Image::from_image(&image::DynamicImage::ImageRgba8(primary));
+        let background = Background {
+            mode: Mode::Blur,
+            blur: 0.1,
+            darken: 0.5,
+            ..Background::default()
+        };
+        background.draw(&mut canvas, &primary, None)?;
+        assert_eq!(*canvas.get_pixel(0, 50), image::Rgba([100, 0, 0, 255]));
+        assert_eq!(*canvas.get_pixel(199, 50), image::Rgba([0, 0, 100, 255]));
+        // the edge between the halves is blurred
+        let center = canvas.get_pixel(100, 50);
+        assert!(center[0] > 20 && center[2] > 20);
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_background() -> Result<()> {
+        let mut canvas = Image::new(100, 100);
+        canvas.fill(Color::white(), FillMode::Set)?;
+        let texture = image_with_color(10, 20, [0, 0, 0, 255]);
+        let background = Background {
+            mode: Mode::Image,
+            scale: 0.5,
+            opacity: 0.5,
+            ..Background::default()
+        };
+        background.draw(&mut canvas, &texture, Some(&texture))?;
+        assert_eq!(*canvas.get_pixel(5, 5), image::Rgba([255, 255, 255, 255]));
+        assert_eq!(canvas.get_pixel(50, 50).0[..3], [127, 127, 127]);
+        assert!(matches!(
+            background.draw(&mut canvas, &texture, None),
+            Err(Error::MissingImage(Mode::Image))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_texture_background() -> Result<()> {
+        let mut canvas = Image::new(100, 100);
+        canvas.fill(Color::white(), FillMode::Set)?;
+        // tile with a black top-left quarter
+        let tile = image::RgbaImage::from_fn(20, 20, |x, y| {
+            if x < 10 && y < 10 {
+                image::Rgba([0, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 0, 0])
+            }
+        });
+        let tile = Image::from_image(&image::DynamicImage::ImageRgba8(tile));
+        let background = Background {
+            mode: Mode::Texture,
+            scale: 2.0,
+            ..Background::default()
+        };
+        background.draw(&mut canvas, &tile, Some(&tile))?;
+        for (x, y) in [(5, 5), (45, 5), (85, 45), (5, 85)] {
+            assert_eq!(*canvas.get_pixel(x, y), image::Rgba([0, 0, 0, 255]));
+        }
+        for (x, y) in [(30, 5), (5, 30), (70, 70)] {
+            assert_eq!(*canvas.get_pixel(x, y), image::Rgba([255, 255, 255, 255]));
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,31 @@
-use super::arithmetic::{ops::CheckedSub, Round};
+use super::arithmetic::Round;
 use super::img::{self, Image};
 use super::types::{self, Point, Rect, Size};
-use super::{arithmetic, debug, error, imageops};
+use super::{arithmetic, debug, error, imageops, procedural, svg};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::ffi::OsStr;
 use std::path::PathBuf;
 
-#[derive(Clone, Copy, Debug)]
+pub mod manifest;
+pub mod stitch;
+
+pub use manifest::Manifest;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
 pub struct Options {
     pub transparent_component_threshold: u32,
     pub alpha_threshold: f64,
+    /// Key color of the windows of borders without transparency
+    pub chroma_key: Option<ChromaKey>,
+    /// Choose the alpha threshold from the alpha histogram of the border
+    /// instead of using `alpha_threshold`
+    pub adaptive_alpha_threshold: bool,
+    /// Minimum area of a window as a fraction of the area of the border
+    pub min_component_area: f64,
+    /// Treat opaque specks enclosed by a window as part of the window
+    pub fill_holes: bool,
 }
 
 impl Default for Options {
@@ -17,13 +34,256 @@ impl Default for Options {
         Self {
             transparent_component_threshold: 8,
             alpha_threshold: 0.95,
+            chroma_key: None,
+            adaptive_alpha_threshold: false,
+            min_component_area: 0.0,
+            fill_holes: false,
+        }
+    }
+}
+
+/// Thresholds the windows of a border were detected with
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Detection {
+    /// Alpha below which pixels were transparent
+    pub alpha_threshold: f64,
+    pub transparent_component_threshold: u32,
+    /// Minimum number of pixels of a window
+    pub min_component_pixels: u64,
+    /// Number of components discarded for being too small
+    pub discarded_components: usize,
+}
+
+/// Flat color marking the windows of a border, e.g. in a scan of a slide mount
+///
+/// Pixels matching the key become transparent when the border is built.
+/// Colors are compared by their distance in the CIELAB color space,
+/// where a distance of about 2.3 is just noticeable.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct ChromaKey {
+    pub color: types::Color,
+    /// Distance up to which pixels become fully transparent
+    pub tolerance: f64,
+    /// Distance beyond the tolerance over which pixels fade back in
+    pub feather: f64,
+}
+
+impl Default for ChromaKey {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            color: types::Color::rgb(0, 255, 0),
+            tolerance: 12.0,
+            feather: 8.0,
         }
     }
 }
 
+/// Order in which images are assigned to the windows of a border
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowOrder {
+    /// Largest window first, windows of equal size in reading order
+    #[default]
+    Area,
+    /// Left to right in rows from top to bottom
+    Reading,
+    /// The `i`-th image goes into the window at index `indices[i]` in
+    /// reading order, remaining windows follow in reading order
+    Indices(Vec<usize>),
+}
+
+impl std::str::FromStr for WindowOrder {
+    type Err = error::ParseEnum;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "area" => Ok(Self::Area),
+            "reading" => Ok(Self::Reading),
+            indices => indices
+                .split(',')
+                .map(|idx| idx.trim().parse::<usize>())
+                .collect::<Result<_, _>>()
+                .map(Self::Indices)
+                .map_err(|_| error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+/// Orders `windows` left to right in rows from top to bottom
+///
+/// Windows belong to the same row if their vertical center lies within
+/// the first window of the row.
+fn reading_order(windows: &[Rect]) -> Vec<usize> {
+    let mut by_top: Vec<usize> = (0..windows.len()).collect();
+    by_top.sort_by_key(|&idx| (windows[idx].top, windows[idx].left));
+    let mut rows: Vec<(Rect, Vec<usize>)> = Vec::new();
+    for idx in by_top {
+        let window = &windows[idx];
+        let center = window.top + window.height() / 2;
+        match rows.last_mut() {
+            Some((first, row)) if (first.top..=first.bottom).contains(&center) => row.push(idx),
+            _ => rows.push((*window, vec![idx])),
+        }
+    }
+    rows.into_iter()
+        .flat_map(|(_, mut row)| {
+            row.sort_by_key(|&idx| (windows[idx].left, windows[idx].top));
+            row
+        })
+        .collect()
+}
+
+/// Coverage of a window of a border
+///
+/// The coverage is 255 where the border is fully transparent and follows
+/// the alpha of the border at soft edges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    /// Top left corner of the mask in the border image
+    pub top_left: Point,
+    pub coverage: image::GrayImage,
+}
+
+impl Mask {
+    /// Coverage at `point` of the border image
+    #[inline]
+    #[must_use]
+    pub fn coverage_at(&self, point: Point) -> u8 {
+        let x = u32::try_from(point.x - self.top_left.x);
+        let y = u32::try_from(point.y - self.top_left.y);
+        match (x, y) {
+            (Ok(x), Ok(y)) => self
+                .coverage
+                .get_pixel_checked(x, y)
+                .map_or(0, |pixel| pixel.0[0]),
+            _ => 0,
+        }
+    }
+
+    /// Bounds of the mask in the border image, with an exclusive bottom right
+    #[inline]
+    fn rect(&self) -> Result<Rect, arithmetic::Error> {
+        use arithmetic::ops::CheckedAdd;
+        let (width, height) = self.coverage.dimensions();
+        let size = Point {
+            x: i64::from(width),
+            y: i64::from(height),
+        };
+        Ok(Rect::from_points(
+            self.top_left,
+            self.top_left.checked_add(size)?,
+        ))
+    }
+
+    /// Places the mask in an image of the border of `size`
+    #[inline]
+    fn to_image(&self, size: Size) -> image::GrayImage {
+        let mut image = image::GrayImage::new(size.width, size.height);
+        imageops::replace(&mut image, &self.coverage, self.top_left.x, self.top_left.y);
+        image
+    }
+
+    /// Mask of the covered pixels of an image of the border
+    ///
+    /// Returns `None` if no pixel is covered.
+    #[inline]
+    fn from_image(image: &image::GrayImage) -> Result<Option<Self>, arithmetic::Error> {
+        use arithmetic::Cast;
+        let mut extent: Option<Rect> = None;
+        for (x, y, pixel) in image.enumerate_pixels() {
+            if pixel.0[0] == 0 {
+                continue;
+            }
+            let point = Point {
+                x: i64::from(x),
+                y: i64::from(y),
+            };
+            match &mut extent {
+                Some(extent) => extent.extend_to(&point),
+                None => extent = Some(Rect::from(point)),
+            }
+        }
+        let Some(extent) = extent else {
+            return Ok(None);
+        };
+        let coverage = imageops::crop_imm(
+            image,
+            extent.left.cast::<u32>()?,
+            extent.top.cast::<u32>()?,
+            (extent.width() + 1).cast::<u32>()?,
+            (extent.height() + 1).cast::<u32>()?,
+        );
+        Ok(Some(Self {
+            top_left: extent.top_left(),
+            coverage: coverage.to_image(),
+        }))
+    }
+
+    /// Mask in the border of `size` rotated by `angle`
+    #[inline]
+    fn rotate(&self, size: Size, angle: types::Rotation) -> Result<Self, arithmetic::Error> {
+        use types::Rotation;
+        let rect = self.rect()?.rotate(size, angle)?;
+        let coverage = match angle {
+            Rotation::Rotate0 => self.coverage.clone(),
+            Rotation::Rotate90 => imageops::rotate90(&self.coverage),
+            Rotation::Rotate180 => imageops::rotate180(&self.coverage),
+            Rotation::Rotate270 => imageops::rotate270(&self.coverage),
+        };
+        Ok(Self {
+            top_left: rect.top_left(),
+            coverage,
+        })
+    }
+
+    /// Mask in the border of `size` resized to `scaled` and cropped to `crop`
+    ///
+    /// Returns `None` if the mask is cropped away.
+    #[inline]
+    fn resize_and_crop(
+        &self,
+        size: Size,
+        scaled: Size,
+        crop: &Rect,
+    ) -> Result<Option<Self>, arithmetic::Error> {
+        use arithmetic::{ops::CheckedSub, Cast};
+        let rect = self.rect()?;
+        let cropped = resize_and_crop_rect(rect, size, scaled, crop, Rect::scale)?;
+        if cropped.width() == 0 || cropped.height() == 0 {
+            return Ok(None);
+        }
+        let rect = rect.scale(size, scaled)?;
+        let Size { width, height } = rect.size()?;
+        let coverage = if self.coverage.dimensions() == (width, height) {
+            self.coverage.clone()
+        } else {
+            // no ringing beyond the edges of the window
+            imageops::resize(
+                &self.coverage,
+                width,
+                height,
+                imageops::FilterType::Triangle,
+            )
+        };
+        let x = CheckedSub::checked_sub(cropped.left + crop.left, rect.left)?.cast::<u32>()?;
+        let y = CheckedSub::checked_sub(cropped.top + crop.top, rect.top)?.cast::<u32>()?;
+        let Size { width, height } = cropped.size()?;
+        Ok(Some(Self {
+            top_left: cropped.top_left(),
+            coverage: imageops::crop_imm(&coverage, x, y, width, height).to_image(),
+        }))
+    }
+}
+
+// borders are loaded once per render, so boxing them gains nothing
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
 pub enum Kind {
     #[cfg(feature = "builtin")]
     Builtin(super::builtin::Builtin),
+    Procedural(procedural::Film),
     Custom(Border),
 }
 
@@ -38,6 +298,18 @@ impl From<Border> for Option<Kind> {
     }
 }
 
+impl From<procedural::Film> for Kind {
+    fn from(film: procedural::Film) -> Self {
+        Kind::Procedural(film)
+    }
+}
+
+impl From<procedural::Film> for Option<Kind> {
+    fn from(film: procedural::Film) -> Self {
+        Some(Kind::Procedural(film))
+    }
+}
+
 #[cfg(feature = "builtin")]
 impl From<super::builtin::Builtin> for Kind {
     fn from(builtin: super::builtin::Builtin) -> Self {
@@ -58,6 +330,7 @@ impl Kind {
         match self {
             #[cfg(feature = "builtin")]
             Self::Builtin(builtin) => builtin.into_border(),
+            Self::Procedural(film) => Border::procedural(film, None),
             Self::Custom(border) => Ok(border),
         }
     }
@@ -68,16 +341,31 @@ impl std::fmt::Debug for Kind {
         match self {
             #[cfg(feature = "builtin")]
             Kind::Builtin(builtin) => write!(f, "Builtin({:?})", builtin),
+            Kind::Procedural(film) => write!(f, "Procedural({:?})", film.format),
             Kind::Custom(_) => write!(f, "Custom"),
         }
     }
 }
 
+/// Source a border is rendered from again whenever it is resized
+#[derive(Clone, Debug)]
+enum Source {
+    Procedural(procedural::Film),
+    Svg {
+        svg: svg::Svg,
+        rotation: types::Rotation,
+    },
+}
+
 #[derive(Clone)]
 pub struct Border {
     inner: Image,
     options: Option<Options>,
     transparent_components: Vec<Rect>,
+    masks: Vec<Option<Mask>>,
+    source: Option<Source>,
+    geometry: Option<Box<manifest::Geometry>>,
+    detection: Option<Detection>,
 }
 
 impl std::ops::Deref for Border {
@@ -100,14 +388,55 @@ impl Border {
         reader: R,
         options: Option<Options>,
     ) -> Result<Self, Error> {
+        Self::from_reader_with_manifest(reader, options, None)
+    }
+
+    /// Reads a border whose geometry is declared in `manifest`
+    ///
+    /// Declared windows do not need to be transparent.
+    #[inline]
+    pub fn from_reader_with_manifest<R: std::io::BufRead + std::io::Seek>(
+        mut reader: R,
+        options: Option<Options>,
+        manifest: Option<&Manifest>,
+    ) -> Result<Self, Error> {
+        if reader.fill_buf().is_ok_and(svg::is_svg) {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).map_err(svg::Error::from)?;
+            return Self::unchecked_svg(svg::Svg::from_data(&data)?, options)?.finish(manifest);
+        }
         let image = Image::from_reader(reader).map_err(img::Error::from)?;
-        Self::from_image(image, options).map_err(Error::from)
+        Self::unchecked(image, options).finish(manifest)
     }
 
+    /// Opens a border and the manifest sidecar next to it, if there is one
     #[inline]
     pub fn open(path: impl Into<PathBuf>, options: Option<Options>) -> Result<Self, Error> {
-        let image = Image::open(path.into()).map_err(img::Error::from)?;
-        Self::from_image(image, options).map_err(Error::from)
+        let path = path.into();
+        let is_svg = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"));
+        let manifest = Manifest::open_sidecar(&path)?;
+        let border = if is_svg {
+            let data = std::fs::read(&path).map_err(svg::Error::from)?;
+            Self::unchecked_svg(svg::Svg::from_data(&data)?, options)?
+        } else {
+            let image = Image::open(path).map_err(img::Error::from)?;
+            Self::unchecked(image, options)
+        };
+        border.finish(manifest.as_ref())
+    }
+
+    /// Uses the geometry declared in `manifest` instead of detecting it
+    ///
+    /// Declared windows replace the transparent components in their
+    /// declared order.
+    /// The detection thresholds of the manifest only apply if the border
+    /// has no options.
+    #[inline]
+    pub fn with_manifest(self, manifest: &Manifest) -> Result<Self, Error> {
+        self.finish(Some(manifest))
     }
 
     #[inline]
@@ -115,38 +444,147 @@ impl Border {
         inner: Image,
         options: Option<Options>,
     ) -> Result<Self, TransparentComponentsError> {
-        let mut border = Self {
+        Self::with_geometry(inner, options, None)
+    }
+
+    #[inline]
+    fn with_geometry(
+        inner: Image,
+        options: Option<Options>,
+        geometry: Option<manifest::Geometry>,
+    ) -> Result<Self, TransparentComponentsError> {
+        let mut border = Self::unchecked(inner, options);
+        border.geometry = geometry.map(Box::new);
+        border.apply_chroma_key();
+        border.compute_transparent_components(options)?;
+        Ok(border)
+    }
+
+    /// Border without any windows yet
+    #[inline]
+    fn unchecked(inner: Image, options: Option<Options>) -> Self {
+        Self {
             inner,
             options,
             transparent_components: Vec::new(),
+            masks: Vec::new(),
+            source: None,
+            geometry: None,
+            detection: None,
+        }
+    }
+
+    #[inline]
+    fn unchecked_svg(svg: svg::Svg, options: Option<Options>) -> Result<Self, Error> {
+        let mut border = Self::unchecked(svg.render(svg.size())?, options);
+        border.source = Some(Source::Svg {
+            svg,
+            rotation: types::Rotation::Rotate0,
+        });
+        Ok(border)
+    }
+
+    /// Finds the windows, or takes them from `manifest`
+    #[inline]
+    fn finish(mut self, manifest: Option<&Manifest>) -> Result<Self, Error> {
+        if let Some(manifest) = manifest {
+            self.geometry = Some(Box::new(manifest.geometry(self.size())?));
+            self.options = self.options.or(manifest.detection);
+        }
+        self.apply_chroma_key();
+        self.compute_transparent_components(self.options)?;
+        Ok(self)
+    }
+
+    /// Makes the pixels matching the chroma key transparent
+    #[inline]
+    fn apply_chroma_key(&mut self) {
+        if let Some(key) = self.options.and_then(|options| options.chroma_key) {
+            imageops::chroma_key(
+                &mut self.inner,
+                key.color.into(),
+                key.tolerance,
+                key.feather,
+            );
+        }
+    }
+
+    /// Generates a border from `film`
+    ///
+    /// Without a `size`, the border is generated at the default size of
+    /// the film format.
+    /// Procedural borders are generated again at the exact target size
+    /// whenever they are resized, so they never get blurry.
+    #[inline]
+    pub fn procedural(
+        film: procedural::Film,
+        size: impl Into<Option<Size>>,
+    ) -> Result<Self, Error> {
+        let size = match size.into() {
+            Some(size) => size,
+            None => film.default_size().map_err(|err| error::Arithmetic {
+                msg: "failed to compute default size of procedural border".into(),
+                source: err.into(),
+            })?,
         };
-        border.compute_transparent_components(options)?;
+        let image = film.render(size).map_err(|err| error::Arithmetic {
+            msg: "failed to render procedural border".into(),
+            source: err.into(),
+        })?;
+        let mut border = Self::from_image(image, None)?;
+        border.source = Some(Source::Procedural(film));
         Ok(border)
     }
 
+    /// Rasterizes an SVG border at the size of the document
+    ///
+    /// Windows are either transparent or marked by elements with an id
+    /// starting with `window`.
+    /// SVG borders are rasterized again at the exact target size whenever
+    /// they are resized, so they never get blurry.
+    #[inline]
+    pub fn from_svg(svg: svg::Svg, options: Option<Options>) -> Result<Self, Error> {
+        Self::unchecked_svg(svg, options)?.finish(None)
+    }
+
     #[inline]
     pub fn custom(
         mut border: Self,
         content_size: Size,
-        stich_direction: Option<types::Orientation>,
+        stitch_axis: Option<types::Axis>,
     ) -> Result<Self, Error> {
-        use arithmetic::{
-            ops::{CheckedAdd, CheckedDiv, CheckedMul},
-            Cast,
-        };
+        use arithmetic::ops::CheckedDiv;
 
-        let components = border.transparent_components().clone();
-        if components.len() != 1 {
-            return Err(Error::Invalid(InvalidTransparentComponentsError {
-                required: (Ordering::Equal, 1),
-                components,
-            }));
+        // procedural borders fit any content without stiching
+        if let Some(Source::Procedural(film)) = border.source {
+            let size = film
+                .size_for_window(content_size)
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to compute size of procedural border".into(),
+                    source: err.into(),
+                })?;
+            return Self::procedural(film, size);
+        }
+
+        // by default, stitch along the axis the content is longer in than the window
+        let stitch_axis = stitch_axis.unwrap_or_else(|| {
+            let window = border.content_size().unwrap_or(content_size);
+            let content_ratio = u64::from(content_size.width) * u64::from(window.height);
+            let window_ratio = u64::from(window.width) * u64::from(content_size.height);
+            if content_ratio > window_ratio {
+                types::Axis::X
+            } else {
+                types::Axis::Y
+            }
+        });
+        debug!(&stitch_axis);
+
+        // stitching is always done vertically
+        let mut content_size = content_size;
+        if stitch_axis == types::Axis::X {
+            border.rotate(&types::Rotation::Rotate90)?;
+            content_size = content_size.rotate(types::Rotation::Rotate90);
         }
-        // by default, use the longer dimension to stich
-        let stich_direction = stich_direction.unwrap_or(types::Orientation::Portrait);
-        border.rotate_to_orientation(stich_direction)?;
-        let original_orientation = content_size.orientation();
-        let content_size = content_size.rotate_to_orientation(stich_direction);
         debug!(&content_size);
 
         let border_size = border.size_for(types::BoundedSize {
@@ -156,225 +594,78 @@ impl Border {
         debug!(&border_size);
         border.resize_and_crop(border_size, types::ResizeMode::Cover)?;
 
-        // border is portrait now, we stich vertically
-        // todo: find optimal overlay patches somehow
-        let (top_patch_rect, _) =
-            compute_patch_rect(border_size, 0.0, 0.25).map_err(|err| error::Arithmetic {
-                msg: "failed to compute top patch".into(),
-                source: err,
-            })?;
-
-        let (bottom_patch_rect, bottom_patch_size) = compute_patch_rect(border_size, 0.75, 1.0)
-            .map_err(|err| error::Arithmetic {
-                msg: "failed to compute bottom patch".into(),
-                source: err,
-            })?;
-
-        let (_, overlay_patch_size) =
-            compute_patch_rect(border_size, 0.3, 0.7).map_err(|err| error::Arithmetic {
-                msg: "failed to compute overlay patch rect".into(),
-                source: err,
-            })?;
-
-        // create buffer for the new border
         let border_content_size = border.content_size()?;
         debug!(&border_content_size);
 
-        let border_padding = border_size
-            .checked_sub(border_content_size)
-            .map_err(|err| error::Arithmetic {
-                msg: "failed to compute border padding".to_string(),
-                source: err.into(),
-            })?;
-        debug!(&border_padding);
-
-        let new_border_size =
-            content_size
-                .checked_add(border_padding)
-                .map_err(|err| error::Arithmetic {
-                    msg: "failed to compute new border size".to_string(),
-                    source: err.into(),
-                })?;
-
-        let mut new_border = Image::with_size(new_border_size);
-        debug!(&new_border.size());
-
-        #[cfg(feature = "debug")]
-        {
-            let green = types::Color::rgba(0, 255, 0, 255);
-            let clear = types::Color::clear();
-            new_border
-                .fill(green, imageops::FillMode::Set)
-                .map_err(img::Error::from)?;
-
-            let content_rect = border.content_rect()?;
-            debug!(&content_rect);
-
-            let new_border_content_rect = (|| {
-                let bottom_right_padding =
-                    Point::from(border.size()).checked_sub(content_rect.bottom_right())?;
-                let new_border_content_rect_bottom_right =
-                    Point::from(new_border_size).checked_sub(bottom_right_padding)?;
-
-                let rect = Rect::from_points(
-                    content_rect.top_left(),
-                    new_border_content_rect_bottom_right,
-                );
-                Ok::<_, arithmetic::Error>(rect)
-            })();
-            let new_border_content_rect =
-                new_border_content_rect.map_err(|err| error::Arithmetic {
-                    msg: "failed to compute new border content rect".into(),
-                    source: err,
-                })?;
-
-            new_border
-                .fill_rect(clear, &new_border_content_rect, imageops::FillMode::Set)
-                .map_err(img::Error::from)?;
-        }
-
-        // draw top patch
-        let mut border_top: Image = border.inner.clone();
-        #[cfg(feature = "debug")]
-        border_top
-            .clip_alpha(&border_top.size().into(), 0, 200)
-            .map_err(img::Error::from)?;
-        border_top
-            .crop(&top_patch_rect)
-            .map_err(img::CropError::from)
-            .map_err(img::Error::from)?;
-
-        new_border.overlay(&border_top, Point::origin());
-
-        // draw bottom patch
-        let mut border_bottom = border.inner.clone();
-        #[cfg(feature = "debug")]
-        border_bottom
-            .clip_alpha(&border_bottom.size().into(), 0, 200)
-            .map_err(img::Error::from)?;
-        border_bottom
-            .crop(&bottom_patch_rect)
-            .map_err(img::CropError::from)
-            .map_err(img::Error::from)?;
-
-        let bottom_patch_top_left = Point::from(new_border_size)
-            .checked_sub(bottom_patch_size.into())
-            .map_err(|err| error::Arithmetic {
-                msg: "failed to compute bottom patch top left".to_string(),
-                source: err.into(),
-            })?;
-        new_border.overlay(&border_bottom, bottom_patch_top_left);
-
-        // draw patches in between
-        let fill_height = (|| {
-            let mut height = i64::from(new_border_size.height);
-            height = CheckedSub::checked_sub(height, bottom_patch_rect.height())?;
-            height = CheckedSub::checked_sub(height, top_patch_rect.height())?;
-            let height = height.cast::<u32>()?;
-            Ok::<_, arithmetic::Error>(height)
-        })();
-        let fill_height: u32 = fill_height.map_err(|err| error::Arithmetic {
-            msg: "failed to compute fill height".to_string(),
-            source: err,
-        })?;
-        debug!(&fill_height);
-        debug!(&overlay_patch_size.height);
-
-        let fade_height = (|| {
-            let height = f64::from(overlay_patch_size.height)
-                .checked_mul(0.2)?
-                .ceil()
-                .cast::<u32>()?;
-            Ok::<_, arithmetic::Error>(height)
+        // the first window is stretched to the content size, all other
+        // windows and the gaps between them are stretched proportionally
+        let segments = (|| {
+            let scale = f64::from(content_size.height)
+                .checked_div(f64::from(border_content_size.height.max(1)))?;
+            let band = border.geometry.as_ref().and_then(|g| g.stretch_y);
+            stretch_segments(&border.transparent_components, scale, band)
         })();
-        let fade_height = fade_height.map_err(|err| error::Arithmetic {
-            msg: "failed to compute fade height".to_string(),
+        let segments = segments.map_err(|err| error::Arithmetic {
+            msg: "failed to compute stretched segments".into(),
             source: err,
         })?;
-        debug!(&fade_height);
-
-        let safe_patch_height = (|| {
-            let total_fade_height = CheckedMul::checked_mul(fade_height, 2)?;
-            let height = CheckedSub::checked_sub(overlay_patch_size.height, total_fade_height)?;
-            Ok::<_, arithmetic::Error>(height)
-        })();
-        let safe_patch_height = safe_patch_height.map_err(|err| error::Arithmetic {
-            msg: "failed to compute safe patch height".to_string(),
-            source: err,
-        })?;
-        debug!(&safe_patch_height);
-
-        let num_patches = (|| {
-            let patches = f64::from(fill_height)
-                .checked_div(f64::from(safe_patch_height))?
-                .ceil()
-                .cast::<u32>()?;
-            Ok::<_, arithmetic::Error>(patches)
-        })();
-        let num_patches = num_patches.map_err(|err| error::Arithmetic {
-            msg: "failed to compute number of patches".to_string(),
-            source: err,
-        })?;
-        debug!(&num_patches);
-
-        let new_safe_patch_height = (|| {
-            let height = f64::from(fill_height)
-                .checked_div(f64::from(num_patches))?
-                .ceil()
-                .cast::<u32>()?;
-            Ok::<_, arithmetic::Error>(height)
-        })();
-        let new_safe_patch_height = new_safe_patch_height.map_err(|err| error::Arithmetic {
-            msg: "failed to compute new safe patch height".to_string(),
-            source: err,
-        })?;
-        debug!(&new_safe_patch_height);
+        debug!(&segments);
+
+        // the masks are stitched with the same seams as the border
+        let size = border.size();
+        let mut masks: Vec<image::GrayImage> = border
+            .masks
+            .iter()
+            .flatten()
+            .map(|mask| mask.to_image(size))
+            .collect();
+        let stitched = stitch::stitch_segments(&border.inner.inner, &segments, &mut masks)?;
+        let mut new_border = Self::unchecked(Image::from_image(&stitched.into()), border.options);
+        let mut masks = masks.iter().map(Mask::from_image);
+        let masks = border
+            .masks
+            .iter()
+            .map(|mask| mask.as_ref().and_then(|_| masks.next()).transpose())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| error::Arithmetic {
+                msg: "failed to compute stretched window masks".into(),
+                source: err,
+            })?;
 
-        let patch_height = (|| {
-            let total_fade_height = CheckedMul::checked_mul(fade_height, 2)?;
-            let height = CheckedAdd::checked_add(new_safe_patch_height, total_fade_height)?;
-            Ok::<_, arithmetic::Error>(height)
+        let map_rect = |rect: Rect| {
+            Ok::<_, arithmetic::Error>(Rect::from_points(
+                (rect.left, stitch::map_row(&segments, rect.top)?),
+                (rect.right, stitch::map_row(&segments, rect.bottom)?),
+            ))
+        };
+        let windows = (|| {
+            let windows = border
+                .transparent_components
+                .iter()
+                .copied()
+                .map(map_rect)
+                .collect::<Result<Vec<_>, _>>()?;
+            let geometry = border
+                .geometry
+                .as_ref()
+                .map(|geometry| geometry.map(map_rect))
+                .transpose()?;
+            Ok::<_, arithmetic::Error>((windows, geometry))
         })();
-        let patch_height = patch_height.map_err(|err| error::Arithmetic {
-            msg: "failed to compute patch height".to_string(),
+        let (windows, geometry) = windows.map_err(|err| error::Arithmetic {
+            msg: "failed to compute stretched windows".into(),
             source: err,
         })?;
-        debug!(&patch_height);
-
-        let patch_size = Size {
-            width: overlay_patch_size.width,
-            height: patch_height,
-        };
-
-        for i in 0..num_patches {
-            let patch_top_left = (|| {
-                let mut patch_offset_y =
-                    CheckedMul::checked_mul(i64::from(i), i64::from(new_safe_patch_height))?;
-                patch_offset_y = CheckedSub::checked_sub(patch_offset_y, i64::from(fade_height))?;
-
-                let top_left = top_patch_rect.bottom_left().checked_add(Point {
-                    x: 0,
-                    y: patch_offset_y,
-                })?;
-                Ok::<_, arithmetic::Error>(top_left)
-            })();
-
-            let patch_top_left = patch_top_left.map_err(|err| error::Arithmetic {
-                msg: "failed to compute patch top left".to_string(),
-                source: err,
-            })?;
-
-            overlay_and_fade_patch(
-                &mut new_border,
-                border.inner.clone(),
-                patch_top_left,
-                patch_size,
-                fade_height,
-            )?;
+        debug!(&windows);
+
+        // keep the windows in their original order
+        new_border.transparent_components = windows;
+        new_border.masks = masks.into_iter().map(Option::flatten).collect();
+        new_border.detection = border.detection;
+        new_border.geometry = geometry.map(Box::new);
+        if stitch_axis == types::Axis::X {
+            new_border.rotate(&types::Rotation::Rotate270)?;
         }
-
-        let mut new_border = Self::from_image(new_border, border.options)?;
-        new_border.rotate_to_orientation(original_orientation)?;
         Ok(new_border)
     }
 
@@ -383,12 +674,63 @@ impl Border {
         &mut self,
         options: Option<Options>,
     ) -> Result<(), TransparentComponentsError> {
+        if self
+            .geometry
+            .as_ref()
+            .is_some_and(|g| !g.windows.is_empty())
+        {
+            self.use_declared_windows();
+            return Ok(());
+        }
         let options = options.unwrap_or_default();
-        self.transparent_components = imageops::find_transparent_components(
-            &self.inner,
-            options.alpha_threshold,
-            options.transparent_component_threshold,
-        )?;
+        let alpha_threshold = if options.adaptive_alpha_threshold {
+            imageops::otsu_alpha_threshold(&self.inner)
+                .map_err(imageops::TransparentComponentsError::from)?
+                .unwrap_or(options.alpha_threshold)
+        } else {
+            options.alpha_threshold
+        };
+        let mut mask = imageops::transparent_mask(&self.inner, alpha_threshold)?;
+        if options.fill_holes {
+            imageops::fill_holes(&mut mask).map_err(imageops::TransparentComponentsError::from)?;
+        }
+        let (labels, components) =
+            imageops::label_connected_components(&mask, options.transparent_component_threshold)
+                .map_err(imageops::TransparentComponentsError::from)?;
+        // labels keep their component, which is merged with close components
+        let (components, groups) = imageops::merge_close_components(
+            components,
+            i64::from(options.transparent_component_threshold),
+        );
+
+        let size = self.size();
+        let area = f64::from(size.width) * f64::from(size.height);
+        let min_component_pixels = {
+            use arithmetic::Cast;
+            (area * options.min_component_area.max(0.0))
+                .ceil()
+                .cast::<u64>()
+                .map_err(arithmetic::Error::from)
+                .map_err(imageops::TransparentComponentsError::from)?
+        };
+        let count = components.len();
+        let mut windows: Vec<(usize, Rect)> = components
+            .into_iter()
+            .enumerate()
+            .filter(|(_, component)| {
+                component
+                    .pixel_count()
+                    .is_ok_and(|pixels| pixels >= min_component_pixels)
+            })
+            .collect();
+        windows.sort_by_key(|(_, window)| std::cmp::Reverse(window.pixel_count().unwrap_or(0)));
+        self.transparent_components = windows.iter().map(|(_, window)| *window).collect();
+        self.detection = Some(Detection {
+            alpha_threshold,
+            transparent_component_threshold: options.transparent_component_threshold,
+            min_component_pixels,
+            discarded_components: count - self.transparent_components.len(),
+        });
 
         if self.transparent_components.is_empty() {
             return Err(TransparentComponentsError::Invalid(
@@ -398,8 +740,13 @@ impl Border {
                 },
             ));
         }
-        self.transparent_components
-            .sort_by_key(|b| std::cmp::Reverse(b.pixel_count().unwrap_or(0)));
+        let mut owners = vec![None; count];
+        for (window, (component, _)) in windows.iter().enumerate() {
+            owners[*component] = Some(window);
+        }
+        let owners: Vec<Option<usize>> = groups.iter().map(|&group| owners[group]).collect();
+        self.masks = window_masks(&self.inner.inner, &labels, &owners, windows.len())
+            .map_err(imageops::TransparentComponentsError::from)?;
         Ok(())
     }
 
@@ -410,24 +757,196 @@ impl Border {
         resize_mode: types::ResizeMode,
     ) -> Result<(), Error> {
         let crop_mode = super::CropMode::Center;
-        self.inner
-            .resize_and_crop(container, resize_mode, crop_mode)
-            .map_err(img::Error::from)?;
-        self.compute_transparent_components(self.options)?;
+        let size = self.size();
+        // render vector borders at the exact size instead of resampling them
+        let scaled = match &self.source {
+            Some(Source::Procedural(film)) => {
+                let image = film.render(container).map_err(|err| error::Arithmetic {
+                    msg: "failed to render procedural border".into(),
+                    source: err.into(),
+                })?;
+                self.inner.inner = image.inner;
+                container
+            }
+            Some(Source::Svg { svg, rotation }) => {
+                let size = self
+                    .size()
+                    .scale_to(container, resize_mode)
+                    .map_err(|err| error::Arithmetic {
+                        msg: "failed to compute size of SVG border".into(),
+                        source: err.into(),
+                    })?;
+                let size = match rotation {
+                    types::Rotation::Rotate90 | types::Rotation::Rotate270 => Size {
+                        width: size.height,
+                        height: size.width,
+                    },
+                    _ => size,
+                };
+                let mut image = svg.render(size)?;
+                image.rotate(rotation);
+                let scaled = image.size();
+                image
+                    .crop_to_fit(container, crop_mode)
+                    .map_err(img::CropError::from)
+                    .map_err(img::Error::from)?;
+                self.inner.inner = image.inner;
+                self.apply_chroma_key();
+                scaled
+            }
+            None => {
+                self.inner
+                    .resize_and_crop(container, resize_mode, crop_mode)
+                    .map_err(img::Error::from)?;
+                size.scale_to(container, resize_mode)
+                    .map_err(|err| error::Arithmetic {
+                        msg: "failed to compute scaled border size".into(),
+                        source: err.into(),
+                    })?
+            }
+        };
+        let crop = scaled
+            .crop_to_fit(container, crop_mode)
+            .map_err(|err| error::Arithmetic {
+                msg: "failed to compute crop of border".into(),
+                source: err.into(),
+            })?;
+        if let Some(geometry) = &self.geometry {
+            let geometry = geometry
+                .resize_and_crop(size, scaled, &crop)
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to resize border geometry".into(),
+                    source: err,
+                })?;
+            self.geometry = Some(Box::new(geometry));
+        }
+        if let Some(Source::Procedural(film)) = &self.source {
+            // the window of the generated film is known exactly
+            let window = film.window(container).map_err(|err| error::Arithmetic {
+                msg: "failed to compute window of procedural border".into(),
+                source: err.into(),
+            })?;
+            self.transparent_components = vec![Rect::from_points(
+                window.top_left(),
+                (window.right - 1, window.bottom - 1),
+            )];
+            self.masks = vec![None];
+        } else {
+            self.map_transparent_components(
+                |rect| {
+                    let rect = resize_and_crop_rect(rect, size, scaled, &crop, Rect::scale)?;
+                    Ok(Some(rect).filter(|rect| rect.width() > 0 && rect.height() > 0))
+                },
+                |mask| mask.resize_and_crop(size, scaled, &crop),
+            )?;
+        }
+        self.use_declared_windows();
+        Ok(())
+    }
+
+    /// Maps the cached transparent components through `f` and their
+    /// masks through `mask`
+    ///
+    /// Components span their pixels inclusively, so `f` is applied to
+    /// their exclusive bounds.
+    /// Components mapped to `None` are removed.
+    #[inline]
+    fn map_transparent_components(
+        &mut self,
+        f: impl Fn(Rect) -> Result<Option<Rect>, arithmetic::Error>,
+        mask: impl Fn(&Mask) -> Result<Option<Mask>, arithmetic::Error>,
+    ) -> Result<(), Error> {
+        use arithmetic::ops::CheckedAdd;
+        let one = Point { x: 1, y: 1 };
+        let masks = self
+            .masks
+            .iter()
+            .map(Option::as_ref)
+            .chain(std::iter::repeat(None));
+        let windows = self
+            .transparent_components
+            .iter()
+            .zip(masks)
+            .map(|(component, window_mask)| {
+                let exclusive = Rect::from_points(
+                    component.top_left(),
+                    component.bottom_right().checked_add(one)?,
+                );
+                let Some(mapped) = f(exclusive)? else {
+                    return Ok(None);
+                };
+                let mapped =
+                    Rect::from_points(mapped.top_left(), (mapped.right - 1, mapped.bottom - 1));
+                let window_mask = window_mask.map(&mask).transpose()?.flatten();
+                Ok::<_, arithmetic::Error>(Some((mapped, window_mask)))
+            })
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| error::Arithmetic {
+                msg: "failed to transform transparent components".into(),
+                source: err,
+            })?;
+        let (components, masks): (Vec<Rect>, Vec<Option<Mask>>) = windows.into_iter().unzip();
+        if components.is_empty() {
+            return Err(Error::Invalid(InvalidTransparentComponentsError {
+                required: (Ordering::Greater, 0),
+                components,
+            }));
+        }
+        self.transparent_components = components;
+        self.masks = masks;
         Ok(())
     }
 
+    /// Uses the windows declared in the manifest, if any
+    ///
+    /// Declared windows have no masks.
+    #[inline]
+    fn use_declared_windows(&mut self) {
+        if let Some(geometry) = self.geometry.as_ref().filter(|g| !g.windows.is_empty()) {
+            self.transparent_components = geometry.windows.clone();
+            self.masks = vec![None; geometry.windows.len()];
+        }
+    }
+
+    /// Detects the transparent components in the current image again
+    ///
+    /// Rotating and resizing the border transforms the components found
+    /// before, which can drift from the pixels after many resamplings.
+    #[inline]
+    pub fn rescan_transparent_components(&mut self) -> Result<(), TransparentComponentsError> {
+        self.compute_transparent_components(self.options)
+    }
+
     #[inline]
     pub fn rotate(&mut self, angle: &types::Rotation) -> Result<(), Error> {
+        if let Some(Source::Svg { rotation, .. }) = &mut self.source {
+            *rotation = rotation.then(*angle);
+        }
+        let size = self.size();
+        if let Some(geometry) = &self.geometry {
+            let geometry = geometry
+                .rotate(size, *angle)
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to rotate border geometry".into(),
+                    source: err,
+                })?;
+            self.geometry = Some(Box::new(geometry));
+        }
         self.inner.rotate(angle);
-        self.compute_transparent_components(self.options)?;
+        self.map_transparent_components(
+            |rect| Ok(Some(rect.rotate(size, *angle)?)),
+            |mask| Ok(Some(mask.rotate(size, *angle)?)),
+        )?;
+        self.use_declared_windows();
         Ok(())
     }
 
     #[inline]
     pub fn rotate_to_orientation(&mut self, orientation: types::Orientation) -> Result<(), Error> {
-        self.inner.rotate_to_orientation(orientation);
-        self.compute_transparent_components(self.options)?;
+        if self.inner.orientation() != orientation {
+            return self.rotate(&types::Rotation::Rotate90);
+        }
         Ok(())
     }
 
@@ -508,74 +1027,243 @@ impl Border {
     pub fn transparent_components(&self) -> &Vec<Rect> {
         &self.transparent_components
     }
+
+    /// Thresholds the windows were detected with
+    ///
+    /// Returns `None` if the windows were declared in a manifest.
+    #[inline]
+    #[must_use]
+    pub fn detection(&self) -> Option<Detection> {
+        self.detection
+    }
+
+    /// Indices of the transparent components in `order`
+    #[inline]
+    pub fn window_order(&self, order: &WindowOrder) -> Result<Vec<usize>, Error> {
+        let windows = &self.transparent_components;
+        let mut ordered = reading_order(windows);
+        match order {
+            WindowOrder::Reading => {}
+            WindowOrder::Area => {
+                ordered
+                    .sort_by_key(|&idx| std::cmp::Reverse(windows[idx].pixel_count().unwrap_or(0)));
+            }
+            WindowOrder::Indices(indices) => {
+                let mut seen = vec![false; windows.len()];
+                let valid = indices.iter().all(|&idx| {
+                    let unseen = seen.get(idx).is_some_and(|seen| !seen);
+                    if unseen {
+                        seen[idx] = true;
+                    }
+                    unseen
+                });
+                if !valid {
+                    return Err(Error::InvalidWindowOrder {
+                        indices: indices.clone(),
+                        windows: windows.len(),
+                    });
+                }
+                let remaining: Vec<usize> = (0..windows.len()).filter(|&idx| !seen[idx]).collect();
+                ordered = indices
+                    .iter()
+                    .chain(&remaining)
+                    .map(|&idx| ordered[idx])
+                    .collect();
+            }
+        }
+        Ok(ordered)
+    }
+
+    /// Transparent components in `order`
+    #[inline]
+    pub fn ordered_windows(&self, order: &WindowOrder) -> Result<Vec<Rect>, Error> {
+        let order = self.window_order(order)?;
+        Ok(order
+            .into_iter()
+            .map(|idx| self.transparent_components[idx])
+            .collect())
+    }
+
+    /// Masks of the windows in the order of the transparent components
+    ///
+    /// A window's mask covers its transparent pixels and the partially
+    /// transparent pixels around them.
+    /// Windows declared in a manifest have no mask.
+    #[inline]
+    #[must_use]
+    pub fn window_masks(&self) -> &[Option<Mask>] {
+        &self.masks
+    }
+
+    /// Areas declared in the manifest where text can be placed
+    #[inline]
+    #[must_use]
+    pub fn text_areas(&self) -> &[Rect] {
+        self.geometry
+            .as_ref()
+            .map_or(&[], |geometry| geometry.text_areas.as_slice())
+    }
 }
 
-fn compute_patch_rect(
-    size: Size,
-    top_percent: f64,
-    bottom_percent: f64,
-) -> Result<(Rect, Size), arithmetic::Error> {
-    use arithmetic::{ops::CheckedMul, Cast};
+/// Masks of the `count` windows owning the labeled components
+///
+/// `labels` are the component labels of the pixels of `image` and
+/// `owners` the window of each component.
+/// Partially transparent pixels belong to the closest window.
+fn window_masks(
+    image: &image::RgbaImage,
+    labels: &[u32],
+    owners: &[Option<usize>],
+    count: usize,
+) -> Result<Vec<Option<Mask>>, arithmetic::Error> {
+    use arithmetic::Cast;
+    use std::collections::VecDeque;
+
+    let width = image.width().cast::<usize>()?;
+    let height = image.height().cast::<usize>()?;
+    let alphas: Vec<u8> = image.pixels().map(|pixel| pixel.0[3]).collect();
+
+    let mut windows: Vec<Option<usize>> = vec![None; labels.len()];
+    let mut queue = VecDeque::new();
+    for (idx, label) in labels.iter().enumerate() {
+        let Some(component) = label.checked_sub(1) else {
+            continue;
+        };
+        let window = owners.get(component.cast::<usize>()?).copied().flatten();
+        if window.is_some() {
+            windows[idx] = window;
+            queue.push_back(idx);
+        }
+    }
+    while let Some(idx) = queue.pop_front() {
+        let (x, y) = (idx % width, idx / width);
+        for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+            for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+                let neighbor = ny * width + nx;
+                if windows[neighbor].is_none() && alphas[neighbor] < u8::MAX {
+                    windows[neighbor] = windows[idx];
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
 
-    let top_left = Point {
-        x: 0,
-        y: f64::from(size.height)
-            .checked_mul(top_percent)?
-            .cast::<i64>()?,
-    };
-    let bottom_right = Point {
-        x: i64::from(size.width),
-        y: f64::from(size.height)
-            .checked_mul(bottom_percent)?
-            .cast::<i64>()?,
-    };
-    let rect = Rect::from_points(top_left, bottom_right);
-    let size = rect.size()?;
-    Ok((rect, size))
-}
-
-fn overlay_and_fade_patch(
-    image: &mut img::Image,
-    mut patch: img::Image,
-    top_left: Point,
-    patch_size: Size,
-    fade_height: u32,
-) -> Result<(), Error> {
-    patch
-        .crop_to_fit(patch_size, types::CropMode::Center)
-        .map_err(img::CropError::from)
-        .map_err(img::Error::from)?;
-
-    #[cfg(feature = "debug")]
-    patch
-        .clip_alpha(&patch.size().into(), 0, 200)
-        .map_err(img::Error::from)?;
-
-    let axis = types::Axis::Y;
-    // fade out to top
-    let fade_start = Point {
-        x: i64::from(patch_size.width),
-        y: i64::from(fade_height),
+    // inclusive bounds of the pixels of each window
+    let mut extents: Vec<Option<(usize, usize, usize, usize)>> = vec![None; count];
+    for (idx, window) in windows.iter().enumerate() {
+        let Some(window) = window else { continue };
+        let (x, y) = (idx % width, idx / width);
+        // pixels are visited in rows from top to bottom
+        extents[*window] = Some(match extents[*window] {
+            Some((left, top, right, _)) => (left.min(x), top, right.max(x), y),
+            None => (x, y, x, y),
+        });
+    }
+    let mut masks = extents
+        .iter()
+        .map(|extent| {
+            extent
+                .map(|(left, top, right, bottom)| {
+                    Ok::<_, arithmetic::Error>(Mask {
+                        top_left: Point {
+                            x: left.cast::<i64>()?,
+                            y: top.cast::<i64>()?,
+                        },
+                        coverage: image::GrayImage::new(
+                            (right - left + 1).cast::<u32>()?,
+                            (bottom - top + 1).cast::<u32>()?,
+                        ),
+                    })
+                })
+                .transpose()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for (idx, window) in windows.iter().enumerate() {
+        let Some(window) = window else { continue };
+        let (Some(mask), Some((left, top, _, _))) = (&mut masks[*window], extents[*window]) else {
+            continue;
+        };
+        let (x, y) = (idx % width - left, idx / width - top);
+        let coverage = image::Luma([u8::MAX - alphas[idx]]);
+        mask.coverage
+            .put_pixel(x.cast::<u32>()?, y.cast::<u32>()?, coverage);
+    }
+    Ok(masks)
+}
+
+/// Maps `rect` in an image of `size` into the image resized to `scaled`
+/// with `scale` and cropped to `crop`
+fn resize_and_crop_rect(
+    rect: Rect,
+    size: Size,
+    scaled: Size,
+    crop: &Rect,
+    scale: fn(Rect, Size, Size) -> Result<Rect, types::rect::ScaleError>,
+) -> Result<Rect, arithmetic::Error> {
+    use arithmetic::ops::CheckedAdd;
+    let offset = Point {
+        x: -crop.left,
+        y: -crop.top,
     };
-    let fade_end = Point::origin();
-    patch
-        .fade_out(fade_start, fade_end, axis)
-        .map_err(img::Error::from)?;
-
-    // fade out to bottom
-    let fade_start = Point::from(patch_size)
-        .checked_sub(fade_start)
-        .map_err(|err| error::Arithmetic {
-            msg: "failed to compute fade start point for bottom patch".into(),
-            source: err.into(),
-        })?;
+    let bounds = Rect::from(crop.size()?);
+    let rect = scale(rect, size, scaled)?.checked_add(offset)?;
+    Ok(rect.clamp(&bounds))
+}
+
+/// Segments of rows to stretch by `scale` when stitching vertically
+///
+/// Windows overlapping in their rows form a single segment, and so do the
+/// gaps between them.
+/// Rows before the first and after the last window keep their size.
+fn stretch_segments(
+    windows: &[Rect],
+    scale: f64,
+    band: Option<Rect>,
+) -> Result<Vec<stitch::Segment>, arithmetic::Error> {
+    use arithmetic::{ops::CheckedMul, Cast};
 
-    let fade_end = Point::from(patch_size);
-    patch
-        .fade_out(fade_start, fade_end, axis)
-        .map_err(img::Error::from)?;
-    image.overlay(&patch, top_left);
-    Ok(())
+    /// Gaps shorter than this are too short to stitch and keep their size
+    const MIN_GAP: i64 = 8;
+
+    let mut spans: Vec<(i64, i64)> = windows
+        .iter()
+        .map(|window| (window.top.max(0), window.bottom.max(0)))
+        .collect();
+    spans.sort_unstable();
+    let mut groups: Vec<(i64, i64)> = Vec::new();
+    for (top, bottom) in spans {
+        match groups.last_mut() {
+            Some(group) if top <= group.1 => group.1 = group.1.max(bottom),
+            _ => groups.push((top, bottom)),
+        }
+    }
+    let gaps: Vec<(i64, i64)> = groups
+        .windows(2)
+        .map(|pair| (pair[0].1, pair[1].0))
+        .filter(|(top, bottom)| bottom - top >= MIN_GAP)
+        .collect();
+
+    let mut spans: Vec<(i64, i64)> = groups.into_iter().chain(gaps).collect();
+    spans.sort_unstable();
+    spans
+        .into_iter()
+        .filter(|(top, bottom)| bottom > top)
+        .map(|(top, bottom)| {
+            let length = (bottom - top).cast::<f64>()?;
+            let length = length.checked_mul(scale)?.round().cast::<u32>()?;
+            // repeat the declared band, or the rows away from rounded corners
+            let declared = band
+                .map(|band| (band.top.max(top), band.bottom.min(bottom)))
+                .filter(|(start, end)| end > start);
+            let margin = (bottom - top) / 8;
+            let (start, end) = declared.unwrap_or((top + margin, bottom - margin));
+            Ok(stitch::Segment {
+                rows: top.cast::<u32>()?..bottom.cast::<u32>()?,
+                length,
+                band: start.cast::<u32>()?..end.cast::<u32>()?,
+            })
+        })
+        .collect()
 }
 
 #[derive(thiserror::Error, PartialEq, Clone, Debug)]
@@ -634,6 +1322,30 @@ pub enum Error {
     #[error(transparent)]
     Image(#[from] img::Error),
 
+    #[error("failed to load SVG border")]
+    Svg(
+        #[from]
+        #[source]
+        svg::Error,
+    ),
+
+    #[error("failed to stitch border")]
+    Stitch(
+        #[from]
+        #[source]
+        stitch::Error,
+    ),
+
+    #[error("window order {indices:?} must refer to distinct windows of the {windows} windows")]
+    InvalidWindowOrder { indices: Vec<usize>, windows: usize },
+
+    #[error("invalid border manifest")]
+    Manifest(
+        #[from]
+        #[source]
+        manifest::Error,
+    ),
+
     #[error(transparent)]
     Arithmetic(#[from] error::Arithmetic),
 }
@@ -715,6 +1427,460 @@ mod tests {
             "samples/borders/border_1_areas_horizontal.png", 1),
     }
 
+    #[test]
+    fn test_manifest_sidecar() -> Result<()> {
+        let dir = std::env::temp_dir()
+            .join("filmborders")
+            .join("border_sidecar");
+        std::fs::create_dir_all(&dir)?;
+        // opaque border, the window is only declared in the sidecar
+        let image = image::RgbaImage::from_pixel(200, 100, image::Rgba([0, 0, 0, 255]));
+        image.save(dir.join("border.png"))?;
+        std::fs::write(
+            dir.join("border.json"),
+            r#"{
+                "windows": [{ "x": 20, "y": 10, "width": 160, "height": 80 }],
+                "stretch": { "x": { "start": 80, "end": 120 } },
+                "text_areas": [{ "x": 20, "y": 92, "width": 60, "height": 6 }]
+            }"#,
+        )?;
+
+        let mut border = Border::open(dir.join("border.png"), None)?;
+        assert_eq!(
+            border.transparent_components(),
+            &vec![Rect::from_points((20, 10), (180, 90))]
+        );
+
+        border.rotate(&types::Rotation::Rotate90)?;
+        assert_eq!(
+            border.transparent_components(),
+            &vec![Rect::from_points((10, 20), (90, 180))]
+        );
+        border.resize_and_crop(
+            Size {
+                width: 50,
+                height: 100,
+            },
+            types::ResizeMode::Cover,
+        )?;
+        assert_eq!(
+            border.transparent_components(),
+            &vec![Rect::from_points((5, 10), (45, 90))]
+        );
+        assert_eq!(border.text_areas(), &[Rect::from_points((1, 10), (4, 40))]);
+
+        // stitching repeats the declared band and keeps the window declared
+        let content_size = Size {
+            width: 400,
+            height: 100,
+        };
+        let stitched = Border::custom(border, content_size, None)?;
+        assert_eq!(stitched.content_size()?, content_size);
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_multiple_windows() -> Result<()> {
+        let repo: PathBuf = env!("CARGO_MANIFEST_DIR").into();
+        let border_file = repo.join("samples/borders/border_3_areas_vertical.png");
+        let border = Border::from_image(Image::open(&border_file)?, None)?;
+        let windows = border.transparent_components().clone();
+        assert_eq!(windows.len(), 3);
+
+        let window_size = border.content_size()?;
+        let content_size = Size {
+            width: window_size.width,
+            height: window_size.height * 3 / 2,
+        };
+        let stitched = Border::custom(border, content_size, Some(types::Axis::Y))?;
+        assert_eq!(stitched.content_size()?, content_size);
+
+        // all windows are stretched and keep their order
+        let stretched = stitched.transparent_components().clone();
+        assert_eq!(stretched.len(), 3);
+        for (window, stretched) in windows.iter().zip(&stretched) {
+            assert_eq!(window.left, stretched.left);
+            assert_eq!(window.right, stretched.right);
+            let expected = window.height() * 3 / 2;
+            assert!((stretched.height() - expected).abs() <= 2);
+        }
+
+        // the stretched windows are where the stitched border is transparent
+        let mut rescanned = stitched.clone();
+        rescanned.rescan_transparent_components()?;
+        let mut rescanned = rescanned.transparent_components().clone();
+        let mut stretched = stretched;
+        rescanned.sort_by_key(|rect| rect.top);
+        stretched.sort_by_key(|rect| rect.top);
+        assert_eq!(rescanned.len(), stretched.len());
+        for (rescanned, stretched) in rescanned.iter().zip(&stretched) {
+            assert!((rescanned.top - stretched.top).abs() <= 2);
+            assert!((rescanned.bottom - stretched.bottom).abs() <= 2);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_adaptive_detection() -> Result<()> {
+        // window with a semi-transparent vignette, dust and a speck in the window
+        let image = image::RgbaImage::from_fn(200, 150, |x, y| {
+            let inside = |margin: u32| {
+                (40 - margin..160 + margin).contains(&x) && (30 - margin..120 + margin).contains(&y)
+            };
+            let speck = (100..105).contains(&x) && (70..75).contains(&y);
+            let dust = (x, y) == (5, 5) || (x, y) == (190, 140);
+            let alpha = match (inside(0) && !speck, inside(10), dust) {
+                (true, _, _) | (_, _, true) => 0,
+                (false, true, _) => 200,
+                _ => 255,
+            };
+            image::Rgba([0, 0, 0, alpha])
+        });
+        let image = Image::from_image(&image.into());
+
+        let border = Border::from_image(image.clone(), None)?;
+        assert_eq!(border.transparent_components().len(), 3);
+
+        let options = Options {
+            adaptive_alpha_threshold: true,
+            min_component_area: 0.01,
+            fill_holes: true,
+            ..Options::default()
+        };
+        let border = Border::from_image(image, Some(options))?;
+        assert_eq!(
+            border.transparent_components(),
+            &vec![Rect::from_points((40, 30), (159, 119))]
+        );
+        let detection = border.detection().unwrap();
+        assert!(detection.alpha_threshold < 200.0 / 255.0);
+        assert_eq!(detection.min_component_pixels, 300);
+        assert_eq!(detection.discarded_components, 2);
+        Ok(())
+    }
+
+    /// Border with two round windows
+    fn two_round_windows() -> Image {
+        let image = image::RgbaImage::from_fn(190, 190, |x, y| {
+            let distance = |cx: f64, cy: f64| (f64::from(x) - cx).hypot(f64::from(y) - cy);
+            // soft edge of two pixels
+            let distance = distance(50.0, 50.0).min(distance(140.0, 140.0));
+            let alpha = ((distance - 39.0) / 2.0).clamp(0.0, 1.0) * 255.0;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            image::Rgba([0, 0, 0, alpha.round() as u8])
+        });
+        Image::from_image(&image.into())
+    }
+
+    #[test]
+    fn test_window_masks() -> Result<()> {
+        let options = Options {
+            transparent_component_threshold: 1,
+            ..Options::default()
+        };
+        let border = Border::from_image(two_round_windows(), Some(options))?;
+        let windows = border.transparent_components().clone();
+        assert_eq!(windows.len(), 2);
+
+        let masks = border.window_masks();
+        let [Some(first), Some(second)] = masks else {
+            panic!("missing window masks: {masks:?}");
+        };
+        let (first, second) = if windows[0].contains(&Point { x: 50, y: 50 }) {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        let point = |x, y| Point { x, y };
+        assert_eq!(first.coverage_at(point(50, 50)), 255);
+        assert_eq!(second.coverage_at(point(140, 140)), 255);
+        assert_eq!(first.coverage_at(point(140, 140)), 0);
+        // inside the bounding box, but outside of the opening
+        assert_eq!(first.coverage_at(point(15, 15)), 0);
+        assert_eq!(second.coverage_at(point(175, 105)), 0);
+        // soft edges are kept
+        assert!((1..255).contains(&first.coverage_at(point(50, 90))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_masks_follow_resize_and_rotate() -> Result<()> {
+        let options = Options {
+            transparent_component_threshold: 1,
+            ..Options::default()
+        };
+        let mut border = Border::from_image(two_round_windows(), Some(options))?;
+        border.resize_and_crop(
+            Size {
+                width: 380,
+                height: 380,
+            },
+            types::ResizeMode::Fill,
+        )?;
+        border.rotate(&types::Rotation::Rotate90)?;
+        // (x, y) of the border before rotating is at (379 - y, x)
+        let point = |x: i64, y: i64| Point { x: 379 - y, y: x };
+        let masks: Vec<&Mask> = border.window_masks().iter().flatten().collect();
+        let [first, second] = masks[..] else {
+            panic!("missing window masks: {masks:?}");
+        };
+        let (first, second) = if first.coverage_at(point(100, 100)) == 255 {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        assert_eq!(first.coverage_at(point(100, 100)), 255);
+        assert_eq!(second.coverage_at(point(280, 280)), 255);
+        assert_eq!(first.coverage_at(point(280, 280)), 0);
+        assert_eq!(second.coverage_at(point(100, 100)), 0);
+        assert_eq!(first.coverage_at(point(30, 30)), 0);
+
+        // the cached masks match the masks of the transformed border
+        let bounds = |border: &Border| {
+            let mut bounds: Vec<_> = border
+                .window_masks()
+                .iter()
+                .flatten()
+                .map(|mask| (mask.top_left, mask.coverage.dimensions()))
+                .collect();
+            bounds.sort_by_key(|(top_left, _)| (top_left.x, top_left.y));
+            bounds
+        };
+        let cached = bounds(&border);
+        border.rescan_transparent_components()?;
+        let rescanned = bounds(&border);
+        assert_eq!(cached.len(), rescanned.len());
+        for ((cached, cached_size), (rescanned, rescanned_size)) in cached.iter().zip(&rescanned) {
+            assert!(
+                cached.x.abs_diff(rescanned.x) <= 1,
+                "{cached:?} {rescanned:?}"
+            );
+            assert!(
+                cached.y.abs_diff(rescanned.y) <= 1,
+                "{cached:?} {rescanned:?}"
+            );
+            assert!(cached_size.0.abs_diff(rescanned_size.0) <= 2);
+            assert!(cached_size.1.abs_diff(rescanned_size.1) <= 2);
+        }
+        Ok(())
+    }
+
+    fn window_grid() -> Result<Border> {
+        // the top left window is the smallest, the top right one is offset
+        let openings = [
+            (10, 20, 90, 80),
+            (110, 14, 190, 94),
+            (10, 110, 90, 190),
+            (110, 110, 190, 190),
+        ];
+        let image = image::RgbaImage::from_fn(200, 200, |x, y| {
+            let inside = openings.iter().any(|&(left, top, right, bottom)| {
+                (left..right).contains(&x) && (top..bottom).contains(&y)
+            });
+            image::Rgba([0, 0, 0, if inside { 0 } else { 255 }])
+        });
+        let options = Options {
+            transparent_component_threshold: 1,
+            ..Options::default()
+        };
+        Ok(Border::from_image(
+            Image::from_image(&image.into()),
+            Some(options),
+        )?)
+    }
+
+    fn window_centers(border: &Border, order: &WindowOrder) -> Result<Vec<(i64, i64)>> {
+        Ok(border
+            .ordered_windows(order)?
+            .iter()
+            .map(|window| {
+                (
+                    (window.left + window.right) / 2 / 100,
+                    (window.top + window.bottom) / 2 / 100,
+                )
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_window_order() -> Result<()> {
+        let border = window_grid()?;
+        assert_eq!(border.transparent_components().len(), 4);
+        assert_eq!(
+            window_centers(&border, &WindowOrder::Reading)?,
+            vec![(0, 0), (1, 0), (0, 1), (1, 1)]
+        );
+        assert_eq!(
+            window_centers(&border, &WindowOrder::Area)?,
+            vec![(1, 0), (0, 1), (1, 1), (0, 0)]
+        );
+        assert_eq!(
+            window_centers(&border, &WindowOrder::Indices(vec![3, 1]))?,
+            vec![(1, 1), (1, 0), (0, 0), (0, 1)]
+        );
+        assert!(matches!(
+            border.window_order(&WindowOrder::Indices(vec![1, 1])),
+            Err(Error::InvalidWindowOrder { .. })
+        ));
+        assert!(matches!(
+            border.window_order(&WindowOrder::Indices(vec![4])),
+            Err(Error::InvalidWindowOrder { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_window_order() {
+        assert!(matches!("Reading".parse(), Ok(WindowOrder::Reading)));
+        assert!(matches!("area".parse(), Ok(WindowOrder::Area)));
+        assert_eq!(
+            "2, 0,1".parse::<WindowOrder>().ok(),
+            Some(WindowOrder::Indices(vec![2, 0, 1]))
+        );
+        assert!("largest".parse::<WindowOrder>().is_err());
+    }
+
+    #[test]
+    fn test_detection_matches_find_transparent_components() -> Result<()> {
+        let repo: PathBuf = env!("CARGO_MANIFEST_DIR").into();
+        let sorted = |mut components: Vec<Rect>| {
+            components.sort_by_key(|c| (c.top, c.left, c.bottom, c.right));
+            components
+        };
+        let options = Options::default();
+        for sample in [
+            "border1.png",
+            "border_1_areas_horizontal.png",
+            "border_1_areas_vertical.png",
+            "border_3_areas_horizontal.png",
+            "border_3_areas_vertical.png",
+        ] {
+            let img = Image::open(repo.join("samples/borders").join(sample))?;
+            let expected = imageops::find_transparent_components(
+                &img,
+                options.alpha_threshold,
+                options.transparent_component_threshold,
+            )?;
+            let border = Border::from_image(img, Some(options))?;
+            assert_eq!(
+                sorted(border.transparent_components().clone()),
+                sorted(expected),
+                "{sample}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_detection_merges_close_openings() -> Result<()> {
+        // the openings are 4 pixels apart, within the default threshold
+        let image = image::RgbaImage::from_fn(120, 80, |x, y| {
+            let inside = (10..70).contains(&y) && ((10..50).contains(&x) || (54..110).contains(&x));
+            image::Rgba([0, 0, 0, if inside { 0 } else { 255 }])
+        });
+        let img = Image::from_image(&image.into());
+        let options = Options::default();
+        let expected = imageops::find_transparent_components(
+            &img,
+            options.alpha_threshold,
+            options.transparent_component_threshold,
+        )?;
+        let border = Border::from_image(img, Some(options))?;
+        assert_eq!(border.transparent_components(), &expected);
+        assert_eq!(
+            border.transparent_components(),
+            &vec![Rect::from_points((10, 10), (109, 69))]
+        );
+        let masks: Vec<&Mask> = border.window_masks().iter().flatten().collect();
+        assert_eq!(masks.len(), 1);
+        let point = |x, y| Point { x, y };
+        assert_eq!(masks[0].coverage_at(point(20, 40)), 255);
+        assert_eq!(masks[0].coverage_at(point(80, 40)), 255);
+        // the opaque gap between the openings is not part of the window
+        assert_eq!(masks[0].coverage_at(point(52, 40)), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transform_transparent_components() -> Result<()> {
+        let repo: PathBuf = env!("CARGO_MANIFEST_DIR").into();
+        let img = Image::open(repo.join("samples/borders/border_3_areas_vertical.png"))?;
+        let mut border = Border::from_image(img, None)?;
+        border.resize_and_crop(
+            Size {
+                width: 400,
+                height: 400,
+            },
+            types::ResizeMode::Cover,
+        )?;
+        border.rotate(&types::Rotation::Rotate90)?;
+        let transformed = border.transparent_components().clone();
+
+        border.rescan_transparent_components()?;
+        let rescanned = border.transparent_components();
+        assert_eq!(transformed.len(), rescanned.len());
+        for (transformed, rescanned) in transformed.iter().zip(rescanned) {
+            // resampling blurs the edges of the windows by a few pixels
+            for (a, b) in [
+                (transformed.top, rescanned.top),
+                (transformed.left, rescanned.left),
+                (transformed.bottom, rescanned.bottom),
+                (transformed.right, rescanned.right),
+            ] {
+                assert!(
+                    (a - b).abs() <= 2,
+                    "{:?} differs from {:?}",
+                    transformed,
+                    rescanned
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_scaled_transparent_components_match_rescan() -> Result<()> {
+        let image = image::RgbaImage::from_fn(300, 200, |x, y| {
+            let inside = (20..140).contains(&x) && (30..170).contains(&y)
+                || (160..285).contains(&x) && (45..155).contains(&y);
+            image::Rgba([0, 0, 0, if inside { 0 } else { 255 }])
+        });
+        // pixels at least half covered by the windows are transparent
+        let options = Options {
+            alpha_threshold: 0.5,
+            ..Options::default()
+        };
+        let border = Border::from_image(Image::from_image(&image.into()), Some(options))?;
+        for (width, height, exact) in [
+            (600, 400, true),
+            (900, 600, true),
+            (450, 300, false),
+            (200, 133, false),
+        ] {
+            let mut scaled = border.clone();
+            scaled.resize_and_crop(Size { width, height }, types::ResizeMode::Fill)?;
+            let mapped = scaled.transparent_components().clone();
+            scaled.rescan_transparent_components()?;
+            let rescanned = scaled.transparent_components();
+            if exact {
+                assert_eq!(&mapped, rescanned, "{width}x{height}");
+                continue;
+            }
+            // windows are mapped to every pixel they partially cover
+            assert_eq!(mapped.len(), rescanned.len());
+            for (mapped, rescanned) in mapped.iter().zip(rescanned) {
+                assert!(mapped.contains(&rescanned.top_left()), "{width}x{height}");
+                assert!(
+                    mapped.contains(&rescanned.bottom_right()),
+                    "{width}x{height}"
+                );
+                assert!(mapped.width() - rescanned.width() <= 2);
+                assert!(mapped.height() - rescanned.height() <= 2);
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_transparent_areas_3_rotate() -> Result<()> {
         use types::Rotation;
@@ -724,6 +1890,7 @@ mod tests {
         let options = Options {
             transparent_component_threshold: 8,
             alpha_threshold: 0.95,
+            ..Options::default()
         };
         let img = Image::open(&border_file)?;
         let border = Border::from_image(img, Some(options))?;
@@ -0,0 +1,321 @@
+use super::Options;
+use crate::arithmetic;
+use crate::types::{self, Rect, Size};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Sidecar describing the geometry of a border image
+///
+/// The sidecar is a JSON file next to the border image with the same
+/// file stem, e.g. `border.json` for `border.png`.
+/// All coordinates are pixels of the border image at its original size.
+///
+/// ```json
+/// {
+///   "windows": [{ "x": 120, "y": 80, "width": 2400, "height": 1600 }],
+///   "stretch": { "y": { "start": 600, "end": 1100 } },
+///   "text_areas": [{ "x": 120, "y": 1700, "width": 800, "height": 60 }],
+///   "detection": { "alpha_threshold": 0.9 }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Manifest {
+    /// Windows in the order images are drawn into them
+    pub windows: Vec<Region>,
+    /// Bands that are repeated when the border is stitched
+    pub stretch: Stretch,
+    /// Areas where text can be placed without covering the border
+    pub text_areas: Vec<Region>,
+    /// Thresholds for detecting the windows if none are declared
+    pub detection: Option<Options>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Range of columns or rows of a border
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Stretch {
+    /// Columns repeated when stitching horizontally
+    pub x: Option<Span>,
+    /// Rows repeated when stitching vertically
+    pub y: Option<Span>,
+}
+
+/// Path of the sidecar of the border image at `path`
+#[inline]
+#[must_use]
+pub fn sidecar_path(path: impl AsRef<Path>) -> PathBuf {
+    path.as_ref().with_extension("json")
+}
+
+impl Manifest {
+    #[inline]
+    pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    #[inline]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|source| Error::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_data(&data)
+    }
+
+    /// Opens the sidecar of the border image at `path`, if there is one
+    #[inline]
+    pub fn open_sidecar(path: impl AsRef<Path>) -> Result<Option<Self>, Error> {
+        let path = sidecar_path(path);
+        if path.is_file() {
+            Self::open(path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Geometry of a border image with `size`
+    #[inline]
+    pub fn geometry(&self, size: Size) -> Result<Geometry, Error> {
+        let bounds = Rect::from(size);
+        let region = |region: &Region| {
+            let rect = Rect::new(
+                (i64::from(region.x), i64::from(region.y)),
+                (region.width, region.height),
+            )?;
+            if rect.clamp(&bounds) != rect || rect.width() == 0 || rect.height() == 0 {
+                return Err(Error::OutOfBounds { rect, size });
+            }
+            Ok(rect)
+        };
+        let band = |span: Option<Span>, length: u32, rect: &dyn Fn(i64, i64) -> Rect| {
+            span.map(|span| {
+                let band = rect(i64::from(span.start), i64::from(span.end));
+                // the stitched border keeps the parts before and after the band
+                if span.start == 0 || span.end >= length || span.start >= span.end {
+                    return Err(Error::Stretch { span, length });
+                }
+                Ok(band)
+            })
+            .transpose()
+        };
+        let width = i64::from(size.width);
+        let height = i64::from(size.height);
+        Ok(Geometry {
+            windows: self.windows.iter().map(region).collect::<Result<_, _>>()?,
+            stretch_x: band(self.stretch.x, size.width, &|start, end| {
+                Rect::from_points((start, 0), (end, height))
+            })?,
+            stretch_y: band(self.stretch.y, size.height, &|start, end| {
+                Rect::from_points((0, start), (width, end))
+            })?,
+            text_areas: self
+                .text_areas
+                .iter()
+                .map(region)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Geometry of a border in pixels of its current image
+///
+/// The geometry follows the border image through rotations and resizes.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Geometry {
+    pub windows: Vec<Rect>,
+    /// Band of columns repeated when stitching horizontally
+    pub stretch_x: Option<Rect>,
+    /// Band of rows repeated when stitching vertically
+    pub stretch_y: Option<Rect>,
+    pub text_areas: Vec<Rect>,
+}
+
+impl Geometry {
+    #[inline]
+    pub(super) fn map(
+        &self,
+        f: impl Fn(Rect) -> Result<Rect, arithmetic::Error>,
+    ) -> Result<Self, arithmetic::Error> {
+        Ok(Self {
+            windows: self
+                .windows
+                .iter()
+                .copied()
+                .map(&f)
+                .collect::<Result<_, _>>()?,
+            stretch_x: self.stretch_x.map(&f).transpose()?,
+            stretch_y: self.stretch_y.map(&f).transpose()?,
+            text_areas: self
+                .text_areas
+                .iter()
+                .copied()
+                .map(&f)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Geometry after rotating the border image with `size`
+    #[inline]
+    pub fn rotate(&self, size: Size, rotation: types::Rotation) -> Result<Self, arithmetic::Error> {
+        let mut rotated = self.map(|rect| Ok(rect.rotate(size, rotation)?))?;
+        if rotation.quarter_turns() % 2 == 1 {
+            std::mem::swap(&mut rotated.stretch_x, &mut rotated.stretch_y);
+        }
+        Ok(rotated)
+    }
+
+    /// Geometry after resizing the border image with `size` to `scaled`
+    /// and cropping it to `crop`
+    #[inline]
+    pub fn resize_and_crop(
+        &self,
+        size: Size,
+        scaled: Size,
+        crop: &Rect,
+    ) -> Result<Self, arithmetic::Error> {
+        self.map(|rect| super::resize_and_crop_rect(rect, size, scaled, crop, Rect::scale_nearest))
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to read border manifest {path:?}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("failed to parse border manifest")]
+    Parse(
+        #[from]
+        #[source]
+        serde_json::Error,
+    ),
+
+    #[error("region {rect:#?} is empty or exceeds the border of size {size:#?}")]
+    OutOfBounds { rect: Rect, size: Size },
+
+    #[error("stretch band {span:?} must lie strictly inside the border of length {length}")]
+    Stretch { span: Span, length: u32 },
+
+    #[error("invalid region")]
+    Region(
+        #[from]
+        #[source]
+        types::rect::Error,
+    ),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Manifest, Span, Stretch};
+    use crate::types::{Rect, Rotation, Size};
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    const MANIFEST: &str = r#"{
+        "windows": [
+            { "x": 10, "y": 10, "width": 80, "height": 30 },
+            { "x": 10, "y": 50, "width": 80, "height": 30 }
+        ],
+        "stretch": { "y": { "start": 40, "end": 50 } },
+        "text_areas": [{ "x": 10, "y": 90, "width": 40, "height": 5 }],
+        "detection": { "alpha_threshold": 0.5 }
+    }"#;
+
+    const SIZE: Size = Size {
+        width: 100,
+        height: 100,
+    };
+
+    #[test]
+    fn test_parse_manifest() -> Result<()> {
+        let manifest = Manifest::from_data(MANIFEST.as_bytes())?;
+        assert_eq!(manifest.windows.len(), 2);
+        assert_eq!(
+            manifest.stretch,
+            Stretch {
+                x: None,
+                y: Some(Span { start: 40, end: 50 })
+            }
+        );
+        let detection = manifest.detection.unwrap();
+        assert_eq!(detection.alpha_threshold, 0.5);
+        assert_eq!(detection.transparent_component_threshold, 8);
+
+        let geometry = manifest.geometry(SIZE)?;
+        assert_eq!(
+            geometry.windows,
+            vec![
+                Rect::from_points((10, 10), (90, 40)),
+                Rect::from_points((10, 50), (90, 80)),
+            ]
+        );
+        assert_eq!(
+            geometry.stretch_y,
+            Some(Rect::from_points((0, 40), (100, 50)))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_manifest() -> Result<()> {
+        let manifest = Manifest::from_data(MANIFEST.as_bytes())?;
+        let small = Size {
+            width: 50,
+            height: 100,
+        };
+        assert!(matches!(
+            manifest.geometry(small),
+            Err(Error::OutOfBounds { .. })
+        ));
+
+        let manifest =
+            Manifest::from_data(br#"{ "stretch": { "x": { "start": 0, "end": 10 } } }"#)?;
+        assert!(matches!(
+            manifest.geometry(SIZE),
+            Err(Error::Stretch { .. })
+        ));
+        assert!(Manifest::from_data(b"{ \"windows\": 1 }").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_transform_geometry() -> Result<()> {
+        let geometry = Manifest::from_data(MANIFEST.as_bytes())?.geometry(SIZE)?;
+
+        let rotated = geometry.rotate(SIZE, Rotation::Rotate90)?;
+        assert_eq!(rotated.windows[0], Rect::from_points((60, 10), (90, 90)));
+        assert_eq!(rotated.stretch_y, None);
+        assert_eq!(
+            rotated.stretch_x,
+            Some(Rect::from_points((50, 0), (60, 100)))
+        );
+
+        let scaled = Size {
+            width: 200,
+            height: 200,
+        };
+        let crop = Rect::from_points((0, 50), (200, 150));
+        let resized = geometry.resize_and_crop(SIZE, scaled, &crop)?;
+        assert_eq!(resized.windows[0], Rect::from_points((20, 0), (180, 30)));
+        assert_eq!(resized.windows[1], Rect::from_points((20, 50), (180, 100)));
+        Ok(())
+    }
+}
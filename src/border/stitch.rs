@@ -0,0 +1,620 @@
+use crate::arithmetic::{
+    self,
+    ops::{CheckedAdd, CheckedDiv, CheckedSub},
+    Cast,
+};
+use image::{GrayImage, ImageBuffer, Pixel, RgbaImage};
+use std::collections::{hash_map::Entry, HashMap};
+use std::ops::Range;
+
+/// Maximum number of rows on either side of a seam that are cut together
+const MAX_OVERLAP: u32 = 8;
+
+/// Number of column bins summarizing a row when searching for seams
+const SIGNATURE_BINS: u32 = 32;
+
+/// Normalized difference above which rows are considered uncorrelated
+const DECORRELATED: f64 = 0.5;
+
+/// Repeating period of the rows in `rows`, e.g. the spacing of sprocket holes
+///
+/// Returns `None` if the rows do not repeat.
+pub fn period(image: &RgbaImage, rows: Range<u32>) -> Result<Option<u32>, arithmetic::Error> {
+    let profile = row_profile(image, rows);
+    let len = profile.len();
+    if len < 8 {
+        return Ok(None);
+    }
+    let count = len.cast::<f64>()?;
+    let mean = profile.iter().sum::<f64>() / count;
+    let variance = profile.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+    if variance < 1e-6 {
+        return Ok(None);
+    }
+
+    // mean squared difference of rows `lag` apart, uncorrelated rows are close to 1
+    let difference = |lag: usize| {
+        let sum: f64 = profile
+            .iter()
+            .zip(&profile[lag..])
+            .map(|(a, b)| (a - b).powi(2))
+            .sum();
+        let mean = sum / (len - lag).cast::<f64>()?;
+        Ok::<_, arithmetic::Error>(mean / (2.0 * variance))
+    };
+    let differences = (0..=len / 2)
+        .map(difference)
+        .collect::<Result<Vec<f64>, _>>()?;
+
+    // neighboring rows are always similar, so only look for a period
+    // once the rows have become different
+    let Some(start) = differences.iter().position(|d| *d > DECORRELATED) else {
+        return Ok(None);
+    };
+    let min = differences
+        .iter()
+        .skip(start)
+        .copied()
+        .min_by(f64::total_cmp)
+        .unwrap_or(f64::INFINITY);
+    if min > DECORRELATED {
+        return Ok(None);
+    }
+    // prefer the fundamental period over its multiples
+    let threshold = min + 0.1;
+    let period = (start + 1..differences.len().saturating_sub(1)).find(|&lag| {
+        let d = differences[lag];
+        d <= threshold && d <= differences[lag - 1] && d <= differences[lag + 1]
+    });
+    let period = period.or_else(|| {
+        differences
+            .iter()
+            .rposition(|d| *d <= threshold)
+            .filter(|lag| *lag > start)
+    });
+    period
+        .map(Cast::cast::<u32>)
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Stitches `image` to `height` by repeating or removing rows in `rows`
+///
+/// Rows are repeated in multiples of their period where possible.
+/// Each seam is placed where the repeated rows differ least from the
+/// rows they replace and follows a minimum-error cut across the columns,
+/// so no blending is required.
+pub fn stitch(image: &RgbaImage, height: u32, rows: Range<u32>) -> Result<RgbaImage, Error> {
+    let plan = Plan::new(image, height, rows)?;
+    Ok(plan.apply(image)?)
+}
+
+/// Seams stitching an image to a new height
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Plan {
+    height: u32,
+    overlap: u32,
+    seams: Vec<Seam>,
+    /// Row each column switches at within the overlap of each seam
+    cuts: Vec<Vec<usize>>,
+}
+
+impl Plan {
+    fn new(image: &RgbaImage, height: u32, rows: Range<u32>) -> Result<Self, Error> {
+        let source_height = image.height();
+        let extra = i64::from(height) - i64::from(source_height);
+        if extra == 0 {
+            return Ok(Self {
+                height,
+                overlap: 0,
+                seams: Vec::new(),
+                cuts: Vec::new(),
+            });
+        }
+        let rows = rows.start..rows.end.min(source_height);
+        let length = rows.end.saturating_sub(rows.start);
+        let overlap = (length / 8).clamp(1, MAX_OVERLAP);
+        let max_jump = length.saturating_sub(2 * overlap);
+        let region = Error::Region {
+            start: rows.start,
+            end: rows.end,
+            height,
+        };
+        if max_jump == 0 {
+            return Err(region);
+        }
+
+        let jumps = plan(extra, period(image, rows.clone())?, max_jump)?;
+        let seams = place_seams(image, &rows, overlap, &jumps)?.ok_or(region)?;
+        let cuts = seams
+            .iter()
+            .map(|seam| cut(image, *seam, overlap))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            height,
+            overlap,
+            seams,
+            cuts,
+        })
+    }
+
+    /// Stitches `image` of the size the seams were placed in
+    fn apply<P: Pixel>(
+        &self,
+        image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    ) -> Result<ImageBuffer<P, Vec<P::Subpixel>>, arithmetic::Error> {
+        let (width, source_height) = image.dimensions();
+        let mut stitched = ImageBuffer::new(width, self.height);
+        let mut cursor = 0;
+        let mut y = 0;
+        let copy_rows = |stitched: &mut ImageBuffer<P, _>, from: u32, to: u32, y: &mut u32| {
+            for source_y in from..to {
+                for x in 0..width {
+                    stitched.put_pixel(x, *y, *image.get_pixel(x, source_y));
+                }
+                *y += 1;
+            }
+        };
+        let mut boundaries = Vec::with_capacity(self.seams.len());
+        for seam in &self.seams {
+            copy_rows(&mut stitched, cursor, seam.row, &mut y);
+            boundaries.push((y, *seam));
+            cursor = seam.resume()?;
+        }
+        copy_rows(&mut stitched, cursor, source_height, &mut y);
+        debug_assert_eq!(y, self.height);
+
+        for ((y, seam), switches) in boundaries.into_iter().zip(&self.cuts) {
+            let first = CheckedSub::checked_sub(seam.row, self.overlap)?;
+            let top = CheckedSub::checked_sub(y, self.overlap)?;
+            for (x, &switch) in switches.iter().enumerate() {
+                let column = x.cast::<u32>()?;
+                for offset in 0..2 * self.overlap {
+                    let mut row = CheckedAdd::checked_add(first, offset)?;
+                    if offset.cast::<usize>()? >= switch {
+                        row = CheckedSub::checked_sub(i64::from(row), seam.jump)?.cast::<u32>()?;
+                    }
+                    let pixel = *image.get_pixel(column, row);
+                    stitched.put_pixel(column, CheckedAdd::checked_add(top, offset)?, pixel);
+                }
+            }
+        }
+        Ok(stitched)
+    }
+}
+
+/// Rows of a border stitched to a new length
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Rows of the segment in the source image
+    pub rows: Range<u32>,
+    /// Number of rows of the segment in the stitched image
+    pub length: u32,
+    /// Rows of the segment that may be repeated or removed
+    pub band: Range<u32>,
+}
+
+impl Segment {
+    #[inline]
+    fn extra(&self) -> i64 {
+        i64::from(self.length) - i64::from(self.rows.end - self.rows.start)
+    }
+}
+
+/// Stitches each of the sorted, non-overlapping `segments` of `image`
+/// to its length and keeps all other rows
+///
+/// `masks` of the size of `image` are stitched along with the same seams.
+pub fn stitch_segments(
+    image: &RgbaImage,
+    segments: &[Segment],
+    masks: &mut [GrayImage],
+) -> Result<RgbaImage, Error> {
+    let mut stitched = image.clone();
+    // stitching from the bottom keeps the rows of the segments above in place
+    for segment in segments.iter().rev().filter(|s| s.extra() != 0) {
+        let height = i64::from(stitched.height()) + segment.extra();
+        let height = u32::try_from(height).map_err(|_| Error::Region {
+            start: segment.band.start,
+            end: segment.band.end,
+            height: 0,
+        })?;
+        let plan = Plan::new(&stitched, height, segment.band.clone())?;
+        stitched = plan.apply(&stitched)?;
+        for mask in masks.iter_mut() {
+            *mask = plan.apply(mask)?;
+        }
+    }
+    Ok(stitched)
+}
+
+/// Row of the stitched image corresponding to `row` of the source image
+///
+/// Rows within a segment are mapped linearly.
+pub fn map_row(segments: &[Segment], row: i64) -> Result<i64, arithmetic::Error> {
+    let mut offset = 0;
+    for segment in segments {
+        let start = i64::from(segment.rows.start);
+        let end = i64::from(segment.rows.end);
+        if row >= end {
+            offset = CheckedAdd::checked_add(offset, segment.extra())?;
+        } else if row > start {
+            let position = CheckedSub::checked_sub(row, start)?.cast::<f64>()?;
+            let scaled = (position * f64::from(segment.length))
+                .checked_div(CheckedSub::checked_sub(end, start)?.cast::<f64>()?)?
+                .round()
+                .cast::<i64>()?;
+            return Ok(start + offset + scaled);
+        }
+    }
+    Ok(CheckedAdd::checked_add(row, offset)?)
+}
+
+/// Jumps back (positive) or ahead (negative) in the source rows
+/// needed to gain `extra` rows
+fn plan(extra: i64, period: Option<u32>, max_jump: u32) -> Result<Vec<i64>, arithmetic::Error> {
+    let max_jump = i64::from(max_jump);
+    let mut jumps = Vec::new();
+    let mut remaining = extra.abs();
+    if let Some(period) = period.map(i64::from).filter(|_| extra > 0) {
+        let max_periods = max_jump / period;
+        if max_periods > 0 {
+            let periods = remaining / period;
+            let full = (periods / max_periods).cast::<usize>()?;
+            jumps.extend(std::iter::repeat_n(max_periods * period, full));
+            let partial = periods % max_periods;
+            if partial > 0 {
+                jumps.push(partial * period);
+            }
+            remaining %= period;
+        }
+    }
+    while remaining > 0 {
+        let jump = remaining.min(max_jump);
+        jumps.push(jump);
+        remaining -= jump;
+    }
+    Ok(jumps
+        .into_iter()
+        .map(|jump| jump * extra.signum())
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Seam {
+    /// First source row replaced at the seam
+    row: u32,
+    /// Number of rows jumped back (positive) or ahead (negative)
+    jump: i64,
+}
+
+impl Seam {
+    /// Source row the stitched image continues with
+    #[inline]
+    fn resume(self) -> Result<u32, arithmetic::Error> {
+        Ok(CheckedSub::checked_sub(i64::from(self.row), self.jump)?.cast::<u32>()?)
+    }
+}
+
+/// Places a seam for each jump where the rows on both sides match best
+///
+/// Returns `None` if the rows are too short for the jumps.
+fn place_seams(
+    image: &RgbaImage,
+    rows: &Range<u32>,
+    overlap: u32,
+    jumps: &[i64],
+) -> Result<Option<Vec<Seam>>, arithmetic::Error> {
+    let signatures = Signatures::new(image, rows.clone())?;
+    let start = i64::from(rows.start);
+    let end = i64::from(rows.end);
+    let overlap = i64::from(overlap);
+    let index =
+        |y: i64| Ok::<_, arithmetic::Error>(CheckedSub::checked_sub(y, start)?.cast::<usize>()?);
+
+    let mut costs: HashMap<i64, Vec<f64>> = HashMap::new();
+    let mut seams = Vec::with_capacity(jumps.len());
+    let mut cursor: Option<i64> = None;
+    for &jump in jumps {
+        // rows on both sides of the seam must be within the stretchable rows
+        let mut lowest = start + overlap + jump.max(0);
+        let highest = end - overlap + jump.min(0);
+        // segments between seams must not overlap
+        if let Some(cursor) = cursor {
+            lowest = lowest.max(cursor + 2 * overlap);
+        }
+        if lowest > highest {
+            return Ok(None);
+        }
+        // cumulative difference between each row and the row it is replaced with
+        let cost = match costs.entry(jump) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut cumulative = vec![0.0; rows.len() + 1];
+                for y in start..end {
+                    let other = y - jump;
+                    let difference = if (start..end).contains(&other) {
+                        signatures.difference(y, other)?
+                    } else {
+                        0.0
+                    };
+                    let i = index(y)?;
+                    cumulative[i + 1] = cumulative[i] + difference;
+                }
+                entry.insert(cumulative)
+            }
+        };
+        let candidates = (lowest..=highest)
+            .map(|row| {
+                let cost = cost[index(row + overlap)?] - cost[index(row - overlap)?];
+                Ok::<_, arithmetic::Error>((row, cost))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let Some((row, _)) = candidates
+            .into_iter()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            return Ok(None);
+        };
+        let seam = Seam {
+            row: row.cast::<u32>()?,
+            jump,
+        };
+        cursor = Some(i64::from(seam.resume()?));
+        seams.push(seam);
+    }
+    Ok(Some(seams))
+}
+
+/// Index and value of the first smallest of `values`, starting at `first`
+#[inline]
+fn argmin(values: impl Iterator<Item = (usize, f64)>, first: (usize, f64)) -> (usize, f64) {
+    values.fold(
+        first,
+        |best, value| if value.1 < best.1 { value } else { best },
+    )
+}
+
+/// Minimum-error cut replacing the hard cut at `seam`
+///
+/// Within `overlap` rows of the seam, every column switches from the rows
+/// before the seam to the rows after it where they differ least, and the
+/// switching row changes by at most one between neighboring columns.
+/// Returns the switching row of each column.
+fn cut(image: &RgbaImage, seam: Seam, overlap: u32) -> Result<Vec<usize>, arithmetic::Error> {
+    let width = image.width().cast::<usize>()?;
+    let rows = (2 * overlap).cast::<usize>()?;
+    if rows < 2 || width == 0 {
+        // keep the hard cut at the seam
+        return Ok(vec![rows / 2; width]);
+    }
+    let first = CheckedSub::checked_sub(seam.row, overlap)?;
+    let before = |offset: usize| {
+        Ok::<_, arithmetic::Error>(CheckedAdd::checked_add(first, offset.cast::<u32>()?)?)
+    };
+    let after = |offset: usize| {
+        let row = CheckedSub::checked_sub(i64::from(before(offset)?), seam.jump)?;
+        Ok::<_, arithmetic::Error>(row.cast::<u32>()?)
+    };
+    let error = |x: usize, offset: usize| {
+        let x = x.cast::<u32>()?;
+        let a = image.get_pixel(x, before(offset)?);
+        let b = image.get_pixel(x, after(offset)?);
+        Ok::<_, arithmetic::Error>(
+            a.0.iter()
+                .zip(b.0.iter())
+                .map(|(a, b)| (f64::from(*a) - f64::from(*b)).powi(2))
+                .sum::<f64>(),
+        )
+    };
+
+    // switching at `k` keeps rows before `k` and replaces rows from `k` on
+    let switches = 1..rows;
+    let count = switches.len();
+    let mut total = vec![0.0_f64; width * count];
+    let mut previous = vec![0; width * count];
+    for x in 0..width {
+        for (i, k) in switches.clone().enumerate() {
+            let cost = error(x, k - 1)? + error(x, k)?;
+            let best = if x == 0 {
+                0.0
+            } else {
+                let row = &total[(x - 1) * count..x * count];
+                let first = i.saturating_sub(1);
+                let neighbors = (first + 1..(i + 2).min(count)).map(|j| (j, row[j]));
+                let (j, best) = argmin(neighbors, (first, row[first]));
+                previous[x * count + i] = j;
+                best
+            };
+            total[x * count + i] = cost + best;
+        }
+    }
+
+    let last = &total[(width - 1) * count..];
+    let (mut i, _) = argmin(last.iter().copied().enumerate().skip(1), (0, last[0]));
+    let mut cuts = vec![0; width];
+    for x in (0..width).rev() {
+        cuts[x] = i + switches.start;
+        i = previous[x * count + i];
+    }
+    Ok(cuts)
+}
+
+/// Mean color and alpha of each row in `rows`
+fn row_profile(image: &RgbaImage, rows: Range<u32>) -> Vec<f64> {
+    let width = f64::from(image.width().max(1));
+    rows.map(|y| {
+        let sum: f64 = (0..image.width())
+            .map(|x| {
+                let channels = image.get_pixel(x, y).0;
+                channels.iter().copied().map(f64::from).sum::<f64>() / (4.0 * 255.0)
+            })
+            .sum();
+        sum / width
+    })
+    .collect()
+}
+
+/// Downsampled rows used to compare rows cheaply
+struct Signatures {
+    start: u32,
+    bins: usize,
+    values: Vec<f64>,
+}
+
+impl Signatures {
+    fn new(image: &RgbaImage, rows: Range<u32>) -> Result<Self, arithmetic::Error> {
+        let width = image.width();
+        let bins = SIGNATURE_BINS.min(width).max(1);
+        let values_per_row = bins.cast::<usize>()? * 4;
+        let mut values = Vec::with_capacity(rows.len() * values_per_row);
+        for y in rows.clone() {
+            for bin in 0..bins {
+                let columns = (bin * width / bins)..((bin + 1) * width / bins);
+                let count = f64::from(columns.len().max(1).cast::<u32>()?);
+                let mut sum = [0.0; 4];
+                for x in columns {
+                    let pixel = image.get_pixel(x, y).0;
+                    for (sum, channel) in sum.iter_mut().zip(pixel) {
+                        *sum += f64::from(channel);
+                    }
+                }
+                values.extend(sum.iter().map(|sum| sum / count));
+            }
+        }
+        Ok(Self {
+            start: rows.start,
+            bins: values_per_row,
+            values,
+        })
+    }
+
+    #[inline]
+    fn row(&self, y: i64) -> Result<&[f64], arithmetic::Error> {
+        let i = CheckedSub::checked_sub(y, i64::from(self.start))?.cast::<usize>()? * self.bins;
+        Ok(&self.values[i..i + self.bins])
+    }
+
+    /// Squared difference of rows `a` and `b`
+    #[inline]
+    fn difference(&self, a: i64, b: i64) -> Result<f64, arithmetic::Error> {
+        Ok(self
+            .row(a)?
+            .iter()
+            .zip(self.row(b)?)
+            .map(|(a, b)| (a - b).powi(2))
+            .sum())
+    }
+}
+
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+pub enum Error {
+    #[error("rows {start}..{end} are too short to stitch the border to a height of {height}")]
+    Region { start: u32, end: u32, height: u32 },
+
+    #[error("failed to compute stitched rows")]
+    Arithmetic(#[from] arithmetic::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{map_row, period, stitch, stitch_segments, Error, Segment};
+    use image::{Rgba, RgbaImage};
+    use pretty_assertions::assert_eq;
+
+    const PERIOD: u32 = 24;
+
+    /// Film strip with sprocket holes in the rebates and a window in between
+    fn film_strip(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            let rebate = x < 12 || x >= width - 12;
+            let hole = (4..8).contains(&(x % (width - 12))) && y % PERIOD < 10;
+            let window = !rebate && (8..height - 8).contains(&y);
+            match (rebate, hole, window) {
+                (true, true, _) | (false, _, true) => Rgba([0, 0, 0, 0]),
+                // slight gradient so no two rows of the frame are identical
+                _ => Rgba([
+                    20,
+                    20,
+                    u8::try_from(y * 255 / height).unwrap_or(u8::MAX),
+                    255,
+                ]),
+            }
+        })
+    }
+
+    /// Rows at which sprocket holes start in the left rebate
+    fn hole_starts(image: &RgbaImage) -> Vec<u32> {
+        let is_hole = |y: u32| image.get_pixel(5, y).0[3] == 0;
+        (1..image.height())
+            .filter(|&y| is_hole(y) && !is_hole(y - 1))
+            .collect()
+    }
+
+    #[test]
+    fn test_period() -> Result<(), Error> {
+        let image = film_strip(60, 200);
+        assert_eq!(period(&image, 20..180)?, Some(PERIOD));
+        let plain = RgbaImage::from_pixel(60, 200, Rgba([0, 0, 0, 255]));
+        assert_eq!(period(&plain, 20..180)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stitch_keeps_sprocket_spacing() -> Result<(), Error> {
+        let image = film_strip(60, 200);
+        for height in [200, 277, 431, 650] {
+            let stitched = stitch(&image, height, 20..180)?;
+            assert_eq!(stitched.dimensions(), (60, height));
+            // the start and end of the border are kept
+            assert_eq!(stitched.get_pixel(30, 0), image.get_pixel(30, 0));
+            assert_eq!(
+                stitched.get_pixel(5, height - 1),
+                image.get_pixel(5, image.height() - 1)
+            );
+            let holes = hole_starts(&stitched);
+            let spacings: Vec<u32> = holes.windows(2).map(|w| w[1] - w[0]).collect();
+            // all but the one remainder seam keep the sprocket period
+            let irregular = spacings.iter().filter(|s| **s != PERIOD).count();
+            assert!(irregular <= 1, "{height}: {spacings:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_stitch_shrink() -> Result<(), Error> {
+        let image = film_strip(60, 200);
+        let stitched = stitch(&image, 170, 20..180)?;
+        assert_eq!(stitched.dimensions(), (60, 170));
+        assert!(matches!(
+            stitch(&image, 400, 20..22),
+            Err(Error::Region { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stitch_segments() -> Result<(), Error> {
+        let image = film_strip(60, 200);
+        let segments = [
+            Segment {
+                rows: 20..60,
+                length: 80,
+                band: 25..55,
+            },
+            Segment {
+                rows: 100..180,
+                length: 120,
+                band: 110..170,
+            },
+        ];
+        let stitched = stitch_segments(&image, &segments, &mut [])?;
+        assert_eq!(stitched.dimensions(), (60, 280));
+        let rows = [0, 20, 40, 60, 80, 100, 180, 199]
+            .iter()
+            .map(|&row| map_row(&segments, row))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(rows, [0, 20, 60, 100, 120, 140, 260, 279]);
+        Ok(())
+    }
+}
@@ -20,14 +20,49 @@ impl std::str::FromStr for Builtin {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let s = s.to_ascii_lowercase();
-        match s.as_str() {
-            "120mm" | "120mm1" => Ok(Self::Border120_1),
-            _ => Err(error::ParseEnum::Unknown(s.to_string())),
-        }
+        Self::ALL
+            .into_iter()
+            .find(|builtin| builtin.name() == s || builtin.aliases().contains(&s.as_str()))
+            .ok_or(error::ParseEnum::Unknown(s))
     }
 }
 
 impl Builtin {
+    pub const ALL: [Builtin; 1] = [Self::Border120_1];
+
+    #[inline]
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Border120_1 => "120mm",
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Self::Border120_1 => &["120mm1"],
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Border120_1 => "Scanned 120 medium format negative",
+        }
+    }
+
+    /// Film format of the border
+    #[inline]
+    #[must_use]
+    pub fn format(self) -> &'static str {
+        match self {
+            Self::Border120_1 => "120",
+        }
+    }
+
     #[inline]
     pub fn into_border(self) -> Result<border::Border, border::Error> {
         match self {
@@ -0,0 +1,578 @@
+use super::arithmetic::{self, Cast};
+use super::metadata::Metadata;
+use super::types::{Color, Point, Rect, Rotation};
+use super::{error, img};
+use exif::{Tag, Value};
+use rusttype::{point, Font, Scale};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum Position {
+    Bottom,
+    Top,
+    Left,
+    Right,
+}
+
+impl Default for Position {
+    #[inline]
+    fn default() -> Self {
+        Position::Bottom
+    }
+}
+
+impl std::str::FromStr for Position {
+    type Err = error::ParseEnum;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "bottom" => Ok(Position::Bottom),
+            "top" => Ok(Position::Top),
+            "left" => Ok(Position::Left),
+            "right" => Ok(Position::Right),
+            _ => Err(error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+/// Alignment of the caption along its margin
+///
+/// `Start` is the left end of a horizontal and the top end
+/// of a vertical margin.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum Alignment {
+    Start,
+    Center,
+    End,
+}
+
+impl Default for Alignment {
+    #[inline]
+    fn default() -> Self {
+        Alignment::Center
+    }
+}
+
+impl std::str::FromStr for Alignment {
+    type Err = error::ParseEnum;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "start" | "left" | "top" => Ok(Alignment::Start),
+            "center" | "middle" => Ok(Alignment::Center),
+            "end" | "right" | "bottom" => Ok(Alignment::End),
+            _ => Err(error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum FontWeight {
+    Thin,
+    ExtraLight,
+    Light,
+    Regular,
+    Medium,
+    SemiBold,
+    Bold,
+    ExtraBold,
+    Black,
+}
+
+impl Default for FontWeight {
+    #[inline]
+    fn default() -> Self {
+        FontWeight::Regular
+    }
+}
+
+impl std::str::FromStr for FontWeight {
+    type Err = error::ParseEnum;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "thin" | "100" => Ok(FontWeight::Thin),
+            "extralight" | "extra-light" | "200" => Ok(FontWeight::ExtraLight),
+            "light" | "300" => Ok(FontWeight::Light),
+            "regular" | "normal" | "400" => Ok(FontWeight::Regular),
+            "medium" | "500" => Ok(FontWeight::Medium),
+            "semibold" | "semi-bold" | "600" => Ok(FontWeight::SemiBold),
+            "bold" | "700" => Ok(FontWeight::Bold),
+            "extrabold" | "extra-bold" | "800" => Ok(FontWeight::ExtraBold),
+            "black" | "900" => Ok(FontWeight::Black),
+            _ => Err(error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+impl FontWeight {
+    #[inline]
+    pub(crate) fn font_data(self) -> &'static [u8] {
+        match self {
+            FontWeight::Thin => include_bytes!("../fonts/Inter-Thin.ttf"),
+            FontWeight::ExtraLight => include_bytes!("../fonts/Inter-ExtraLight.ttf"),
+            FontWeight::Light => include_bytes!("../fonts/Inter-Light.ttf"),
+            FontWeight::Regular => include_bytes!("../fonts/Inter-Regular.ttf"),
+            FontWeight::Medium => include_bytes!("../fonts/Inter-Medium.ttf"),
+            FontWeight::SemiBold => include_bytes!("../fonts/Inter-SemiBold.ttf"),
+            FontWeight::Bold => include_bytes!("../fonts/Inter-Bold.ttf"),
+            FontWeight::ExtraBold => include_bytes!("../fonts/Inter-ExtraBold.ttf"),
+            FontWeight::Black => include_bytes!("../fonts/Inter-Black.ttf"),
+        }
+    }
+
+    #[inline]
+    fn font(self) -> Result<Font<'static>, Error> {
+        Font::try_from_bytes(self.font_data()).ok_or(Error::Font(self))
+    }
+}
+
+/// Caption drawn into one of the margins
+///
+/// The template may contain the placeholders `{camera}`, `{make}`,
+/// `{model}`, `{lens}`, `{film}`, `{date}`, `{iso}`, `{aperture}`,
+/// `{shutter}` and `{focal_length}`, which are filled from the EXIF
+/// metadata of the primary image. User `values` take precedence and
+/// may define additional placeholders. Use `{{` and `}}` for literal
+/// braces.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(default)]
+pub struct Caption {
+    #[wasm_bindgen(getter_with_clone)]
+    pub template: String,
+    pub position: Position,
+    pub alignment: Alignment,
+    pub weight: FontWeight,
+    /// Text size relative to the thickness of the margin
+    pub size: f32,
+    pub color: Color,
+    #[wasm_bindgen(skip)]
+    pub values: HashMap<String, String>,
+}
+
+impl Default for Caption {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            template: String::new(),
+            position: Position::default(),
+            alignment: Alignment::default(),
+            weight: FontWeight::default(),
+            size: 0.4,
+            color: Color::black(),
+            values: HashMap::new(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Caption {
+    #[wasm_bindgen(constructor)]
+    #[inline]
+    #[must_use]
+    pub fn new(template: String) -> Self {
+        Self {
+            template,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    pub fn set_value(&mut self, key: String, value: String) {
+        self.values.insert(key, value);
+    }
+}
+
+impl Caption {
+    /// Fills the placeholders of the template
+    #[inline]
+    pub fn text(&self, metadata: &Metadata) -> Result<String, Error> {
+        let mut text = String::new();
+        let mut chars = self.template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(Error::UnclosedPlaceholder(name)),
+                        }
+                    }
+                    let name = name.trim();
+                    let value = match self.values.get(name) {
+                        Some(value) => Some(value.clone()),
+                        None => placeholder(name, metadata)?,
+                    };
+                    text.push_str(&value.unwrap_or_default());
+                }
+                c => text.push(c),
+            }
+        }
+        Ok(text.trim().to_string())
+    }
+
+    /// Draws `text` into the margin between `content_rect` and `inner_rect`
+    #[inline]
+    pub fn draw(
+        &self,
+        image: &mut img::Image,
+        text: &str,
+        content_rect: &Rect,
+        inner_rect: &Rect,
+    ) -> Result<(), Error> {
+        let margin = match self.position {
+            Position::Bottom => Rect::from_points(
+                Point {
+                    x: inner_rect.left,
+                    y: inner_rect.bottom,
+                },
+                Point {
+                    x: inner_rect.right,
+                    y: content_rect.bottom,
+                },
+            ),
+            Position::Top => Rect::from_points(
+                Point {
+                    x: inner_rect.left,
+                    y: content_rect.top,
+                },
+                Point {
+                    x: inner_rect.right,
+                    y: inner_rect.top,
+                },
+            ),
+            Position::Left => Rect::from_points(
+                Point {
+                    x: content_rect.left,
+                    y: inner_rect.top,
+                },
+                Point {
+                    x: inner_rect.left,
+                    y: inner_rect.bottom,
+                },
+            ),
+            Position::Right => Rect::from_points(
+                Point {
+                    x: inner_rect.right,
+                    y: inner_rect.top,
+                },
+                Point {
+                    x: content_rect.right,
+                    y: inner_rect.bottom,
+                },
+            ),
+        };
+        let vertical = matches!(self.position, Position::Left | Position::Right);
+        let (length, thickness) = if vertical {
+            (margin.height(), margin.width())
+        } else {
+            (margin.width(), margin.height())
+        };
+        if text.is_empty() || length <= 0 || thickness <= 0 {
+            return Ok(());
+        }
+
+        let font = self.weight.font()?;
+        let mut label = (|| {
+            let thickness = thickness.cast::<f32>()?;
+            let length = length.cast::<f32>()?;
+            let height = (thickness * self.size.clamp(0.0, 1.0)).max(1.0);
+            let width = text_width(&font, text, Scale::uniform(height));
+            // shrink the text until it fits into the margin
+            let height = if width > length {
+                height * length / width
+            } else {
+                height
+            };
+            render_text(&font, text, height, self.color)
+        })()
+        .map_err(|err| error::Arithmetic {
+            msg: format!("failed to compute layout for caption `{}`", text),
+            source: err,
+        })?;
+
+        match self.position {
+            Position::Left => label.rotate(&Rotation::Rotate270),
+            Position::Right => label.rotate(&Rotation::Rotate90),
+            Position::Top | Position::Bottom => {}
+        }
+
+        let label_size = label.size();
+        let (label_length, label_thickness) = if vertical {
+            (i64::from(label_size.height), i64::from(label_size.width))
+        } else {
+            (i64::from(label_size.width), i64::from(label_size.height))
+        };
+        let along = match self.alignment {
+            Alignment::Start => 0,
+            Alignment::Center => (length - label_length) / 2,
+            Alignment::End => length - label_length,
+        }
+        .max(0);
+        let across = ((thickness - label_thickness) / 2).max(0);
+        let top_left = if vertical {
+            Point {
+                x: margin.left + across,
+                y: margin.top + along,
+            }
+        } else {
+            Point {
+                x: margin.left + along,
+                y: margin.top + across,
+            }
+        };
+        image.overlay(&label, top_left);
+        Ok(())
+    }
+}
+
+#[inline]
+pub(crate) fn text_width(font: &Font<'_>, text: &str, scale: Scale) -> f32 {
+    font.layout(text, scale, point(0.0, 0.0))
+        .filter_map(|glyph| {
+            let position = glyph.position().x;
+            let advance = glyph.unpositioned().h_metrics().advance_width;
+            Some(position + advance).filter(|width| width.is_finite())
+        })
+        .fold(0.0, f32::max)
+}
+
+#[inline]
+pub(crate) fn render_text(
+    font: &Font<'_>,
+    text: &str,
+    height: f32,
+    color: Color,
+) -> Result<img::Image, arithmetic::Error> {
+    let scale = Scale::uniform(height);
+    let v_metrics = font.v_metrics(scale);
+    let width = text_width(font, text, scale).ceil();
+    let line_height = (v_metrics.ascent - v_metrics.descent).ceil();
+    let mut label = img::Image::new(
+        width.max(1.0).cast::<u32>()?,
+        line_height.max(1.0).cast::<u32>()?,
+    );
+
+    let image::Rgba([red, green, blue, alpha]) = color.into();
+    let mut result = Ok(());
+    for glyph in font.layout(text, scale, point(0.0, v_metrics.ascent)) {
+        if let Some(bounds) = glyph.pixel_bounding_box() {
+            glyph.draw(|x, y, coverage| {
+                let x = i64::from(bounds.min.x) + i64::from(x);
+                let y = i64::from(bounds.min.y) + i64::from(y);
+                if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+                    if x < label.width() && y < label.height() {
+                        let alpha = (coverage.clamp(0.0, 1.0) * f32::from(alpha)).round();
+                        match alpha.cast::<u8>() {
+                            Ok(alpha) => {
+                                let pixel = label.get_pixel_mut(x, y);
+                                if alpha > pixel[3] {
+                                    *pixel = image::Rgba([red, green, blue, alpha]);
+                                }
+                            }
+                            Err(err) => result = Err(err.into()),
+                        }
+                    }
+                }
+            });
+        }
+    }
+    result.map(|()| label)
+}
+
+#[inline]
+fn placeholder(name: &str, metadata: &Metadata) -> Result<Option<String>, Error> {
+    let ascii = |tag: Tag| -> Option<String> {
+        match &metadata.exif_field(tag)?.value {
+            Value::Ascii(values) => {
+                let value = values.first()?;
+                let value = String::from_utf8_lossy(value);
+                let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+                Some(value.to_string()).filter(|value| !value.is_empty())
+            }
+            _ => None,
+        }
+    };
+    let rational = |tag: Tag| -> Option<f64> {
+        match &metadata.exif_field(tag)?.value {
+            Value::Rational(values) => values
+                .first()
+                .map(exif::Rational::to_f64)
+                .filter(|value| value.is_finite() && *value > 0.0),
+            _ => None,
+        }
+    };
+
+    let value = match name {
+        "make" => ascii(Tag::Make),
+        "model" => ascii(Tag::Model),
+        "camera" => match (ascii(Tag::Make), ascii(Tag::Model)) {
+            (Some(make), Some(model)) => {
+                if model.to_lowercase().starts_with(&make.to_lowercase()) {
+                    Some(model)
+                } else {
+                    Some(format!("{} {}", make, model))
+                }
+            }
+            (make, model) => make.or(model),
+        },
+        "lens" => ascii(Tag::LensModel).or_else(|| ascii(Tag::LensMake)),
+        "film" => None,
+        "date" => ascii(Tag::DateTimeOriginal)
+            .or_else(|| ascii(Tag::DateTime))
+            .and_then(|date| exif::DateTime::from_ascii(date.as_bytes()).ok())
+            .map(|date| format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)),
+        "iso" => metadata
+            .exif_field(Tag::PhotographicSensitivity)
+            .and_then(|field| field.value.get_uint(0))
+            .map(|iso| format!("ISO {}", iso)),
+        "aperture" => rational(Tag::FNumber).map(|f| format!("f/{}", (f * 10.0).round() / 10.0)),
+        "shutter" => rational(Tag::ExposureTime).map(|time| {
+            if time < 1.0 {
+                format!("1/{}s", (1.0 / time).round())
+            } else {
+                format!("{}s", (time * 10.0).round() / 10.0)
+            }
+        }),
+        "focal_length" => rational(Tag::FocalLength).map(|length| format!("{}mm", length.round())),
+        _ => return Err(Error::UnknownPlaceholder(name.to_string())),
+    };
+    Ok(value)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("unknown caption placeholder `{{{0}}}`")]
+    UnknownPlaceholder(String),
+
+    #[error("unclosed caption placeholder `{{{0}`")]
+    UnclosedPlaceholder(String),
+
+    #[error("failed to load Inter font with weight {0:?}")]
+    Font(FontWeight),
+
+    #[error(transparent)]
+    Arithmetic(#[from] error::Arithmetic),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Caption, Error};
+    use crate::metadata::Metadata;
+    use crate::types::{Color, Point, Rect};
+    use crate::Image;
+    use anyhow::Result;
+    use exif::{Field, In, Tag, Value};
+
+    fn metadata() -> Metadata {
+        let ascii = |tag: Tag, value: &str| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        };
+        Metadata::from_exif_fields(vec![
+            ascii(Tag::Make, "Canon"),
+            ascii(Tag::Model, "Canon EOS 5D"),
+            ascii(Tag::LensModel, "EF50mm f/1.4 USM"),
+            ascii(Tag::DateTimeOriginal, "2021:07:14 18:30:00"),
+            Field {
+                tag: Tag::PhotographicSensitivity,
+                ifd_num: In::PRIMARY,
+                value: Value::Short(vec![400]),
+            },
+            Field {
+                tag: Tag::ExposureTime,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![(1, 125).into()]),
+            },
+            Field {
+                tag: Tag::FNumber,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![(28, 10).into()]),
+            },
+        ])
+    }
+
+    macro_rules! template_tests {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<()> {
+                    let (template, want): (&str, &str) = $values;
+                    let mut caption = Caption::new(template.to_string());
+                    caption.set_value("film".into(), "Kodak Portra 400".into());
+                    assert_eq!(caption.text(&metadata())?, want);
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    template_tests! {
+        test_template_camera: ("{camera}", "Canon EOS 5D"),
+        test_template_lens_and_film: ("{lens} | {film}", "EF50mm f/1.4 USM | Kodak Portra 400"),
+        test_template_date: ("{date}", "2021-07-14"),
+        test_template_exposure: ("{iso}  {aperture} {shutter}", "ISO 400  f/2.8 1/125s"),
+        test_template_missing_value: ("{focal_length} {make}", "Canon"),
+        test_template_escaped: ("{{camera}}", "{camera}"),
+    }
+
+    #[test]
+    fn test_template_errors() {
+        let caption = Caption::new("{unknown}".into());
+        assert!(matches!(
+            caption.text(&metadata()),
+            Err(Error::UnknownPlaceholder(name)) if name == "unknown"
+        ));
+        let caption = Caption::new("{camera".into());
+        assert!(matches!(
+            caption.text(&metadata()),
+            Err(Error::UnclosedPlaceholder(_))
+        ));
+    }
+
+    #[test]
+    fn test_draw_into_bottom_margin() -> Result<()> {
+        let mut image = Image::new(200, 120);
+        let content_rect = Rect::from_points(Point { x: 0, y: 0 }, Point { x: 200, y: 120 });
+        let inner_rect = Rect::from_points(Point { x: 20, y: 20 }, Point { x: 180, y: 80 });
+        let caption = Caption {
+            color: Color::rgba(255, 0, 0, 255),
+            ..Caption::new("Kodak Portra".into())
+        };
+        caption.draw(&mut image, "Kodak Portra", &content_rect, &inner_rect)?;
+
+        let drawn: Vec<(u32, u32)> = image
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel[3] > 0)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert!(!drawn.is_empty());
+        assert!(drawn
+            .iter()
+            .all(|&(x, y)| (20..180).contains(&x) && (80..120).contains(&y)));
+        Ok(())
+    }
+}
@@ -1,15 +1,18 @@
 use clap::Parser;
-#[cfg(feature = "builtin")]
-use filmborders::builtin;
 use filmborders::{
     border::{self, Border},
+    caption, config,
     debug::Instant,
     error::Report,
-    img, types, Error, ImageBorders,
+    img, markings, metadata, procedural, registry, types, Error, ImageBorders,
 };
-use std::path::PathBuf;
-#[cfg(feature = "builtin")]
-use std::str::FromStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+use std::thread;
 
 #[derive(Parser, Debug, Clone)]
 #[clap(
@@ -22,12 +25,100 @@ struct Options {
     #[clap(short = 'i', long = "image")]
     images: Vec<PathBuf>,
 
-    #[clap(short = 'o', long = "output")]
+    #[clap(
+        short = 'o',
+        long = "output",
+        help = "output file, or output directory in batch mode (default: ./output)"
+    )]
     output: Option<PathBuf>,
 
-    #[clap(short = 'b', long = "border")]
+    #[clap(
+        long = "config",
+        help = "config file with render profiles (TOML, JSON or YAML)"
+    )]
+    config: Option<PathBuf>,
+
+    #[clap(long = "profile", help = "name of the profile in the config file")]
+    profile: Option<String>,
+
+    #[clap(
+        short = 'b',
+        long = "border",
+        help = "name of a builtin or installed border, or path to a border image"
+    )]
     border: Option<String>,
 
+    #[clap(
+        long = "border-pack",
+        help = "directory or .tar.gz archive with a pack of borders"
+    )]
+    border_packs: Vec<PathBuf>,
+
+    #[clap(
+        long = "border-key-color",
+        help = "make the key color of a border image without transparency transparent"
+    )]
+    border_key_color: Option<types::Color>,
+    #[clap(
+        long = "border-key-tolerance",
+        help = "maximum CIELAB distance of pixels to the border key color"
+    )]
+    border_key_tolerance: Option<f64>,
+    #[clap(
+        long = "border-key-feather",
+        help = "CIELAB distance beyond the tolerance over which pixels fade back in"
+    )]
+    border_key_feather: Option<f64>,
+
+    #[clap(
+        long = "border-adaptive-threshold",
+        help = "choose the alpha threshold of border windows from the alpha histogram",
+        action = clap::ArgAction::SetTrue
+    )]
+    border_adaptive_threshold: bool,
+    #[clap(
+        long = "border-min-window-area",
+        help = "minimum area of a border window as a fraction of the border area"
+    )]
+    border_min_window_area: Option<f64>,
+    #[clap(
+        long = "border-fill-holes",
+        help = "treat opaque specks inside border windows as part of the window",
+        action = clap::ArgAction::SetTrue
+    )]
+    border_fill_holes: bool,
+
+    #[clap(
+        long = "list-borders",
+        help = "list the available borders and exit",
+        action = clap::ArgAction::SetTrue
+    )]
+    list_borders: bool,
+
+    #[clap(
+        long = "film-format",
+        help = "generate the border for a film format (35mm, 120, sheet)"
+    )]
+    film_format: Option<procedural::Format>,
+    #[clap(long = "sprocket-holes", help = "sprocket holes along each side")]
+    sprocket_holes: Option<u32>,
+    #[clap(
+        long = "sprocket-shape",
+        help = "sprocket hole shape (rect, rounded, round)"
+    )]
+    sprocket_shape: Option<procedural::HoleShape>,
+    #[clap(
+        long = "rebate-width",
+        help = "rebate width relative to the film width"
+    )]
+    rebate_width: Option<f32>,
+    #[clap(long = "window-radius", help = "corner radius of the film window")]
+    window_radius: Option<f32>,
+    #[clap(long = "edge-irregularity", help = "wobble of the film window edges")]
+    edge_irregularity: Option<f32>,
+    #[clap(long = "film-seed", help = "seed of the film window edge wobble")]
+    film_seed: Option<u32>,
+
     #[clap(long = "width")]
     output_width: Option<u32>,
 
@@ -42,6 +133,30 @@ struct Options {
 
     #[clap(long = "margin", aliases = &["margin-factor"])]
     margin: Option<f32>,
+    #[clap(long = "margin-top")]
+    margin_top: Option<f32>,
+    #[clap(long = "margin-right")]
+    margin_right: Option<f32>,
+    #[clap(long = "margin-bottom")]
+    margin_bottom: Option<f32>,
+    #[clap(long = "margin-left")]
+    margin_left: Option<f32>,
+
+    #[clap(
+        long = "anchor",
+        help = "position of the content in the output (e.g. center, top, bottom-left or 0.5,0.4)"
+    )]
+    anchor: Option<types::Anchor>,
+
+    #[clap(long = "layout", help = "margin preset (classic, polaroid, gallery)")]
+    layout: Option<filmborders::Layout>,
+
+    #[clap(
+        long = "aspect-ratio",
+        aliases = &["ratio"],
+        help = "exact output aspect ratio (e.g. 4:5, 1.91:1, portrait, square, story, landscape)"
+    )]
+    aspect_ratio: Option<types::AspectRatio>,
 
     #[clap(long = "scale", aliases = &["scale-factor"])]
     scale_factor: Option<f32>,
@@ -61,6 +176,27 @@ struct Options {
     #[clap(long = "fit", help = "fitting mode")]
     mode: Option<types::FitMode>,
 
+    #[clap(
+        long = "window-order",
+        help = "order of the border windows the images are drawn into (area, reading, or indices such as 2,0,1)"
+    )]
+    window_order: Option<border::WindowOrder>,
+
+    #[clap(
+        long = "window-fit",
+        help = "fitting of the images into the border windows (cover, contain, blur)"
+    )]
+    window_fit: Option<filmborders::window::Mode>,
+    #[clap(
+        long = "window-fill-color",
+        help = "fill color around contained images in HEX format"
+    )]
+    window_fill_color: Option<types::Color>,
+    #[clap(long = "window-fill-blur")]
+    window_fill_blur: Option<f32>,
+    #[clap(long = "window-fill-darken")]
+    window_fill_darken: Option<f32>,
+
     #[clap(long = "rotate", aliases = &["rotate-image"])]
     image_rotation: Option<types::Rotation>,
 
@@ -70,15 +206,167 @@ struct Options {
     #[clap(long = "background-color", help = "background color in HEX format")]
     background_color: Option<types::Color>,
 
+    #[clap(
+        long = "background",
+        help = "background mode (color, blur, image, texture)"
+    )]
+    background: Option<filmborders::background::Mode>,
+    #[clap(
+        long = "background-image",
+        help = "image for the image and texture background modes"
+    )]
+    background_image: Option<PathBuf>,
+    #[clap(long = "background-blur")]
+    background_blur: Option<f32>,
+    #[clap(long = "background-darken")]
+    background_darken: Option<f32>,
+    #[clap(long = "background-scale")]
+    background_scale: Option<f32>,
+    #[clap(long = "background-opacity")]
+    background_opacity: Option<f32>,
+
     #[clap(long = "frame-color", help = "frame color in HEX format")]
     frame_color: Option<types::Color>,
 
+    #[clap(
+        long = "frame-radius",
+        help = "corner radius of the frame relative to its shorter side"
+    )]
+    frame_radius: Option<f32>,
+
+    #[clap(
+        long = "content-radius",
+        help = "corner radius of the content relative to its shorter side"
+    )]
+    content_radius: Option<f32>,
+
+    #[clap(
+        long = "frame-fill",
+        help = "frame fill as COLOR, linear:FROM,TO[,ANGLE] or radial:INNER,OUTER"
+    )]
+    frame_fill: Option<filmborders::frame::Fill>,
+
+    #[clap(
+        long = "frame-stroke",
+        help = "additional frame stroke as WIDTH[,INSET],FILL (can be repeated)"
+    )]
+    frame_strokes: Vec<filmborders::frame::Stroke>,
+
+    #[clap(long = "shadow", help = "draw a drop shadow under the frame", action = clap::ArgAction::SetTrue)]
+    shadow: bool,
+    #[clap(long = "shadow-offset-x")]
+    shadow_offset_x: Option<f32>,
+    #[clap(long = "shadow-offset-y")]
+    shadow_offset_y: Option<f32>,
+    #[clap(long = "shadow-blur")]
+    shadow_blur: Option<f32>,
+    #[clap(long = "shadow-spread")]
+    shadow_spread: Option<f32>,
+    #[clap(long = "shadow-color", help = "shadow color in HEX format")]
+    shadow_color: Option<types::Color>,
+    #[clap(long = "shadow-opacity")]
+    shadow_opacity: Option<f32>,
+
+    #[clap(long = "markings", help = "draw film edge markings into the margins", action = clap::ArgAction::SetTrue)]
+    markings: bool,
+    #[clap(long = "stock", help = "film stock name or key in the stock catalog")]
+    stock: Option<String>,
+    #[clap(
+        long = "stock-catalog",
+        help = "catalog of film stocks with default markings (TOML, JSON or YAML)"
+    )]
+    stock_catalog: Option<PathBuf>,
+    #[clap(
+        long = "frame-number",
+        help = "number of the first frame, counting up in batch mode"
+    )]
+    frame_number: Option<u32>,
+    #[clap(long = "dx", help = "DX number of the film stock, e.g. 115-4")]
+    dx: Option<markings::DxNumber>,
+    #[clap(long = "rebate-color", help = "film rebate color in HEX format")]
+    rebate_color: Option<types::Color>,
+    #[clap(long = "markings-color", help = "edge markings color in HEX format")]
+    markings_color: Option<types::Color>,
+
     #[clap(long = "preview", help = "overlay instagram preview visiable area", action = clap::ArgAction::SetTrue)]
     preview: bool,
 
+    #[clap(
+        long = "transform",
+        help = "framing of the n-th image as focus:X,Y[,ZOOM] or region:X,Y,WIDTH,HEIGHT"
+    )]
+    transforms: Vec<types::Transform>,
+
     #[clap(long = "no-border", action = clap::ArgAction::SetTrue)]
     no_border: bool,
 
+    #[clap(
+        long = "ignore-exif-orientation",
+        help = "do not rotate images according to their EXIF orientation",
+        action = clap::ArgAction::SetTrue
+    )]
+    ignore_exif_orientation: bool,
+
+    #[clap(
+        long = "metadata",
+        help = "metadata to keep (preserve, strip-private, strip)"
+    )]
+    metadata: Option<metadata::Policy>,
+
+    #[clap(
+        long = "caption",
+        help = "caption template, e.g. \"{camera} | {lens} | {film}\""
+    )]
+    caption: Option<String>,
+
+    #[clap(
+        long = "caption-position",
+        help = "margin of the caption (bottom, top, left, right)"
+    )]
+    caption_position: Option<caption::Position>,
+
+    #[clap(
+        long = "caption-align",
+        help = "caption alignment (start, center, end)"
+    )]
+    caption_alignment: Option<caption::Alignment>,
+
+    #[clap(long = "caption-weight", help = "Inter font weight of the caption")]
+    caption_weight: Option<caption::FontWeight>,
+
+    #[clap(long = "caption-size", help = "caption size relative to the margin")]
+    caption_size: Option<f32>,
+
+    #[clap(long = "caption-color", help = "caption color in HEX format")]
+    caption_color: Option<types::Color>,
+
+    #[clap(
+        long = "caption-value",
+        help = "caption placeholder value in KEY=VALUE format, e.g. film=\"Portra 400\"",
+        parse(try_from_str = parse_key_value)
+    )]
+    caption_values: Vec<(String, String)>,
+
+    #[clap(
+        long = "batch",
+        help = "render each image in a directory or glob pattern on its own"
+    )]
+    batch: Vec<String>,
+
+    #[clap(
+        short = 'j',
+        long = "jobs",
+        help = "number of images rendered in parallel in batch mode"
+    )]
+    jobs: Option<usize>,
+
+    #[clap(
+        long = "force",
+        help = "render images in batch mode even if the output is up to date",
+        action = clap::ArgAction::SetTrue
+    )]
+    force: bool,
+
     #[clap(long = "quality", help = "output image quality (1-100)")]
     quality: Option<u8>,
 
@@ -86,79 +374,389 @@ struct Options {
     verbosity: u8,
 }
 
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) => Ok((key.trim().to_string(), value.to_string())),
+        None => Err(format!("missing `=` in `{}`", s)),
+    }
+}
+
+/// Options for detecting the windows of a border image, if any are given
+#[inline]
+fn detection_options(options: &Options) -> Option<border::Options> {
+    let chroma_key = options.border_key_color.map(|color| {
+        let default = border::ChromaKey::default();
+        border::ChromaKey {
+            color,
+            tolerance: options.border_key_tolerance.unwrap_or(default.tolerance),
+            feather: options.border_key_feather.unwrap_or(default.feather),
+        }
+    });
+    let customized = chroma_key.is_some()
+        || options.border_adaptive_threshold
+        || options.border_min_window_area.is_some()
+        || options.border_fill_holes;
+    let default = border::Options::default();
+    customized.then(|| border::Options {
+        chroma_key,
+        adaptive_alpha_threshold: options.border_adaptive_threshold,
+        min_component_area: options
+            .border_min_window_area
+            .unwrap_or(default.min_component_area),
+        fill_holes: options.border_fill_holes,
+        ..default
+    })
+}
+
+#[inline]
+fn border_kind(
+    options: &Options,
+    registry: &registry::Registry,
+) -> Result<Option<border::Kind>, border::Error> {
+    if options.no_border {
+        return Ok(None);
+    }
+    if let Some(format) = options.film_format {
+        let mut film = procedural::Film::new(format);
+        film.holes = options.sprocket_holes.unwrap_or(film.holes);
+        film.hole_shape = options.sprocket_shape.unwrap_or(film.hole_shape);
+        film.rebate = options.rebate_width.unwrap_or(film.rebate);
+        film.radius = options.window_radius.unwrap_or(film.radius);
+        film.irregularity = options.edge_irregularity.unwrap_or(film.irregularity);
+        film.seed = options.film_seed.unwrap_or(film.seed);
+        return Ok(Some(border::Kind::Procedural(film)));
+    }
+    let border = match &options.border {
+        Some(name) => match registry.find(name) {
+            Some(entry) => entry.border(),
+            None => Border::open(PathBuf::from(name), detection_options(options))
+                .map(border::Kind::Custom),
+        },
+        #[cfg(feature = "builtin")]
+        None => Ok(border::Kind::default()),
+        #[cfg(not(feature = "builtin"))]
+        None => Err(border::Error::Missing),
+    };
+    border.map(Some)
+}
+
+#[inline]
+fn list_borders(registry: &registry::Registry) {
+    for entry in registry.entries() {
+        let mut details = vec![];
+        details.extend(entry.format.clone());
+        details.extend(entry.pack.as_ref().map(|pack| format!("pack: {}", pack)));
+        details.extend(
+            entry
+                .license
+                .as_ref()
+                .map(|license| format!("license: {}", license)),
+        );
+        print!("{}", entry.name);
+        if !entry.aliases.is_empty() {
+            print!(" ({})", entry.aliases.join(", "));
+        }
+        if !details.is_empty() {
+            print!(" [{}]", details.join(", "));
+        }
+        if !entry.description.is_empty() {
+            print!(" - {}", entry.description);
+        }
+        println!();
+    }
+}
+
+#[inline]
+fn default_options() -> filmborders::Options {
+    filmborders::Options {
+        margin: types::sides::percent::Sides::uniform(0.05),
+        frame_width: types::sides::percent::Sides::uniform(0.01),
+        ..filmborders::Options::default()
+    }
+}
+
+/// Fills settings that are not set on the command line from the profile
+#[inline]
+fn apply_profile(options: &mut Options, profile: &config::Profile) {
+    if options.border.is_none() {
+        options.border = profile.border.clone();
+        options.no_border |= profile.no_border.unwrap_or(false);
+    }
+    if options.output.is_none() {
+        options.output = profile.output.clone();
+    }
+    if options.quality.is_none() {
+        options.quality = profile.quality;
+    }
+    if options.background_image.is_none() {
+        options.background_image = profile.background_image.clone();
+    }
+}
+
+/// Overrides the base options with the command line flags
+#[inline]
+fn border_options(
+    options: &Options,
+    base: filmborders::Options,
+    stock: Option<&markings::Stock>,
+) -> filmborders::Options {
+    let mut border_options = base;
+    if options.output_width.is_some() || options.output_height.is_some() {
+        border_options.output_size = types::BoundedSize {
+            width: options.output_width,
+            height: options.output_height,
+        };
+    }
+    if let Some(width) = options.max_output_width {
+        border_options.output_size_bounds.width = Some(width);
+    }
+    if let Some(height) = options.max_output_height {
+        border_options.output_size_bounds.height = Some(height);
+    }
+    if let Some(mode) = options.mode {
+        border_options.mode = mode;
+    }
+    if let Some(order) = &options.window_order {
+        border_options.window_order = order.clone();
+    }
+    let fit = &mut border_options.window_fit;
+    if let Some(mode) = options.window_fit {
+        fit.mode = mode;
+    }
+    fit.color = options.window_fill_color.unwrap_or(fit.color);
+    fit.blur = options.window_fill_blur.unwrap_or(fit.blur);
+    fit.darken = options.window_fill_darken.unwrap_or(fit.darken);
+    let crop = [
+        options.crop_top,
+        options.crop_right,
+        options.crop_bottom,
+        options.crop_left,
+    ];
+    if crop.iter().any(Option::is_some) {
+        let mut sides = border_options.crop.unwrap_or_default();
+        sides.top = options.crop_top.unwrap_or(sides.top);
+        sides.right = options.crop_right.unwrap_or(sides.right);
+        sides.bottom = options.crop_bottom.unwrap_or(sides.bottom);
+        sides.left = options.crop_left.unwrap_or(sides.left);
+        border_options.crop = Some(sides);
+    }
+    if let Some(scale_factor) = options.scale_factor {
+        border_options.scale_factor = scale_factor;
+    }
+    if let Some(layout) = options.layout {
+        border_options.apply_layout(layout);
+    }
+    if let Some(margin) = options.margin {
+        border_options.margin = types::sides::percent::Sides::uniform(margin);
+    }
+    let margin = &mut border_options.margin;
+    margin.top = options.margin_top.unwrap_or(margin.top);
+    margin.right = options.margin_right.unwrap_or(margin.right);
+    margin.bottom = options.margin_bottom.unwrap_or(margin.bottom);
+    margin.left = options.margin_left.unwrap_or(margin.left);
+    if let Some(anchor) = options.anchor {
+        border_options.anchor = anchor;
+    }
+    if let Some(aspect_ratio) = options.aspect_ratio {
+        border_options.aspect_ratio = Some(aspect_ratio);
+    }
+    if let Some(frame_width) = options.frame_width {
+        border_options.frame_width = types::sides::percent::Sides::uniform(frame_width);
+    }
+    if let Some(rotation) = options.image_rotation {
+        border_options.image_rotation = rotation;
+    }
+    if let Some(rotation) = options.border_rotation {
+        border_options.border_rotation = rotation;
+    }
+    if let Some(color) = options.background_color {
+        border_options.background_color = Some(color);
+    }
+    let background = &mut border_options.background;
+    if let Some(mode) = options.background {
+        background.mode = mode;
+    } else if options.background_image.is_some()
+        && background.mode == filmborders::background::Mode::Color
+    {
+        background.mode = filmborders::background::Mode::Image;
+    }
+    background.blur = options.background_blur.unwrap_or(background.blur);
+    background.darken = options.background_darken.unwrap_or(background.darken);
+    background.scale = options.background_scale.unwrap_or(background.scale);
+    background.opacity = options.background_opacity.unwrap_or(background.opacity);
+    if let Some(color) = options.frame_color {
+        border_options.frame_color = color;
+    }
+    if options.frame_radius.is_some()
+        || options.content_radius.is_some()
+        || options.frame_fill.is_some()
+        || !options.frame_strokes.is_empty()
+    {
+        let style = border_options
+            .frame_style
+            .get_or_insert_with(Default::default);
+        style.radius = options.frame_radius.unwrap_or(style.radius);
+        style.content_radius = options.content_radius.unwrap_or(style.content_radius);
+        if let Some(fill) = options.frame_fill {
+            style.fill = Some(fill);
+        }
+        if !options.frame_strokes.is_empty() {
+            style.strokes = options.frame_strokes.clone();
+        }
+    }
+    if options.shadow {
+        border_options.shadow.get_or_insert_with(Default::default);
+    }
+    if let Some(shadow) = &mut border_options.shadow {
+        shadow.offset_x = options.shadow_offset_x.unwrap_or(shadow.offset_x);
+        shadow.offset_y = options.shadow_offset_y.unwrap_or(shadow.offset_y);
+        shadow.blur = options.shadow_blur.unwrap_or(shadow.blur);
+        shadow.spread = options.shadow_spread.unwrap_or(shadow.spread);
+        shadow.color = options.shadow_color.unwrap_or(shadow.color);
+        shadow.opacity = options.shadow_opacity.unwrap_or(shadow.opacity);
+    }
+    if options.markings || options.stock.is_some() {
+        border_options.markings.get_or_insert_with(Default::default);
+    }
+    if let Some(markings) = &mut border_options.markings {
+        if let Some(stock) = stock {
+            markings.apply_stock(stock);
+        } else if let Some(name) = &options.stock {
+            markings.stock = name.clone();
+        }
+        markings.frame_number = options.frame_number.unwrap_or(markings.frame_number);
+        markings.dx = options.dx.or(markings.dx);
+        markings.rebate_color = options.rebate_color.unwrap_or(markings.rebate_color);
+        markings.color = options.markings_color.unwrap_or(markings.color);
+    }
+    border_options.preview |= options.preview;
+    if let Some(metadata) = options.metadata {
+        border_options.metadata = metadata;
+    }
+
+    if let Some(template) = &options.caption {
+        let caption = border_options.caption.get_or_insert_with(Default::default);
+        caption.template = template.clone();
+    }
+    if let Some(caption) = &mut border_options.caption {
+        if let Some(position) = options.caption_position {
+            caption.position = position;
+        }
+        if let Some(alignment) = options.caption_alignment {
+            caption.alignment = alignment;
+        }
+        if let Some(weight) = options.caption_weight {
+            caption.weight = weight;
+        }
+        if let Some(size) = options.caption_size {
+            caption.size = size;
+        }
+        if let Some(color) = options.caption_color {
+            caption.color = color;
+        }
+        caption
+            .values
+            .extend(options.caption_values.iter().cloned());
+    }
+    border_options
+}
+
 fn main() {
-    let options = Options::parse();
+    let mut options = Options::parse();
     let start = Instant::now();
+    let read_options = img::ReadOptions {
+        apply_exif_orientation: !options.ignore_exif_orientation,
+    };
+
+    let base_options = match &options.config {
+        Some(path) => {
+            let profile = config::Config::open(path).and_then(|config| {
+                let profile = config.profile(options.profile.as_deref())?.clone();
+                let base_options = profile.options(&default_options())?;
+                Ok((profile, base_options))
+            });
+            match profile {
+                Ok((profile, base_options)) => {
+                    apply_profile(&mut options, &profile);
+                    base_options
+                }
+                Err(err) => {
+                    eprintln!("failed to load config: {}", err.report());
+                    return;
+                }
+            }
+        }
+        None => default_options(),
+    };
+
+    let mut registry = registry::Registry::builtin();
+    for pack in &options.border_packs {
+        if let Err(err) = registry.load_pack(pack) {
+            eprintln!("failed to load border pack: {}", err.report());
+            return;
+        }
+    }
+    if options.list_borders {
+        list_borders(&registry);
+        return;
+    }
+
+    let border = match border_kind(&options, &registry) {
+        Ok(border) => border,
+        Err(err) => {
+            eprintln!("failed to read border: {}", err.report());
+            return;
+        }
+    };
+    let stock = match (&options.stock_catalog, &options.stock) {
+        (Some(path), Some(name)) => match markings::Catalog::open(path) {
+            Ok(catalog) => catalog.stock(name).cloned(),
+            Err(err) => {
+                eprintln!("failed to load stock catalog: {}", err.report());
+                return;
+            }
+        },
+        _ => None,
+    };
+    let border_options = border_options(&options, base_options, stock.as_ref());
+    filmborders::debug!(&border_options);
+
+    let background = match &options.background_image {
+        Some(path) => match img::Image::open(path) {
+            Ok(image) => Some(image),
+            Err(err) => {
+                eprintln!("failed to read background image: {}", err.report());
+                return;
+            }
+        },
+        None => None,
+    };
+
+    if !options.batch.is_empty() {
+        run_batch(
+            &options,
+            border.as_ref(),
+            &border_options,
+            background.as_ref(),
+            read_options,
+        );
+        return;
+    }
+
     let images = options
         .images
         .iter()
-        .map(|image_path| img::Image::open(image_path).map_err(Error::from))
+        .map(|image_path| {
+            img::Image::open_with_options(image_path, Some(read_options)).map_err(Error::from)
+        })
         .collect::<Result<Vec<img::Image>, Error>>();
 
     match images.and_then(ImageBorders::new) {
         Ok(mut borders) => {
-            let border = if options.no_border {
-                None
-            } else {
-                #[cfg(feature = "builtin")]
-                let border = match options.border {
-                    None => Ok(border::Kind::default()),
-                    Some(border) => builtin::Builtin::from_str(&border)
-                        .map(border::Kind::Builtin)
-                        .or_else(|_| {
-                            Border::open(PathBuf::from(border), None).map(border::Kind::Custom)
-                        }),
-                };
-
-                #[cfg(not(feature = "builtin"))]
-                let border = options
-                    .border
-                    .ok_or(border::Error::Missing)
-                    .and_then(|border| {
-                        Border::open(PathBuf::from(border), None)
-                            .map(border::Kind::Custom)
-                            .map_err(border::Error::from)
-                    });
-
-                let border = match border {
-                    Ok(border) => border,
-                    Err(err) => {
-                        eprintln!("failed to read border: {}", err.report());
-                        return;
-                    }
-                };
-                Some(border)
-            };
-
-            let border_options = filmborders::Options {
-                output_size: types::BoundedSize {
-                    width: options.output_width,
-                    height: options.output_height,
-                },
-                output_size_bounds: types::BoundedSize {
-                    width: options.max_output_width,
-                    height: options.max_output_height,
-                },
-                mode: options.mode.unwrap_or_default(),
-                crop: Some(types::sides::percent::Sides {
-                    top: options.crop_top.unwrap_or(0.0),
-                    right: options.crop_right.unwrap_or(0.0),
-                    bottom: options.crop_bottom.unwrap_or(0.0),
-                    left: options.crop_left.unwrap_or(0.0),
-                }),
-                scale_factor: options.scale_factor.unwrap_or(1.0),
-                margin: options.margin.unwrap_or(0.05),
-                frame_width: types::sides::percent::Sides::uniform(
-                    options.frame_width.unwrap_or(0.01),
-                ),
-                image_rotation: options.image_rotation.unwrap_or_default(),
-                border_rotation: options.border_rotation.unwrap_or_default(),
-                background_color: options.background_color,
-                frame_color: options.frame_color.unwrap_or_else(types::Color::black),
-
-                preview: options.preview,
-            };
-            filmborders::debug!(&border_options);
+            for (idx, transform) in options.transforms.iter().enumerate() {
+                borders.set_transform(idx, *transform);
+            }
+            borders.set_background_image(background);
             match borders
                 .render(border, &border_options)
                 .map_err(Error::from)
@@ -181,3 +779,333 @@ fn main() {
         Err(err) => eprintln!("{}", err.report()),
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Job {
+    input: PathBuf,
+    output: PathBuf,
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Rendered,
+    UpToDate,
+    Failed(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+enum BatchError {
+    #[error("invalid glob pattern `{pattern}`")]
+    Pattern {
+        pattern: String,
+        source: glob::PatternError,
+    },
+
+    #[error("failed to read {path:?}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("no images found for `{0}`")]
+    NoMatches(String),
+
+    #[error("batch mode renders a single image per job, but {0} transforms were given")]
+    MultipleTransforms(usize),
+}
+
+#[inline]
+fn is_supported_image(path: &Path) -> bool {
+    path.is_file()
+        && matches!(
+            img::ImageFormat::from_path(path),
+            Ok(img::ImageFormat::Jpeg | img::ImageFormat::Png | img::ImageFormat::Tiff)
+        )
+}
+
+/// Directory a glob pattern is relative to
+///
+/// This is the longest leading path of the pattern without
+/// any glob meta characters.
+#[inline]
+fn glob_root(pattern: &str) -> PathBuf {
+    let mut root = PathBuf::new();
+    for component in Path::new(pattern).components() {
+        let component = component.as_os_str().to_string_lossy();
+        if component.contains(['*', '?', '[', ']']) {
+            break;
+        }
+        root.push(component.as_ref());
+    }
+    if Path::new(pattern) == root {
+        // the pattern is a plain file path
+        root.pop();
+    }
+    root
+}
+
+#[inline]
+fn walk_dir(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), BatchError> {
+    let io_error = |source| BatchError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+    let mut entries = fs::read_dir(dir)
+        .map_err(io_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io_error)?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk_dir(&path, files)?;
+        } else if is_supported_image(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Collects the images for directories and glob patterns
+///
+/// Outputs mirror the relative location of each image below its
+/// directory or the root of its glob pattern.
+#[inline]
+fn batch_jobs(inputs: &[String], output_dir: &Path) -> Result<Vec<Job>, BatchError> {
+    let mut jobs: Vec<Job> = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        let (root, files) = if path.is_dir() {
+            let mut files = Vec::new();
+            walk_dir(path, &mut files)?;
+            (path.to_path_buf(), files)
+        } else {
+            let paths = glob::glob(input).map_err(|source| BatchError::Pattern {
+                pattern: input.clone(),
+                source,
+            })?;
+            let files = paths
+                .filter_map(Result::ok)
+                .filter(|path| is_supported_image(path))
+                .collect();
+            (glob_root(input), files)
+        };
+        if files.is_empty() {
+            return Err(BatchError::NoMatches(input.clone()));
+        }
+        for file in files {
+            let relative = file.strip_prefix(&root).unwrap_or(&file);
+            let output = output_dir.join(relative);
+            if jobs.iter().all(|job| job.input != file) {
+                jobs.push(Job {
+                    input: file,
+                    output,
+                });
+            }
+        }
+    }
+    Ok(jobs)
+}
+
+#[inline]
+fn is_up_to_date(job: &Job) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    match (modified(&job.input), modified(&job.output)) {
+        (Some(input), Some(output)) => output >= input,
+        _ => false,
+    }
+}
+
+#[inline]
+fn render_job(
+    job: &Job,
+    border: Option<&border::Kind>,
+    border_options: &filmborders::Options,
+    background: Option<&img::Image>,
+    read_options: img::ReadOptions,
+    transform: Option<types::Transform>,
+    quality: Option<u8>,
+) -> Result<(), Error> {
+    let image = img::Image::open_with_options(&job.input, Some(read_options))?;
+    let mut borders = ImageBorders::single(image);
+    borders.set_transform(0, transform);
+    borders.set_background_image(background.cloned());
+    let result = borders.render(border.cloned(), border_options)?;
+    result
+        .save_with_filename(&job.output, quality)
+        .map_err(img::Error::from)?;
+    Ok(())
+}
+
+#[inline]
+fn run_batch(
+    options: &Options,
+    border: Option<&border::Kind>,
+    border_options: &filmborders::Options,
+    background: Option<&img::Image>,
+    read_options: img::ReadOptions,
+) {
+    let start = Instant::now();
+    if options.transforms.len() > 1 {
+        let err = BatchError::MultipleTransforms(options.transforms.len());
+        eprintln!("{}", err.report());
+        return;
+    }
+    let output_dir = options
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("output"));
+    let jobs = match batch_jobs(&options.batch, &output_dir) {
+        Ok(jobs) => jobs,
+        Err(err) => {
+            eprintln!("{}", err.report());
+            return;
+        }
+    };
+
+    let workers = options
+        .jobs
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1)
+        .clamp(1, jobs.len().max(1));
+    let next = AtomicUsize::new(0);
+    let outcomes: Mutex<Vec<(usize, Outcome)>> = Mutex::new(Vec::with_capacity(jobs.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(job) = jobs.get(idx) else {
+                    break;
+                };
+                let outcome = if !options.force && is_up_to_date(job) {
+                    Outcome::UpToDate
+                } else {
+                    // each image is the next frame on the roll
+                    let mut job_options = border_options.clone();
+                    if let Some(markings) = &mut job_options.markings {
+                        let offset = u32::try_from(idx).unwrap_or(u32::MAX);
+                        *markings = markings.for_frame(offset);
+                    }
+                    match render_job(
+                        job,
+                        border,
+                        &job_options,
+                        background,
+                        read_options,
+                        options.transforms.first().copied(),
+                        options.quality,
+                    ) {
+                        Ok(()) => Outcome::Rendered,
+                        Err(err) => Outcome::Failed(err.report()),
+                    }
+                };
+                match &outcome {
+                    Outcome::Rendered => println!("rendered {}", job.output.display()),
+                    Outcome::UpToDate => println!("skipped {} (up to date)", job.output.display()),
+                    Outcome::Failed(_) => eprintln!("failed {}", job.input.display()),
+                }
+                if let Ok(mut outcomes) = outcomes.lock() {
+                    outcomes.push((idx, outcome));
+                }
+            });
+        }
+    });
+
+    let mut outcomes = outcomes.into_inner().unwrap_or_default();
+    outcomes.sort_by_key(|(idx, _)| *idx);
+    let rendered = outcomes
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, Outcome::Rendered))
+        .count();
+    let up_to_date = outcomes
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, Outcome::UpToDate))
+        .count();
+    let failures: Vec<_> = outcomes
+        .iter()
+        .filter_map(|(idx, outcome)| match outcome {
+            Outcome::Failed(report) => Some((&jobs[*idx], report)),
+            _ => None,
+        })
+        .collect();
+
+    println!(
+        "rendered {} of {} images ({} up to date, {} failed) in {} msec",
+        rendered,
+        jobs.len(),
+        up_to_date,
+        failures.len(),
+        start.elapsed_millis()
+    );
+    if !failures.is_empty() {
+        eprintln!();
+        eprintln!("failures:");
+        for (job, report) in failures {
+            eprintln!("{}:", job.input.display());
+            eprintln!("{}", report);
+        }
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{batch_jobs, glob_root, Job};
+    use anyhow::Result;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_glob_root() {
+        assert_eq!(glob_root("scans/*.jpg"), PathBuf::from("scans"));
+        assert_eq!(glob_root("scans/**/roll?/*.tif"), PathBuf::from("scans"));
+        assert_eq!(glob_root("*.png"), PathBuf::new());
+        assert_eq!(
+            glob_root("scans/roll1/01.jpg"),
+            PathBuf::from("scans/roll1")
+        );
+    }
+
+    #[test]
+    fn test_batch_jobs_mirror_input_tree() -> Result<()> {
+        let root = std::env::temp_dir().join("filmborders").join("batch_input");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("roll1"))?;
+        fs::create_dir_all(root.join("roll2"))?;
+        for file in [
+            "roll1/01.jpg",
+            "roll1/02.png",
+            "roll2/01.tiff",
+            "roll2/notes.txt",
+        ] {
+            fs::write(root.join(file), [])?;
+        }
+
+        let output = Path::new("out");
+        let jobs = batch_jobs(&[root.to_string_lossy().to_string()], output)?;
+        let job = |file: &str| Job {
+            input: root.join(file),
+            output: output.join(file),
+        };
+        assert_eq!(
+            jobs,
+            vec![
+                job("roll1/01.jpg"),
+                job("roll1/02.png"),
+                job("roll2/01.tiff")
+            ]
+        );
+
+        let pattern = root.join("roll1/*.jpg");
+        let jobs = batch_jobs(&[pattern.to_string_lossy().to_string()], output)?;
+        assert_eq!(
+            jobs,
+            vec![Job {
+                input: root.join("roll1/01.jpg"),
+                output: output.join("01.jpg"),
+            }]
+        );
+        Ok(())
+    }
+}
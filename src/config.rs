@@ -0,0 +1,397 @@
+use super::Options;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    #[inline]
+    #[must_use]
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path
+            .as_ref()
+            .extension()?
+            .to_string_lossy()
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// Named render profiles
+///
+/// ```toml
+/// default_profile = "house"
+///
+/// [profiles.house]
+/// border = "120_1"
+/// quality = 90
+///
+/// [profiles.house.options]
+/// margin = 0.08
+/// frame_color = "#1a1a1a"
+/// output_size_bounds = { width = 3000, height = 3000 }
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Profile {
+    /// Builtin border name or path to a border image
+    pub border: Option<String>,
+    pub no_border: Option<bool>,
+    pub quality: Option<u8>,
+    pub output: Option<PathBuf>,
+    /// Image for the image and texture background modes
+    pub background_image: Option<PathBuf>,
+    /// Partial `Options` that override the base options
+    pub options: serde_json::Value,
+}
+
+/// Reads a TOML, JSON or YAML file based on its extension
+#[inline]
+pub(crate) fn open<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, Error> {
+    let path = path.as_ref();
+    let format = Format::from_path(path).ok_or_else(|| Error::UnknownFormat(path.to_path_buf()))?;
+    let content = std::fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse(&content, format)
+}
+
+#[inline]
+pub(crate) fn parse<T: DeserializeOwned>(content: &str, format: Format) -> Result<T, Error> {
+    match format {
+        Format::Toml => Ok(toml::from_str(content)?),
+        Format::Json => Ok(serde_json::from_str(content)?),
+        Format::Yaml => Ok(serde_yaml::from_str(content)?),
+    }
+}
+
+impl Config {
+    /// Reads a config file
+    ///
+    /// Relative paths in the profiles are resolved against the directory
+    /// of the config file.
+    #[inline]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mut config: Self = open(path)?;
+        if let Some(dir) = path.parent() {
+            for profile in config.profiles.values_mut() {
+                profile.resolve_paths(dir);
+            }
+        }
+        Ok(config)
+    }
+
+    #[inline]
+    pub fn parse(content: &str, format: Format) -> Result<Self, Error> {
+        parse(content, format)
+    }
+
+    /// Selects a profile by name
+    ///
+    /// Without a name, the `default_profile` or the only profile is used.
+    #[inline]
+    pub fn profile(&self, name: Option<&str>) -> Result<&Profile, Error> {
+        let name = match name.or(self.default_profile.as_deref()) {
+            Some(name) => name,
+            None => {
+                let mut profiles = self.profiles.values();
+                if let (Some(profile), None) = (profiles.next(), profiles.next()) {
+                    return Ok(profile);
+                }
+                let mut names: Vec<_> = self.profiles.keys().cloned().collect();
+                names.sort();
+                return Err(Error::AmbiguousProfile(names));
+            }
+        };
+        self.profiles
+            .get(name)
+            .ok_or_else(|| Error::MissingProfile(name.to_string()))
+    }
+}
+
+impl Profile {
+    /// Resolves relative paths against `dir`
+    ///
+    /// The border is only resolved if it names an existing file, so the
+    /// names of builtin borders are kept.
+    #[inline]
+    pub fn resolve_paths(&mut self, dir: &Path) {
+        let resolve = |path: &Path| {
+            if path.is_relative() {
+                dir.join(path)
+            } else {
+                path.to_path_buf()
+            }
+        };
+        if let Some(border) = &mut self.border {
+            let path = resolve(Path::new(border.as_str()));
+            if path.is_file() {
+                *border = path.to_string_lossy().into_owned();
+            }
+        }
+        self.output = self.output.as_deref().map(resolve);
+        self.background_image = self.background_image.as_deref().map(resolve);
+    }
+
+    /// Applies the options of the profile on top of `base`
+    #[inline]
+    pub fn options(&self, base: &Options) -> Result<Options, Error> {
+        let mut options = serde_json::to_value(base).map_err(Error::InvalidOptions)?;
+        merge(&mut options, &self.options);
+        serde_json::from_value(options).map_err(Error::InvalidOptions)
+    }
+}
+
+#[inline]
+fn merge(base: &mut serde_json::Value, overrides: &serde_json::Value) {
+    use serde_json::Value;
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(key) {
+                    Some(base_value) => merge(base_value, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (_, Value::Null) => {}
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to read config {path:?}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("unknown config format for {0:?} (expected .toml, .json, .yaml or .yml)")]
+    UnknownFormat(PathBuf),
+
+    #[error("invalid TOML config")]
+    Toml(#[from] toml::de::Error),
+
+    #[error("invalid JSON config")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid YAML config")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("profile `{0}` does not exist")]
+    MissingProfile(String),
+
+    #[error("no profile selected and no default profile (available: {0:?})")]
+    AmbiguousProfile(Vec<String>),
+
+    #[error("invalid options in profile")]
+    InvalidOptions(#[source] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Error, Format};
+    use crate::{types, Options};
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    const TOML: &str = r#"
+default_profile = "house"
+
+[profiles.house]
+border = "120_1"
+quality = 90
+
+[profiles.house.options]
+margin = 0.08
+mode = "Border"
+output_size_bounds = { width = 3000 }
+
+[profiles.plain]
+no_border = true
+"#;
+
+    const JSON: &str = r#"{
+        "default_profile": "house",
+        "profiles": {
+            "house": {
+                "border": "120_1",
+                "quality": 90,
+                "options": {
+                    "margin": 0.08,
+                    "mode": "Border",
+                    "output_size_bounds": { "width": 3000 }
+                }
+            },
+            "plain": { "no_border": true }
+        }
+    }"#;
+
+    const YAML: &str = r#"
+default_profile: house
+profiles:
+  house:
+    border: "120_1"
+    quality: 90
+    options:
+      margin: 0.08
+      mode: Border
+      output_size_bounds:
+        width: 3000
+  plain:
+    no_border: true
+"#;
+
+    macro_rules! format_tests {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<()> {
+                    let (content, format) = $values;
+                    let config = Config::parse(content, format)?;
+                    let base = Options {
+                        margin: types::sides::percent::Sides::uniform(0.05),
+                        scale_factor: 0.9,
+                        output_size_bounds: types::BoundedSize {
+                            width: None,
+                            height: Some(2000),
+                        },
+                        ..Options::default()
+                    };
+
+                    let house = config.profile(None)?;
+                    assert_eq!(house.border.as_deref(), Some("120_1"));
+                    assert_eq!(house.quality, Some(90));
+                    let options = house.options(&base)?;
+                    assert_eq!(options.margin, types::sides::percent::Sides::uniform(0.08));
+                    assert_eq!(options.scale_factor, 0.9);
+                    assert_eq!(options.mode, types::FitMode::Border);
+                    assert_eq!(
+                        options.output_size_bounds,
+                        types::BoundedSize {
+                            width: Some(3000),
+                            height: Some(2000),
+                        }
+                    );
+
+                    let plain = config.profile(Some("plain"))?;
+                    assert_eq!(plain.no_border, Some(true));
+                    assert_eq!(
+                        plain.options(&base)?.margin,
+                        types::sides::percent::Sides::uniform(0.05)
+                    );
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    format_tests! {
+        test_parse_toml_config: (TOML, Format::Toml),
+        test_parse_json_config: (JSON, Format::Json),
+        test_parse_yaml_config: (YAML, Format::Yaml),
+    }
+
+    #[test]
+    fn test_select_profile() -> Result<()> {
+        let mut config = Config::parse(TOML, Format::Toml)?;
+        assert!(matches!(
+            config.profile(Some("unknown")),
+            Err(Error::MissingProfile(name)) if name == "unknown"
+        ));
+        config.default_profile = None;
+        assert!(matches!(
+            config.profile(None),
+            Err(Error::AmbiguousProfile(names)) if names == vec!["house", "plain"]
+        ));
+        config.profiles.remove("house");
+        assert_eq!(config.profile(None)?.no_border, Some(true));
+        config.profiles.clear();
+        assert!(matches!(
+            config.profile(None),
+            Err(Error::AmbiguousProfile(names)) if names.is_empty()
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_profile_options() -> Result<()> {
+        let config = Config::parse(
+            "[profiles.broken.options]\nmargin = \"wide\"\n",
+            Format::Toml,
+        )?;
+        let profile = config.profile(None)?;
+        assert!(matches!(
+            profile.options(&Options::default()),
+            Err(Error::InvalidOptions(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_profile_paths() -> Result<()> {
+        let dir = std::env::temp_dir().join("filmborders").join("config");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("border.png"), [])?;
+        let path = dir.join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+[profiles.house]
+border = "border.png"
+output = "prints"
+background_image = "/textures/paper.png"
+
+[profiles.builtin]
+border = "120_1"
+"#,
+        )?;
+        let config = Config::open(&path)?;
+        let house = config.profile(Some("house"))?;
+        assert_eq!(
+            house.border.as_deref().map(std::path::PathBuf::from),
+            Some(dir.join("border.png"))
+        );
+        assert_eq!(house.output, Some(dir.join("prints")));
+        assert_eq!(
+            house.background_image.as_deref(),
+            Some(std::path::Path::new("/textures/paper.png"))
+        );
+        let builtin = config.profile(Some("builtin"))?;
+        assert_eq!(builtin.border.as_deref(), Some("120_1"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_format_from_path() {
+        assert_eq!(Format::from_path("styles.toml"), Some(Format::Toml));
+        assert_eq!(Format::from_path("styles.JSON"), Some(Format::Json));
+        assert_eq!(Format::from_path("styles.yml"), Some(Format::Yaml));
+        assert_eq!(Format::from_path("styles.ini"), None);
+    }
+}
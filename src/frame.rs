@@ -0,0 +1,538 @@
+use super::arithmetic::{self, Cast};
+use super::img;
+use super::types::{Color, Rect};
+use serde::{Deserialize, Serialize};
+
+/// Paint of the frame or of a stroke
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+pub enum Fill {
+    Solid(Color),
+    /// Gradient along `angle` degrees, where 0 runs from left to right
+    /// and 90 from top to bottom
+    Linear {
+        from: Color,
+        to: Color,
+        angle: f32,
+    },
+    /// Gradient from the center to the corners
+    Radial {
+        inner: Color,
+        outer: Color,
+    },
+}
+
+impl Default for Fill {
+    #[inline]
+    fn default() -> Self {
+        Fill::Solid(Color::black())
+    }
+}
+
+/// Line drawn on top of the frame, following its rounded corners
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+pub struct Stroke {
+    /// Width relative to the shorter side of the frame
+    pub width: f32,
+    /// Distance from the outer edge of the frame relative to its shorter side
+    #[serde(default)]
+    pub inset: f32,
+    pub fill: Fill,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Style {
+    /// Corner radius of the frame relative to its shorter side
+    pub radius: f32,
+    /// Corner radius of the content relative to its shorter side
+    pub content_radius: f32,
+    /// Fill of the frame in place of the frame color
+    pub fill: Option<Fill>,
+    pub strokes: Vec<Stroke>,
+}
+
+/// Pixels of the content corners that are restored after drawing the content
+#[derive(Debug, Clone)]
+pub struct CornerMask {
+    pixels: Vec<(u32, u32, image::Rgba<u8>, f64)>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Area {
+    left: f64,
+    top: f64,
+    right: f64,
+    bottom: f64,
+}
+
+impl TryFrom<&Rect> for Area {
+    type Error = arithmetic::Error;
+
+    #[inline]
+    fn try_from(rect: &Rect) -> Result<Self, Self::Error> {
+        Ok(Self {
+            left: rect.left.cast::<f64>()?,
+            top: rect.top.cast::<f64>()?,
+            right: rect.right.cast::<f64>()?,
+            bottom: rect.bottom.cast::<f64>()?,
+        })
+    }
+}
+
+impl Area {
+    #[inline]
+    fn half_size(&self) -> (f64, f64) {
+        (
+            ((self.right - self.left) / 2.0).max(0.0),
+            ((self.bottom - self.top) / 2.0).max(0.0),
+        )
+    }
+
+    #[inline]
+    fn center(&self) -> (f64, f64) {
+        (
+            (self.left + self.right) / 2.0,
+            (self.top + self.bottom) / 2.0,
+        )
+    }
+
+    #[inline]
+    fn min_dim(&self) -> f64 {
+        let (half_width, half_height) = self.half_size();
+        2.0 * half_width.min(half_height)
+    }
+
+    #[inline]
+    fn inset(&self, inset: f64) -> Self {
+        Self {
+            left: self.left + inset,
+            top: self.top + inset,
+            right: self.right - inset,
+            bottom: self.bottom - inset,
+        }
+    }
+
+    /// Anti-aliased coverage of the pixel at `(x, y)` by the area with rounded corners
+    #[inline]
+    fn coverage(&self, radius: f64, x: f64, y: f64) -> f64 {
+        let (half_width, half_height) = self.half_size();
+        let (center_x, center_y) = self.center();
+        let radius = radius.clamp(0.0, half_width.min(half_height));
+        // signed distance of the pixel center to the rounded rectangle
+        let qx = (x + 0.5 - center_x).abs() - (half_width - radius);
+        let qy = (y + 0.5 - center_y).abs() - (half_height - radius);
+        let outside = qx.max(0.0).hypot(qy.max(0.0));
+        let inside = qx.max(qy).min(0.0);
+        (0.5 - (outside + inside - radius)).clamp(0.0, 1.0)
+    }
+}
+
+impl Fill {
+    #[inline]
+    fn color_at(&self, area: &Area, x: f64, y: f64) -> [f64; 4] {
+        let (center_x, center_y) = area.center();
+        let (half_width, half_height) = area.half_size();
+        let (dx, dy) = (x + 0.5 - center_x, y + 0.5 - center_y);
+        let (from, to, t) = match *self {
+            Fill::Solid(color) => return rgba(color),
+            Fill::Linear { from, to, angle } => {
+                let (sin, cos) = f64::from(angle).to_radians().sin_cos();
+                let extent = half_width * cos.abs() + half_height * sin.abs();
+                let t = if extent > 0.0 {
+                    ((dx * cos + dy * sin) / extent + 1.0) / 2.0
+                } else {
+                    0.0
+                };
+                (from, to, t)
+            }
+            Fill::Radial { inner, outer } => {
+                let extent = half_width.hypot(half_height);
+                let t = if extent > 0.0 {
+                    dx.hypot(dy) / extent
+                } else {
+                    0.0
+                };
+                (inner, outer, t)
+            }
+        };
+        let t = t.clamp(0.0, 1.0);
+        let (from, to) = (rgba(from), rgba(to));
+        [0, 1, 2, 3].map(|c| from[c] + (to[c] - from[c]) * t)
+    }
+}
+
+#[inline]
+fn rgba(color: Color) -> [f64; 4] {
+    let image::Rgba(rgba) = color.into();
+    rgba.map(f64::from)
+}
+
+#[inline]
+fn mix(
+    pixel: &mut image::Rgba<u8>,
+    color: [f64; 4],
+    coverage: f64,
+) -> Result<(), arithmetic::Error> {
+    for (channel, target) in pixel.0.iter_mut().zip(color) {
+        let value = f64::from(*channel);
+        *channel = (value + (target - value) * coverage)
+            .round()
+            .clamp(0.0, 255.0)
+            .cast::<u8>()?;
+    }
+    Ok(())
+}
+
+/// Pixel coordinates of `rect` that lie within `image`
+#[inline]
+fn pixels(
+    image: &img::Image,
+    rect: &Rect,
+) -> Result<impl Iterator<Item = (u32, u32)>, arithmetic::Error> {
+    let clamp = |value: i64, max: u32| value.clamp(0, i64::from(max)).cast::<u32>();
+    let (left, right) = (
+        clamp(rect.left, image.width())?,
+        clamp(rect.right, image.width())?,
+    );
+    let (top, bottom) = (
+        clamp(rect.top, image.height())?,
+        clamp(rect.bottom, image.height())?,
+    );
+    Ok((top..bottom).flat_map(move |y| (left..right).map(move |x| (x, y))))
+}
+
+/// Anti-aliased coverage of the pixel at `(x, y)` by `rect` with rounded corners
+#[inline]
+pub(crate) fn coverage(rect: &Rect, radius: f64, x: f64, y: f64) -> Result<f64, arithmetic::Error> {
+    Ok(Area::try_from(rect)?.coverage(radius, x, y))
+}
+
+impl Style {
+    /// Corner radius of the frame in `rect` in pixels
+    #[inline]
+    pub(crate) fn radius_in(&self, rect: &Rect) -> Result<f64, arithmetic::Error> {
+        Ok(f64::from(self.radius).max(0.0) * Area::try_from(rect)?.min_dim())
+    }
+
+    /// Draws the frame with its strokes into `rect`
+    #[inline]
+    pub fn draw_frame(
+        &self,
+        image: &mut img::Image,
+        frame_color: Color,
+        rect: &Rect,
+    ) -> Result<(), arithmetic::Error> {
+        let area = Area::try_from(rect)?;
+        let min_dim = area.min_dim();
+        let radius = self.radius_in(rect)?;
+        let fill = self.fill.unwrap_or(Fill::Solid(frame_color));
+
+        let strokes: Vec<_> = self
+            .strokes
+            .iter()
+            .map(|stroke| {
+                let inset = f64::from(stroke.inset).max(0.0) * min_dim;
+                let width = f64::from(stroke.width).max(0.0) * min_dim;
+                let outer = area.inset(inset);
+                let inner = area.inset(inset + width);
+                (
+                    stroke.fill,
+                    outer,
+                    inner,
+                    (radius - inset).max(0.0),
+                    (radius - inset - width).max(0.0),
+                )
+            })
+            .collect();
+
+        for (x, y) in pixels(image, rect)? {
+            let (fx, fy) = (f64::from(x), f64::from(y));
+            let coverage = area.coverage(radius, fx, fy);
+            if coverage <= 0.0 {
+                continue;
+            }
+            let pixel = image.get_pixel_mut(x, y);
+            mix(pixel, fill.color_at(&area, fx, fy), coverage)?;
+            for (fill, outer, inner, outer_radius, inner_radius) in &strokes {
+                let coverage =
+                    outer.coverage(*outer_radius, fx, fy) - inner.coverage(*inner_radius, fx, fy);
+                if coverage > 0.0 {
+                    mix(pixel, fill.color_at(&area, fx, fy), coverage)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remembers the pixels outside the rounded corners of the content in `rect`
+    ///
+    /// Returns `None` if the content has no rounded corners.
+    #[inline]
+    pub fn content_corners(
+        &self,
+        image: &img::Image,
+        rect: &Rect,
+    ) -> Result<Option<CornerMask>, arithmetic::Error> {
+        let area = Area::try_from(rect)?;
+        let radius = f64::from(self.content_radius).max(0.0) * area.min_dim();
+        if radius <= 0.0 {
+            return Ok(None);
+        }
+        let pixels = pixels(image, rect)?
+            .filter_map(|(x, y)| {
+                let coverage = area.coverage(radius, f64::from(x), f64::from(y));
+                if coverage < 1.0 {
+                    Some((x, y, *image.get_pixel(x, y), 1.0 - coverage))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Ok(Some(CornerMask { pixels }))
+    }
+}
+
+impl CornerMask {
+    /// Blends the remembered pixels back over the content
+    #[inline]
+    pub fn restore(&self, image: &mut img::Image) -> Result<(), arithmetic::Error> {
+        for &(x, y, previous, weight) in &self.pixels {
+            mix(image.get_pixel_mut(x, y), previous.0.map(f64::from), weight)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Fill {
+    type Err = ParseError;
+
+    /// Parses `COLOR`, `linear:FROM,TO[,ANGLE]` or `radial:INNER,OUTER`
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::InvalidFill(s.to_string());
+        let (kind, values) = match s.split_once(':') {
+            Some((kind, values)) => (kind.trim().to_ascii_lowercase(), values),
+            None => return s.parse().map(Fill::Solid).map_err(|_| invalid()),
+        };
+        let values: Vec<_> = values.split(',').map(str::trim).collect();
+        let color = |value: &str| value.parse::<Color>().map_err(|_| invalid());
+        match (kind.as_str(), values.as_slice()) {
+            ("linear", &[from, to]) => Ok(Fill::Linear {
+                from: color(from)?,
+                to: color(to)?,
+                angle: 0.0,
+            }),
+            ("linear", &[from, to, angle]) => Ok(Fill::Linear {
+                from: color(from)?,
+                to: color(to)?,
+                angle: angle.parse().map_err(|_| invalid())?,
+            }),
+            ("radial", &[inner, outer]) => Ok(Fill::Radial {
+                inner: color(inner)?,
+                outer: color(outer)?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::str::FromStr for Stroke {
+    type Err = ParseError;
+
+    /// Parses `WIDTH[,INSET],FILL`
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::InvalidStroke(s.to_string());
+        let (width, rest) = s.split_once(',').ok_or_else(invalid)?;
+        let width = width.trim().parse().map_err(|_| invalid())?;
+        let (inset, fill) = match rest.split_once(',') {
+            Some((inset, fill)) => match inset.trim().parse() {
+                Ok(inset) => (inset, fill),
+                Err(_) => (0.0, rest),
+            },
+            None => (0.0, rest),
+        };
+        Ok(Stroke {
+            width,
+            inset,
+            fill: fill.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[derive(thiserror::Error, PartialEq, Eq, Clone, Debug)]
+pub enum ParseError {
+    #[error(
+        "invalid fill `{0}` (expected `COLOR`, `linear:FROM,TO[,ANGLE]` or `radial:INNER,OUTER`)"
+    )]
+    InvalidFill(String),
+
+    #[error("invalid stroke `{0}` (expected `WIDTH[,INSET],FILL`)")]
+    InvalidStroke(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fill, Stroke, Style};
+    use crate::img::Image;
+    use crate::types::{Color, Rect};
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_fill() {
+        assert_eq!(
+            Fill::from_str("#ff0000").ok(),
+            Some(Fill::Solid(Color::rgb(255, 0, 0)))
+        );
+        assert_eq!(
+            Fill::from_str("linear:#000000, #ffffff, 90").ok(),
+            Some(Fill::Linear {
+                from: Color::black(),
+                to: Color::white(),
+                angle: 90.0
+            })
+        );
+        assert_eq!(
+            Fill::from_str("Radial:#000000,#ffffff").ok(),
+            Some(Fill::Radial {
+                inner: Color::black(),
+                outer: Color::white(),
+            })
+        );
+        assert!(Fill::from_str("radial:#000000").is_err());
+        assert!(Fill::from_str("conic:#000000,#ffffff").is_err());
+    }
+
+    #[test]
+    fn test_parse_stroke() {
+        assert_eq!(
+            Stroke::from_str("0.01,#ffffff").ok(),
+            Some(Stroke {
+                width: 0.01,
+                inset: 0.0,
+                fill: Fill::Solid(Color::white())
+            })
+        );
+        assert_eq!(
+            Stroke::from_str("0.01,0.05,linear:#000000,#ffffff").ok(),
+            Some(Stroke {
+                width: 0.01,
+                inset: 0.05,
+                fill: Fill::Linear {
+                    from: Color::black(),
+                    to: Color::white(),
+                    angle: 0.0
+                }
+            })
+        );
+        assert_eq!(
+            Stroke::from_str("0.01,linear:#000000,#ffffff,45").ok(),
+            Some(Stroke {
+                width: 0.01,
+                inset: 0.0,
+                fill: Fill::Linear {
+                    from: Color::black(),
+                    to: Color::white(),
+                    angle: 45.0
+                }
+            })
+        );
+        assert!(Stroke::from_str("#ffffff").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_style() -> anyhow::Result<()> {
+        let style: Style = serde_json::from_str(
+            r##"{
+                "radius": 0.1,
+                "strokes": [{ "width": 0.01, "fill": { "Solid": "#ffffff" } }]
+            }"##,
+        )?;
+        assert_eq!(
+            style,
+            Style {
+                radius: 0.1,
+                strokes: vec![Stroke {
+                    width: 0.01,
+                    inset: 0.0,
+                    fill: Fill::Solid(Color::white())
+                }],
+                ..Style::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_rounded_frame() -> anyhow::Result<()> {
+        let mut image = Image::new(100, 100);
+        image.fill(Color::white(), crate::FillMode::Set)?;
+        let rect = Rect::new((0, 0), (100, 100))?;
+        let style = Style {
+            radius: 0.2,
+            ..Style::default()
+        };
+        style.draw_frame(&mut image, Color::black(), &rect)?;
+        // corners keep the background
+        assert_eq!(*image.get_pixel(0, 0), image::Rgba([255, 255, 255, 255]));
+        assert_eq!(*image.get_pixel(99, 99), image::Rgba([255, 255, 255, 255]));
+        assert_eq!(*image.get_pixel(50, 0), image::Rgba([0, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(50, 50), image::Rgba([0, 0, 0, 255]));
+        // edges of the corners are anti-aliased
+        let edge = (0..20)
+            .flat_map(|y| (0..20).map(move |x| (x, y)))
+            .map(|(x, y)| image.get_pixel(x, y)[0])
+            .collect::<Vec<_>>();
+        assert!(edge.iter().any(|&v| v > 0 && v < 255));
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_frame_strokes_and_gradient() -> anyhow::Result<()> {
+        let mut image = Image::new(100, 50);
+        let rect = Rect::new((0, 0), (100, 50))?;
+        let style = Style {
+            fill: Some(Fill::Linear {
+                from: Color::black(),
+                to: Color::white(),
+                angle: 0.0,
+            }),
+            strokes: vec![Stroke {
+                width: 0.1,
+                inset: 0.2,
+                fill: Fill::Solid(Color::rgb(255, 0, 0)),
+            }],
+            ..Style::default()
+        };
+        style.draw_frame(&mut image, Color::black(), &rect)?;
+        assert!(image.get_pixel(0, 2)[0] < 5);
+        assert!(image.get_pixel(99, 2)[0] > 250);
+        assert!(image.get_pixel(30, 2)[0] < image.get_pixel(60, 2)[0]);
+        // stroke is 5px wide and inset by 10px
+        assert_eq!(*image.get_pixel(50, 12), image::Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(12, 25), image::Rgba([255, 0, 0, 255]));
+        assert_ne!(*image.get_pixel(50, 8), image::Rgba([255, 0, 0, 255]));
+        assert_ne!(*image.get_pixel(50, 16), image::Rgba([255, 0, 0, 255]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_content_corners() -> anyhow::Result<()> {
+        let mut image = Image::new(60, 60);
+        image.fill(Color::white(), crate::FillMode::Set)?;
+        let rect = Rect::new((10, 10), (40, 40))?;
+        let style = Style {
+            content_radius: 0.5,
+            ..Style::default()
+        };
+        let corners = style.content_corners(&image, &rect)?.unwrap();
+        image.fill_rect(Color::black(), &rect, crate::FillMode::Set)?;
+        corners.restore(&mut image)?;
+        assert_eq!(*image.get_pixel(10, 10), image::Rgba([255, 255, 255, 255]));
+        assert_eq!(*image.get_pixel(30, 30), image::Rgba([0, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(30, 11), image::Rgba([0, 0, 0, 255]));
+        assert!(Style::default().content_corners(&image, &rect)?.is_none());
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use super::arithmetic::{
     self,
-    ops::{CheckedDiv, CheckedMul},
+    ops::{CheckedAdd, CheckedDiv, CheckedMul},
     Cast, Clamp,
 };
 use super::{
@@ -8,7 +8,7 @@ use super::{
     types::{Point, Rect},
 };
 pub use image::imageops::*;
-use image::{GenericImage, GenericImageView, Pixel, Rgba};
+use image::{GenericImage, GenericImageView, Pixel};
 
 #[derive(Clone, Copy, Debug)]
 pub enum FillMode {
@@ -16,68 +16,415 @@ pub enum FillMode {
     Set,
 }
 
+/// Disjoint sets of component labels
+#[derive(Debug, Default)]
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    #[inline]
+    fn insert(&mut self) -> usize {
+        let label = self.parents.len();
+        self.parents.push(label);
+        label
+    }
+
+    #[inline]
+    fn find(&mut self, mut label: usize) -> usize {
+        while self.parents[label] != label {
+            // path halving
+            self.parents[label] = self.parents[self.parents[label]];
+            label = self.parents[label];
+        }
+        label
+    }
+
+    #[inline]
+    fn union(&mut self, a: usize, b: usize) -> usize {
+        let (a, b) = (self.find(a), self.find(b));
+        // the older label stays the root to keep the scan order
+        let (root, child) = if a <= b { (a, b) } else { (b, a) };
+        self.parents[child] = root;
+        root
+    }
+}
+
+/// Checks if `a` padded by `padding` overlaps `b`
+#[inline]
+fn within_distance(a: &Rect, b: &Rect, padding: i64) -> bool {
+    a.left - padding <= b.right
+        && b.left <= a.right + padding
+        && a.top - padding <= b.bottom
+        && b.top <= a.bottom + padding
+}
+
+/// Finds the bounding boxes of transparent components
+///
+/// Transparent pixels at most `component_threshold` pixels apart belong
+/// to the same component, as do components whose bounding boxes are at
+/// most `component_threshold` pixels apart.
+/// Rects span from the first to the last transparent pixel, inclusive.
+///
+/// Components are labeled with union-find in a single pass over the
+/// image, keeping only two rows of labels in memory.
+/// To connect pixels up to `component_threshold` apart, every transparent
+/// pixel covers a square of that size to its bottom right and the covered
+/// cells are labeled as 8-connected components.
 #[inline]
 pub fn find_transparent_components(
     image: &img::Image,
     alpha_threshold: f64,
     component_threshold: u32,
 ) -> Result<Vec<Rect>, TransparentComponentsError> {
-    let mut components: Vec<Rect> = Vec::new();
+    let mask = transparent_mask(image, alpha_threshold)?;
+    Ok(find_components(&mask, component_threshold))
+}
+
+/// Mask of the pixels with an alpha below `alpha_threshold`
+///
+/// Transparent pixels are set to 255, all others to 0.
+#[inline]
+pub fn transparent_mask(
+    image: &img::Image,
+    alpha_threshold: f64,
+) -> Result<image::GrayImage, TransparentComponentsError> {
     let alpha_threshold =
         CheckedMul::checked_mul(alpha_threshold, 255.0).map_err(arithmetic::Error::from)?;
     let alpha_threshold = alpha_threshold
         .cast::<u8>()
         .map_err(arithmetic::Error::from)?;
-
     let (w, h) = image.inner.dimensions();
+    Ok(image::GrayImage::from_fn(w, h, |x, y| {
+        let transparent = image.inner.get_pixel(x, y).channels()[3] < alpha_threshold;
+        image::Luma([if transparent { 255 } else { 0 }])
+    }))
+}
+
+/// Alpha threshold separating transparent from opaque pixels
+///
+/// The threshold is chosen with Otsu's method, maximizing the variance
+/// between the alpha values of both classes.
+/// Returns `None` if all pixels have the same alpha.
+#[inline]
+pub fn otsu_alpha_threshold(image: &img::Image) -> Result<Option<f64>, arithmetic::Error> {
+    let mut histogram = [0u64; 256];
+    for pixel in image.inner.pixels() {
+        histogram[usize::from(pixel.0[3])] += 1;
+    }
+    let histogram = histogram
+        .iter()
+        .map(|count| count.cast::<f64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let total: f64 = histogram.iter().sum();
+    let sum: f64 = (0..=u8::MAX)
+        .zip(&histogram)
+        .map(|(alpha, count)| f64::from(alpha) * count)
+        .sum();
+
+    // thresholds with the same variance form a plateau between two
+    // peaks, so use its middle
+    let mut best: Option<(f64, u8, u8)> = None;
+    let (mut weight, mut weighted_sum) = (0.0, 0.0);
+    for (alpha, count) in (0..u8::MAX).zip(&histogram) {
+        weight += count;
+        weighted_sum += f64::from(alpha) * count;
+        let other_weight = total - weight;
+        if weight == 0.0 || other_weight == 0.0 {
+            continue;
+        }
+        let mean = weighted_sum / weight;
+        let other_mean = (sum - weighted_sum) / other_weight;
+        let variance = weight * other_weight * (mean - other_mean).powi(2);
+        best = match best {
+            Some((best, first, _)) if (variance - best).abs() <= best * 1e-9 => {
+                Some((best, first, alpha))
+            }
+            Some((best, ..)) if variance < best => continue,
+            _ => Some((variance, alpha, alpha)),
+        };
+    }
+    let Some((_, first, last)) = best else {
+        return Ok(None);
+    };
+    // pixels up to the threshold are transparent
+    let threshold = (u16::from(first) + u16::from(last)) / 2;
+    Ok(Some((f64::from(threshold) + 1.0) / 255.0))
+}
+
+/// Marks opaque regions of `mask` that are enclosed by transparent pixels
+/// as transparent
+///
+/// Opaque pixels that are not 4-connected to the edge of the mask are
+/// holes, such as dust specks inside a window.
+#[inline]
+pub fn fill_holes(mask: &mut image::GrayImage) -> Result<(), arithmetic::Error> {
+    let (w, h) = mask.dimensions();
+    let width = w.cast::<usize>()?;
+    let len = CheckedMul::checked_mul(u64::from(w), u64::from(h))?.cast::<usize>()?;
+    let mut outside = vec![false; len];
+    // indices are below `len`, so they cannot overflow
+    let index = |x: u32, y: u32| -> Result<usize, arithmetic::Error> {
+        Ok(y.cast::<usize>()? * width + x.cast::<usize>()?)
+    };
+    let mut stack: Vec<(u32, u32)> = (0..w)
+        .flat_map(|x| [(x, 0), (x, h.saturating_sub(1))])
+        .chain((0..h).flat_map(|y| [(0, y), (w.saturating_sub(1), y)]))
+        .filter(|_| w > 0 && h > 0)
+        .collect();
+    while let Some((x, y)) = stack.pop() {
+        let idx = index(x, y)?;
+        if outside[idx] || mask.get_pixel(x, y).0[0] != 0 {
+            continue;
+        }
+        outside[idx] = true;
+        if x > 0 {
+            stack.push((x - 1, y));
+        }
+        if x + 1 < w {
+            stack.push((x + 1, y));
+        }
+        if y > 0 {
+            stack.push((x, y - 1));
+        }
+        if y + 1 < h {
+            stack.push((x, y + 1));
+        }
+    }
+    for (x, y, pixel) in mask.enumerate_pixels_mut() {
+        if !outside[index(x, y)?] {
+            pixel.0[0] = 255;
+        }
+    }
+    Ok(())
+}
+
+/// Finds the bounding boxes of the components of the non-zero pixels of `mask`
+///
+/// See [`find_transparent_components`].
+#[inline]
+#[must_use]
+pub fn find_components(mask: &image::GrayImage, component_threshold: u32) -> Vec<Rect> {
+    let components = find_connected_components(mask, component_threshold);
+    let (components, _) = merge_close_components(components, i64::from(component_threshold));
+    components
+}
+
+/// Merges components whose bounding boxes are at most `padding` apart
+///
+/// Close boxes are found by sweeping over the boxes sorted by their left
+/// edge and joined with union-find.
+/// Merged boxes can come close to other boxes, so the sweep repeats until
+/// no boxes were merged.
+/// Merged components keep the position of their first component.
+///
+/// Returns the merged components and the index of the merged component
+/// of each of the given components.
+#[inline]
+#[must_use]
+pub fn merge_close_components(mut components: Vec<Rect>, padding: i64) -> (Vec<Rect>, Vec<usize>) {
+    let mut groups: Vec<usize> = (0..components.len()).collect();
+    loop {
+        let mut sets = UnionFind::default();
+        for _ in &components {
+            sets.insert();
+        }
+        let mut order: Vec<usize> = (0..components.len()).collect();
+        order.sort_by_key(|&idx| components[idx].left);
+
+        let mut merged = false;
+        // boxes that may still be close to the following boxes
+        let mut active: Vec<usize> = Vec::new();
+        for idx in order {
+            let component = &components[idx];
+            active.retain(|&other| components[other].right + padding >= component.left);
+            for &other in &active {
+                if within_distance(component, &components[other], padding)
+                    && sets.find(idx) != sets.find(other)
+                {
+                    sets.union(idx, other);
+                    merged = true;
+                }
+            }
+            active.push(idx);
+        }
+        if !merged {
+            return (components, groups);
+        }
+
+        let mut joined: Vec<Option<Rect>> = vec![None; components.len()];
+        for (idx, component) in components.iter().enumerate() {
+            match &mut joined[sets.find(idx)] {
+                Some(joined) => {
+                    joined.extend_to(&component.top_left());
+                    joined.extend_to(&component.bottom_right());
+                }
+                joined @ None => *joined = Some(*component),
+            }
+        }
+        let mut indices = vec![0; joined.len()];
+        let mut count = 0;
+        for (idx, joined) in joined.iter().enumerate() {
+            if joined.is_some() {
+                indices[idx] = count;
+                count += 1;
+            }
+        }
+        for group in &mut groups {
+            *group = indices[sets.find(*group)];
+        }
+        components = joined.into_iter().flatten().collect();
+    }
+}
+
+/// Finds the bounding boxes of the components of the non-zero pixels of
+/// `mask` without merging components whose bounding boxes are close
+///
+/// Pixels at most `component_threshold` pixels apart belong to the same
+/// component, so the bounding boxes of separate components, such as two
+/// round openings, may overlap.
+#[inline]
+#[must_use]
+pub fn find_connected_components(mask: &image::GrayImage, component_threshold: u32) -> Vec<Rect> {
+    let (components, _) = connected_components(mask, component_threshold, |_, _| {});
+    components
+}
+
+/// Labels the components of the non-zero pixels of `mask`
+///
+/// Returns the label of each pixel in row-major order, where 0 is the
+/// background and label `i + 1` belongs to the component at index `i` of
+/// [`find_connected_components`].
+#[inline]
+pub fn label_connected_components(
+    mask: &image::GrayImage,
+    component_threshold: u32,
+) -> Result<(Vec<u32>, Vec<Rect>), arithmetic::Error> {
+    let mut labels = vec![0; mask.len()];
+    let mut overflow = None;
+    let (components, sets) = connected_components(mask, component_threshold, |pixel, label| {
+        match label.checked_add(1).map(Cast::cast::<u32>) {
+            Some(Ok(label)) => labels[pixel] = label,
+            Some(Err(err)) => overflow = Some(err.into()),
+            None => {}
+        }
+    });
+    if let Some(err) = overflow {
+        return Err(err);
+    }
+    for label in labels.iter_mut().filter(|label| **label != 0) {
+        *label = match sets[(*label - 1).cast::<usize>()?] {
+            Some(component) => CheckedAdd::checked_add(component.cast::<u32>()?, 1)?,
+            None => 0,
+        };
+    }
+    Ok((labels, components))
+}
+
+/// Labels the components of `mask` in a single pass
+///
+/// `visit` is called with the index and provisional label of each
+/// non-zero pixel.
+/// Returns the components and the component of each provisional label.
+#[inline]
+fn connected_components(
+    mask: &image::GrayImage,
+    component_threshold: u32,
+    mut visit: impl FnMut(usize, usize),
+) -> (Vec<Rect>, Vec<Option<usize>>) {
+    let (w, h) = mask.dimensions();
+    let extent = component_threshold.max(1);
+    let width = w as usize;
+
+    let mut sets = UnionFind::default();
+    let mut boxes: Vec<Option<Rect>> = Vec::new();
+    // last row with a transparent pixel in each column
+    let mut last_rows: Vec<Option<u32>> = vec![None; width];
+    let mut prev_labels: Vec<Option<usize>> = vec![None; width];
+    let mut labels: Vec<Option<usize>> = vec![None; width];
+
+    let mut pixel = 0;
     for y in 0..h {
+        // last column covered by the squares of the columns
+        let mut last_covered: Option<u32> = None;
         for x in 0..w {
-            let point = Point {
-                x: i64::from(x),
-                y: i64::from(y),
-            };
-            let p: &Rgba<u8> = image.inner.get_pixel(x, y);
-            let alpha = p.channels()[3];
-            if alpha >= alpha_threshold {
+            let idx = x as usize;
+            let transparent = mask.get_pixel(x, y).0[0] != 0;
+            pixel += 1;
+            if transparent {
+                last_rows[idx] = Some(y);
+            }
+            if last_rows[idx].is_some_and(|row| y - row < extent) {
+                last_covered = Some(x);
+            }
+            let covered = last_covered.is_some_and(|col| x - col < extent);
+            if !covered {
+                labels[idx] = None;
                 continue;
             }
 
-            let mut updated = None;
-            // check if this is a new component
-            for c in &mut components {
-                let padded = c
-                    .padded(component_threshold)
-                    .map_err(arithmetic::Error::from)?;
-                if padded.contains(&point) {
-                    // update component
-                    updated = Some(*c);
-                    c.extend_to(&point);
-                    break;
-                }
+            let left = idx.checked_sub(1);
+            let neighbors = [
+                left.and_then(|left| labels[left]),
+                left.and_then(|left| prev_labels[left]),
+                prev_labels[idx],
+                prev_labels.get(idx + 1).copied().flatten(),
+            ];
+            let mut label = None;
+            for &neighbor in neighbors.iter().flatten() {
+                label = match label {
+                    Some(label) if label != neighbor => Some(sets.union(label, neighbor)),
+                    Some(label) => Some(label),
+                    None => Some(neighbor),
+                };
             }
+            let label = label.unwrap_or_else(|| {
+                boxes.push(None);
+                sets.insert()
+            });
+            labels[idx] = Some(label);
 
-            match updated {
-                Some(mut updated) => {
-                    // merge components
-                    // this will remove updated component as well
-                    components.retain(|other| {
-                        if updated.has_intersection(other) {
-                            updated.extend_to(&other.top_left());
-                            updated.extend_to(&other.bottom_right());
-                            false
-                        } else {
-                            true
-                        }
-                    });
-                    components.push(updated);
-                }
-                None => {
-                    components.push(Rect::from(point));
+            if transparent {
+                visit(pixel - 1, label);
+                let point = Point {
+                    x: i64::from(x),
+                    y: i64::from(y),
+                };
+                let bounds = &mut boxes[label];
+                match bounds {
+                    Some(bounds) => bounds.extend_to(&point),
+                    None => *bounds = Some(Rect::from(point)),
                 }
             }
         }
+        std::mem::swap(&mut labels, &mut prev_labels);
+    }
+
+    // collect the bounding boxes of each set in the order they were found
+    let mut components: Vec<Option<Rect>> = vec![None; boxes.len()];
+    for (label, bounds) in boxes.into_iter().enumerate() {
+        let Some(bounds) = bounds else { continue };
+        let component = &mut components[sets.find(label)];
+        match component {
+            Some(component) => {
+                component.extend_to(&bounds.top_left());
+                component.extend_to(&bounds.bottom_right());
+            }
+            None => *component = Some(bounds),
+        }
     }
-    Ok(components)
+    let mut indices: Vec<Option<usize>> = vec![None; components.len()];
+    let mut found = Vec::new();
+    for (root, component) in components.into_iter().enumerate() {
+        if let Some(component) = component {
+            indices[root] = Some(found.len());
+            found.push(component);
+        }
+    }
+    let labels = (0..indices.len())
+        .map(|label| indices[sets.find(label)])
+        .collect();
+    (found, labels)
 }
 
 #[inline]
@@ -115,6 +462,101 @@ pub fn fill_rect(
     }
 }
 
+/// Gaussian blur of a coverage mask
+#[inline]
+#[must_use]
+pub fn blur_mask(mask: &image::GrayImage, sigma: f32) -> image::GrayImage {
+    if sigma > 0.0 {
+        imageproc::filter::gaussian_blur_f32(mask, sigma)
+    } else {
+        mask.clone()
+    }
+}
+
+/// Blends `color` onto `image` at `offset`, weighted by the coverage of `mask`
+#[inline]
+pub fn blend_mask(
+    image: &mut image::RgbaImage,
+    mask: &image::GrayImage,
+    offset: Point,
+    color: image::Rgba<u8>,
+) {
+    let (width, height) = image.dimensions();
+    for (x, y, coverage) in mask.enumerate_pixels() {
+        let (Ok(x), Ok(y)) = (
+            u32::try_from(offset.x + i64::from(x)),
+            u32::try_from(offset.y + i64::from(y)),
+        ) else {
+            continue;
+        };
+        if x >= width || y >= height || coverage.0[0] == 0 {
+            continue;
+        }
+        let alpha = u16::from(color.0[3]) * u16::from(coverage.0[0]) / 255;
+        let mut color = color;
+        color.0[3] = u8::try_from(alpha).unwrap_or(u8::MAX);
+        image.get_pixel_mut(x, y).blend(&color);
+    }
+}
+
+/// Converts an sRGB color to the CIELAB color space
+#[inline]
+fn srgb_to_lab([r, g, b]: [u8; 3]) -> [f64; 3] {
+    let linear = |c: u8| {
+        let c = f64::from(c) / 255.0;
+        if c <= 0.040_45 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+    // relative to the D65 white point
+    let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / 0.950_47;
+    let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+    let z = (0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b) / 1.088_83;
+    let f = |t: f64| {
+        const DELTA: f64 = 6.0 / 29.0;
+        if t > DELTA.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA.powi(2)) + 4.0 / 29.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Makes pixels with a color close to `key` transparent
+///
+/// Pixels within `tolerance` of the key in the CIELAB color space become
+/// fully transparent, pixels up to `feather` further away fade back in.
+#[inline]
+pub fn chroma_key(image: &mut img::Image, key: image::Rgba<u8>, tolerance: f64, feather: f64) {
+    let key = srgb_to_lab(key.to_rgb().0);
+    for pixel in image.inner.pixels_mut() {
+        if pixel.0[3] == 0 {
+            continue;
+        }
+        let lab = srgb_to_lab(pixel.to_rgb().0);
+        let distance = key
+            .iter()
+            .zip(lab)
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        let opacity = if distance <= tolerance {
+            0.0
+        } else if distance < tolerance + feather {
+            (distance - tolerance) / feather
+        } else {
+            continue;
+        };
+        let alpha = (f64::from(pixel.0[3]) * opacity).round();
+        pixel.0[3] = Clamp::clamp(alpha, 0.0, 255.0).cast::<u8>().unwrap_or(0);
+    }
+}
+
 #[inline]
 pub fn fade_out(
     mut image: image::SubImage<&mut image::RgbaImage>,
@@ -173,3 +615,308 @@ pub enum FadeError {
 }
 
 impl arithmetic::error::Arithmetic for FadeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        chroma_key, fill_holes, find_connected_components, find_transparent_components,
+        label_connected_components, merge_close_components, otsu_alpha_threshold, transparent_mask,
+    };
+    use crate::arithmetic::{self, ops::CheckedMul, Cast};
+    use crate::img::Image;
+    use crate::types::{Point, Rect};
+    use anyhow::Result;
+    use image::{Pixel, Rgba, RgbaImage};
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    /// Previous implementation that grows bounding boxes pixel by pixel
+    fn find_transparent_components_scan(
+        image: &Image,
+        alpha_threshold: f64,
+        component_threshold: u32,
+    ) -> Result<Vec<Rect>> {
+        let mut components: Vec<Rect> = Vec::new();
+        let alpha_threshold = CheckedMul::checked_mul(alpha_threshold, 255.0)
+            .map_err(arithmetic::Error::from)?
+            .cast::<u8>()
+            .map_err(arithmetic::Error::from)?;
+
+        let (w, h) = image.inner.dimensions();
+        for y in 0..h {
+            for x in 0..w {
+                let point = Point {
+                    x: i64::from(x),
+                    y: i64::from(y),
+                };
+                let p: &Rgba<u8> = image.inner.get_pixel(x, y);
+                if p.channels()[3] >= alpha_threshold {
+                    continue;
+                }
+                let mut updated = None;
+                for c in &mut components {
+                    let padded = c
+                        .padded(component_threshold)
+                        .map_err(arithmetic::Error::from)?;
+                    if padded.contains(&point) {
+                        updated = Some(*c);
+                        c.extend_to(&point);
+                        break;
+                    }
+                }
+                match updated {
+                    Some(mut updated) => {
+                        components.retain(|other| {
+                            if updated.has_intersection(other) {
+                                updated.extend_to(&other.top_left());
+                                updated.extend_to(&other.bottom_right());
+                                false
+                            } else {
+                                true
+                            }
+                        });
+                        components.push(updated);
+                    }
+                    None => components.push(Rect::from(point)),
+                }
+            }
+        }
+        Ok(components)
+    }
+
+    fn sorted(mut components: Vec<Rect>) -> Vec<Rect> {
+        components.sort_by_key(|c| (c.top, c.left, c.bottom, c.right));
+        components
+    }
+
+    fn image_with_holes(width: u32, height: u32, holes: &[(u32, u32, u32, u32)]) -> Image {
+        let mut image = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+        for &(left, top, right, bottom) in holes {
+            for y in top..bottom {
+                for x in left..right {
+                    image.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+                }
+            }
+        }
+        Image::from_image(&image::DynamicImage::ImageRgba8(image))
+    }
+
+    #[test]
+    fn test_components_within_threshold() -> Result<()> {
+        let image = image_with_holes(
+            100,
+            60,
+            &[
+                (10, 10, 30, 30),
+                // 5 pixels apart from the first hole
+                (35, 10, 50, 30),
+                // 20 pixels apart from the others
+                (70, 10, 90, 50),
+                // single noisy pixel
+                (5, 55, 6, 56),
+            ],
+        );
+        let components = find_transparent_components(&image, 0.95, 8)?;
+        assert_eq!(
+            components,
+            vec![
+                Rect::from_points((10, 10), (49, 29)),
+                Rect::from_points((70, 10), (89, 49)),
+                Rect::from_points((5, 55), (5, 55)),
+            ]
+        );
+        let components = find_transparent_components(&image, 0.95, 2)?;
+        assert_eq!(components.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_components_match_scan() -> Result<()> {
+        let image = image_with_holes(
+            120,
+            80,
+            &[
+                (5, 5, 40, 35),
+                (44, 5, 80, 35),
+                (5, 50, 100, 75),
+                (110, 2, 111, 3),
+                (113, 70, 118, 78),
+            ],
+        );
+        // with larger thresholds, the scan depends on the order components
+        // are found in and keeps the noisy pixel above the merged windows
+        for threshold in [1, 3, 8] {
+            assert_eq!(
+                sorted(find_transparent_components(&image, 0.95, threshold)?),
+                sorted(find_transparent_components_scan(&image, 0.95, threshold)?),
+                "threshold {}",
+                threshold
+            );
+        }
+        assert_eq!(
+            find_transparent_components(&image, 0.95, 20)?,
+            vec![Rect::from_points((5, 2), (117, 77))]
+        );
+
+        let repo: PathBuf = env!("CARGO_MANIFEST_DIR").into();
+        for sample in [
+            "samples/borders/border_1_areas_horizontal.png",
+            "samples/borders/border_3_areas_vertical.png",
+        ] {
+            let mut image = Image::open(repo.join(sample))?;
+            image.resize(
+                crate::types::Size {
+                    width: 400,
+                    height: 400,
+                },
+                crate::types::ResizeMode::Contain,
+            )?;
+            assert_eq!(
+                sorted(find_transparent_components(&image, 0.95, 8)?),
+                sorted(find_transparent_components_scan(&image, 0.95, 8)?),
+                "{}",
+                sample
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_label_connected_components() -> Result<()> {
+        let image = image_with_holes(
+            120,
+            80,
+            &[
+                (5, 5, 40, 35),
+                (44, 5, 80, 35),
+                (5, 50, 100, 75),
+                (110, 2, 111, 3),
+            ],
+        );
+        let mask = transparent_mask(&image, 0.95)?;
+        for threshold in [1, 8] {
+            let (labels, components) = label_connected_components(&mask, threshold)?;
+            assert_eq!(components, find_connected_components(&mask, threshold));
+            // every transparent pixel lies within the component it is labeled with
+            for ((x, y, pixel), label) in mask.enumerate_pixels().zip(&labels) {
+                assert_eq!(pixel.0[0] != 0, *label != 0, "({x}, {y})");
+                if let Some(component) = label.checked_sub(1) {
+                    let point = Point {
+                        x: i64::from(x),
+                        y: i64::from(y),
+                    };
+                    let component = components[component.cast::<usize>()?];
+                    assert!(component.contains(&point), "({x}, {y})");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_close_components() -> Result<()> {
+        // the third hole is only close to the box of the first two holes
+        // once they are merged
+        let image = image_with_holes(
+            100,
+            60,
+            &[
+                (10, 10, 20, 20),
+                (22, 22, 32, 32),
+                (34, 6, 36, 8),
+                (60, 40, 70, 50),
+            ],
+        );
+        assert_eq!(
+            find_transparent_components(&image, 0.95, 4)?,
+            vec![
+                Rect::from_points((10, 6), (35, 31)),
+                Rect::from_points((60, 40), (69, 49)),
+            ]
+        );
+        let mask = transparent_mask(&image, 0.95)?;
+        let (_, groups) = merge_close_components(find_connected_components(&mask, 4), 4);
+        // the first two holes are already connected
+        assert_eq!(groups, vec![0, 0, 1]);
+
+        // isolated specks stay separate
+        let specks: Vec<_> = (0..20)
+            .flat_map(|y| (0..20).map(move |x| (x * 10, y * 10, x * 10 + 1, y * 10 + 1)))
+            .collect();
+        let image = image_with_holes(200, 200, &specks);
+        assert_eq!(find_transparent_components(&image, 0.95, 4)?.len(), 400);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chroma_key() -> Result<()> {
+        // blue-screen window with a slightly noisy key and a soft edge
+        let mut image = Image::from_image(
+            &RgbaImage::from_fn(100, 60, |x, y| {
+                let window = (20..80).contains(&x) && (10..50).contains(&y);
+                let edge = (x == 19 || x == 80) && (10..50).contains(&y);
+                #[allow(clippy::cast_possible_truncation)]
+                let noise = ((x * 7 + y * 13) % 5) as u8;
+                match (window, edge) {
+                    (true, _) => Rgba([10 + noise, 20, 230 - noise, 255]),
+                    (_, true) => Rgba([40, 50, 215, 255]),
+                    _ => Rgba([30, 30, 30, 255]),
+                }
+            })
+            .into(),
+        );
+        chroma_key(&mut image, Rgba([12, 20, 228, 255]), 12.0, 30.0);
+
+        let alpha = |x, y| image.inner.get_pixel(x, y).channels()[3];
+        assert_eq!(alpha(50, 30), 0);
+        assert_eq!(alpha(20, 10), 0);
+        assert_eq!(alpha(5, 5), 255);
+        assert!((1..255).contains(&alpha(19, 30)));
+
+        let components = find_transparent_components(&image, 0.95, 8)?;
+        // the feathered edge is part of the window
+        assert_eq!(components, vec![Rect::from_points((19, 10), (80, 49))]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_otsu_alpha_threshold() -> Result<()> {
+        let image = Image::from_image(
+            &RgbaImage::from_fn(10, 10, |x, _| Rgba([0, 0, 0, if x < 3 { 0 } else { 255 }])).into(),
+        );
+        assert_eq!(otsu_alpha_threshold(&image)?, Some(128.0 / 255.0));
+
+        // the soft edge belongs to the transparent side
+        let image = Image::from_image(
+            &RgbaImage::from_fn(10, 10, |x, _| {
+                Rgba([
+                    0,
+                    0,
+                    0,
+                    [10, 20, 30, 235, 240, 245, 250, 255, 255, 255][x as usize],
+                ])
+            })
+            .into(),
+        );
+        let threshold = otsu_alpha_threshold(&image)?.unwrap() * 255.0;
+        assert!((31.0..=235.0).contains(&threshold), "{threshold}");
+
+        let opaque = Image::from_image(&RgbaImage::from_pixel(10, 10, Rgba([0, 0, 0, 255])).into());
+        assert_eq!(otsu_alpha_threshold(&opaque)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fill_holes() -> Result<()> {
+        let mut mask = image::GrayImage::from_fn(20, 20, |x, y| {
+            let window = (2..18).contains(&x) && (2..18).contains(&y);
+            let speck = (8..11).contains(&x) && (8..11).contains(&y);
+            image::Luma([if window && !speck { 255 } else { 0 }])
+        });
+        fill_holes(&mut mask)?;
+        assert_eq!(mask.get_pixel(9, 9).0[0], 255);
+        assert_eq!(mask.get_pixel(1, 9).0[0], 0);
+        assert_eq!(mask.get_pixel(0, 0).0[0], 0);
+        Ok(())
+    }
+}
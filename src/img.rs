@@ -1,16 +1,32 @@
 use super::arithmetic::{self, ops::CheckedSub, Cast};
 use super::types::{self, sides::abs::Sides, Point, Rect, Size};
-use super::{defaults, imageops};
+use super::{defaults, imageops, metadata};
 use crate::{debug, debug::Instant};
 pub use image::ImageFormat;
 use std::fs;
-use std::io::{BufReader, Seek};
+use std::io::{BufReader, Cursor, Seek};
 use std::path::{Path, PathBuf};
 
+#[derive(Clone, Copy, Debug)]
+pub struct ReadOptions {
+    pub apply_exif_orientation: bool,
+}
+
+impl Default for ReadOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            apply_exif_orientation: true,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Image {
     pub(crate) inner: image::RgbaImage,
     pub(crate) path: Option<PathBuf>,
+    pub(crate) exif_orientation: Option<metadata::Orientation>,
+    pub(crate) metadata: metadata::Metadata,
 }
 
 impl std::ops::Deref for Image {
@@ -38,7 +54,12 @@ impl Image {
     #[must_use]
     pub fn new(width: u32, height: u32) -> Self {
         let inner = image::RgbaImage::new(width, height);
-        Self { inner, path: None }
+        Self {
+            inner,
+            path: None,
+            exif_orientation: None,
+            metadata: metadata::Metadata::default(),
+        }
     }
 
     #[inline]
@@ -52,15 +73,44 @@ impl Image {
     #[must_use]
     pub fn from_image(image: &image::DynamicImage) -> Self {
         let inner = image.to_rgba8();
-        Self { inner, path: None }
+        Self {
+            inner,
+            path: None,
+            exif_orientation: None,
+            metadata: metadata::Metadata::default(),
+        }
     }
 
     #[inline]
     pub fn from_reader(reader: impl std::io::BufRead + std::io::Seek) -> Result<Self, ReadError> {
+        Self::from_reader_with_options(reader, None)
+    }
+
+    #[inline]
+    pub fn from_reader_with_options(
+        mut reader: impl std::io::BufRead + std::io::Seek,
+        options: Option<ReadOptions>,
+    ) -> Result<Self, ReadError> {
+        let options = options.unwrap_or_default();
         match (|| {
-            let reader = image::io::Reader::new(reader).with_guessed_format()?;
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            let metadata = metadata::Metadata::from_bytes(&data);
+            let exif_orientation = metadata.orientation();
+
+            let reader = image::io::Reader::new(Cursor::new(&data)).with_guessed_format()?;
             let inner = reader.decode()?.to_rgba8();
-            let image = Self { inner, path: None };
+            let mut image = Self {
+                inner,
+                path: None,
+                exif_orientation,
+                metadata,
+            };
+            if options.apply_exif_orientation {
+                if let Some(orientation) = exif_orientation {
+                    image.apply_exif_orientation(orientation);
+                }
+            }
             Ok::<Self, ReadErrorSource>(image)
         })() {
             Ok(image) => Ok(image),
@@ -73,6 +123,14 @@ impl Image {
 
     #[inline]
     pub fn open(path: impl Into<PathBuf>) -> Result<Self, ReadError> {
+        Self::open_with_options(path, None)
+    }
+
+    #[inline]
+    pub fn open_with_options(
+        path: impl Into<PathBuf>,
+        options: Option<ReadOptions>,
+    ) -> Result<Self, ReadError> {
         let path = path.into();
         let file = fs::OpenOptions::new()
             .read(true)
@@ -82,7 +140,7 @@ impl Image {
                 source: err.into(),
             })?;
         let reader = BufReader::new(&file);
-        let image = Self::from_reader(reader).map_err(|err| ReadError {
+        let image = Self::from_reader_with_options(reader, options).map_err(|err| ReadError {
             path: Some(path.clone()),
             source: err.source,
         })?;
@@ -92,6 +150,28 @@ impl Image {
         })
     }
 
+    /// EXIF orientation detected when the image was read
+    ///
+    /// Unless disabled in the `ReadOptions`, the orientation has
+    /// already been applied to the pixels.
+    #[inline]
+    #[must_use]
+    pub fn exif_orientation(&self) -> Option<metadata::Orientation> {
+        self.exif_orientation
+    }
+
+    /// Metadata of the source image that is written when saving
+    #[inline]
+    #[must_use]
+    pub fn metadata(&self) -> &metadata::Metadata {
+        &self.metadata
+    }
+
+    #[inline]
+    pub fn set_metadata(&mut self, metadata: metadata::Metadata) {
+        self.metadata = metadata;
+    }
+
     #[inline]
     #[must_use]
     pub fn is_portrait(&self) -> bool {
@@ -344,6 +424,20 @@ impl Image {
         }
     }
 
+    #[inline]
+    pub fn flip_horizontal(&mut self) {
+        self.inner = imageops::flip_horizontal(&self.inner);
+    }
+
+    #[inline]
+    pub fn apply_exif_orientation(&mut self, orientation: metadata::Orientation) {
+        self.rotate(&orientation.rotation());
+        if orientation.is_mirrored() {
+            self.flip_horizontal();
+        }
+        self.metadata.set_orientation_applied(true);
+    }
+
     #[inline]
     pub fn rotate_to_orientation(&mut self, orientation: super::Orientation) {
         if self.orientation() != orientation {
@@ -397,12 +491,43 @@ impl Image {
         self.save_with_filename(path, quality)
     }
 
+    /// Encodes the image including its metadata
+    ///
+    /// Metadata is only written for JPEG, PNG and TIFF outputs.
     #[inline]
     pub fn encode_to(
         &self,
         w: &mut (impl std::io::Write + Seek),
         format: ImageFormat,
         quality: impl Into<Option<u8>>,
+    ) -> Result<(), EncodeError> {
+        if self.metadata.is_empty() {
+            self.encode_pixels(w, format, quality)?;
+            return Ok(());
+        }
+        let size = self.size();
+        let encoded = match format {
+            ImageFormat::Jpeg | ImageFormat::Png => {
+                let mut buffer = Cursor::new(Vec::new());
+                self.encode_pixels(&mut buffer, format, quality)?;
+                self.metadata.embed(buffer.get_ref(), format, size)?
+            }
+            ImageFormat::Tiff => self.metadata.encode_tiff(self.inner.as_raw(), size)?,
+            _ => {
+                self.encode_pixels(w, format, quality)?;
+                return Ok(());
+            }
+        };
+        w.write_all(&encoded).map_err(metadata::Error::from)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_pixels(
+        &self,
+        w: &mut (impl std::io::Write + Seek),
+        format: ImageFormat,
+        quality: impl Into<Option<u8>>,
     ) -> Result<(), image::ImageError> {
         use image::{codecs, ImageEncoder, ImageOutputFormat};
 
@@ -490,6 +615,15 @@ pub struct ReadError {
     source: ReadErrorSource,
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum EncodeError {
+    #[error(transparent)]
+    Image(#[from] image::error::ImageError),
+
+    #[error("failed to write metadata")]
+    Metadata(#[from] metadata::Error),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum SaveErrorSource {
     #[error("missing output file path")]
@@ -500,6 +634,9 @@ pub enum SaveErrorSource {
 
     #[error(transparent)]
     Image(#[from] image::error::ImageError),
+
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -673,8 +810,127 @@ pub enum Error {
 
 #[cfg(test)]
 mod tests {
-    use super::{Image, ImageFormat};
+    use super::{Image, ImageFormat, ReadOptions};
+    use crate::metadata::{Metadata, Orientation};
+    use anyhow::Result;
     use image::RgbaImage;
+    use std::io::Cursor;
+
+    fn jpeg_with_exif_orientation(orientation: Orientation) -> Result<Vec<u8>> {
+        // 16x8 image with a red left half and a blue right half
+        let pixels = RgbaImage::from_fn(16, 8, |x, _| {
+            if x < 8 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        });
+        let img = Image {
+            inner: pixels,
+            path: None,
+            exif_orientation: None,
+            metadata: Metadata::default(),
+        };
+        let mut jpeg = Cursor::new(Vec::new());
+        img.encode_to(&mut jpeg, ImageFormat::Jpeg, 100)?;
+        let jpeg = jpeg.into_inner();
+
+        // big endian TIFF header with a single IFD entry for the orientation
+        let mut exif: Vec<u8> =
+            b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+        #[allow(clippy::cast_possible_truncation)]
+        exif.extend([0, orientation.to_exif() as u8, 0, 0, 0, 0, 0, 0]);
+        let segment_len = u16::try_from(exif.len() + 2)?;
+
+        let mut data = jpeg[..2].to_vec();
+        data.extend([0xFF, 0xE1]);
+        data.extend(segment_len.to_be_bytes());
+        data.extend(exif);
+        data.extend(&jpeg[2..]);
+        Ok(data)
+    }
+
+    #[test]
+    fn test_read_exif_orientation() -> Result<()> {
+        let data = jpeg_with_exif_orientation(Orientation::Rotate90)?;
+        let img = Image::from_reader(Cursor::new(&data))?;
+        assert_eq!(img.exif_orientation(), Some(Orientation::Rotate90));
+        assert_eq!((img.width(), img.height()), (8, 16));
+        // red left half is rotated to the top
+        assert!(img.get_pixel(4, 2)[0] > 200);
+        assert!(img.get_pixel(4, 13)[2] > 200);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_exif_orientation_mirrored() -> Result<()> {
+        let data = jpeg_with_exif_orientation(Orientation::FlipHorizontal)?;
+        let img = Image::from_reader(Cursor::new(&data))?;
+        assert_eq!(img.exif_orientation(), Some(Orientation::FlipHorizontal));
+        assert_eq!((img.width(), img.height()), (16, 8));
+        assert!(img.get_pixel(2, 4)[2] > 200);
+        assert!(img.get_pixel(13, 4)[0] > 200);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignore_exif_orientation() -> Result<()> {
+        let data = jpeg_with_exif_orientation(Orientation::Rotate270)?;
+        let options = ReadOptions {
+            apply_exif_orientation: false,
+        };
+        let img = Image::from_reader_with_options(Cursor::new(&data), Some(options))?;
+        assert_eq!(img.exif_orientation(), Some(Orientation::Rotate270));
+        assert_eq!((img.width(), img.height()), (16, 8));
+        Ok(())
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_apply_exif_orientation() {
+        // pixel (x, y) stores x + 10 * y in its red channel
+        let pixels = RgbaImage::from_fn(3, 2, |x, y| image::Rgba([(x + 10 * y) as u8, 0, 0, 255]));
+        let red = |img: &Image| -> Vec<u8> { img.pixels().map(|p| p[0]).collect() };
+        let transformed = |orientation: Orientation| {
+            let mut img = Image {
+                inner: pixels.clone(),
+                path: None,
+                exif_orientation: None,
+                metadata: Metadata::default(),
+            };
+            img.apply_exif_orientation(orientation);
+            red(&img)
+        };
+        assert_eq!(transformed(Orientation::Normal), vec![0, 1, 2, 10, 11, 12]);
+        assert_eq!(
+            transformed(Orientation::FlipHorizontal),
+            vec![2, 1, 0, 12, 11, 10]
+        );
+        assert_eq!(
+            transformed(Orientation::Rotate180),
+            vec![12, 11, 10, 2, 1, 0]
+        );
+        assert_eq!(
+            transformed(Orientation::FlipVertical),
+            vec![10, 11, 12, 0, 1, 2]
+        );
+        assert_eq!(
+            transformed(Orientation::Rotate90FlipHorizontal),
+            vec![0, 10, 1, 11, 2, 12]
+        );
+        assert_eq!(
+            transformed(Orientation::Rotate90),
+            vec![10, 0, 11, 1, 12, 2]
+        );
+        assert_eq!(
+            transformed(Orientation::Rotate270FlipHorizontal),
+            vec![12, 2, 11, 1, 10, 0]
+        );
+        assert_eq!(
+            transformed(Orientation::Rotate270),
+            vec![2, 12, 1, 11, 0, 10]
+        );
+    }
 
     macro_rules! output_path_tests {
         ($($name:ident: $values:expr,)*) => {
@@ -690,6 +946,8 @@ mod tests {
                     let img = Image {
                         inner: RgbaImage::new(32, 32),
                         path: path.map(Into::into),
+                        exif_orientation: None,
+                        metadata: Metadata::default(),
                     };
                     let (have_path, have_format) = img.output_path(format);
                     assert_eq!(have_path, want_path.map(Into::into));
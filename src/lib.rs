@@ -4,20 +4,31 @@
 #![allow(clippy::unsafe_derive_deserialize)]
 
 pub mod arithmetic;
+pub mod background;
 pub mod border;
 #[cfg(feature = "builtin")]
 pub mod builtin;
+pub mod caption;
+pub mod config;
 pub mod debug;
 pub mod defaults;
 pub mod error;
+pub mod frame;
 pub mod imageops;
 pub mod img;
+pub mod markings;
+pub mod metadata;
 pub mod options;
+pub mod procedural;
+pub mod registry;
+pub mod shadow;
+pub mod svg;
 #[cfg(test)]
 mod test;
 pub mod types;
 #[cfg(feature = "wasm")]
 pub mod wasm;
+pub mod window;
 
 pub use border::Border;
 pub use image::ImageFormat;
@@ -47,6 +58,9 @@ pub struct ResultSize {
 
 pub struct ImageBorders {
     images: Vec<img::Image>,
+    transforms: Vec<Option<Transform>>,
+    fits: Vec<Option<window::Fit>>,
+    background: Option<img::Image>,
 }
 
 impl ImageBorders {
@@ -56,14 +70,24 @@ impl ImageBorders {
         if images.is_empty() {
             Err(Error::MissingImage)
         } else {
-            Ok(ImageBorders { images })
+            Ok(ImageBorders {
+                images,
+                transforms: Vec::new(),
+                fits: Vec::new(),
+                background: None,
+            })
         }
     }
 
     #[inline]
     #[must_use]
     pub fn single(img: img::Image) -> ImageBorders {
-        ImageBorders { images: vec![img] }
+        ImageBorders {
+            images: vec![img],
+            transforms: Vec::new(),
+            fits: Vec::new(),
+            background: None,
+        }
     }
 
     #[inline]
@@ -84,6 +108,46 @@ impl ImageBorders {
         Ok(Self::single(img))
     }
 
+    /// Set how the image of component `idx` is framed in its window
+    ///
+    /// Without a transform, the image is centered in the window.
+    #[inline]
+    pub fn set_transform(&mut self, idx: usize, transform: impl Into<Option<Transform>>) {
+        if self.transforms.len() <= idx {
+            self.transforms.resize(idx + 1, None);
+        }
+        self.transforms[idx] = transform.into();
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn transform(&self, idx: usize) -> Option<&Transform> {
+        self.transforms.get(idx).and_then(Option::as_ref)
+    }
+
+    /// Set how the image of component `idx` is fitted into its window
+    ///
+    /// Without a fit, the window fit of the render options is used.
+    #[inline]
+    pub fn set_fit(&mut self, idx: usize, fit: impl Into<Option<window::Fit>>) {
+        if self.fits.len() <= idx {
+            self.fits.resize(idx + 1, None);
+        }
+        self.fits[idx] = fit.into();
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn fit(&self, idx: usize) -> Option<&window::Fit> {
+        self.fits.get(idx).and_then(Option::as_ref)
+    }
+
+    /// Set the image used by the `Image` and `Texture` background modes
+    #[inline]
+    pub fn set_background_image(&mut self, image: impl Into<Option<img::Image>>) {
+        self.background = image.into();
+    }
+
     #[inline]
     /// Add (optional) border to image
     ///
@@ -103,6 +167,10 @@ impl ImageBorders {
         let primary = images.get_mut(0).ok_or(RenderError::MissingImage)?;
 
         prepare_primary(primary, options)?;
+        let caption = match options.caption {
+            Some(ref caption) => Some((caption, caption.text(&primary.metadata)?)),
+            None => None,
+        };
         let mut border = border_for_primary(border_kind, primary, options)?;
 
         let result_size = compute_result_size(&border, &*primary, options)?;
@@ -111,18 +179,22 @@ impl ImageBorders {
         // create new result image
         let mut result_image = img::Image {
             path: primary.path.clone(),
+            metadata: primary.metadata.clone().with_policy(options.metadata),
             ..img::Image::with_size(result_size.output_size)
         };
 
         result_image
             .fill(options.background_color(), FillMode::Set)
             .map_err(img::Error::from)?;
+        options
+            .background
+            .draw(&mut result_image, primary, self.background.as_ref())?;
 
         let content_rect = result_size
             .output_size
-            .center(result_size.content_size)
+            .anchor(result_size.content_size, options.anchor)
             .map_err(|err| error::Arithmetic {
-                msg: "failed to center content size".to_string(),
+                msg: "failed to anchor content size".to_string(),
                 source: err.into(),
             })?;
         debug!(&content_rect);
@@ -143,13 +215,9 @@ impl ImageBorders {
                     source: err.into(),
                 })?;
 
-        result_image
-            .fill_rect(
-                options.frame_color,
-                &content_rect_sub_margins,
-                FillMode::Set,
-            )
-            .map_err(img::Error::from)?;
+        if let Some(markings) = &options.markings {
+            markings.draw(&mut result_image, &content_rect, &content_rect_sub_margins)?;
+        }
 
         let border_rect = content_rect_sub_margins
             .checked_sub(result_size.frame_width)
@@ -163,6 +231,52 @@ impl ImageBorders {
             source: err.into(),
         })?;
 
+        if let Some(shadow) = options.shadow {
+            let radius = options
+                .frame_style
+                .as_ref()
+                .map_or(Ok(0.0), |style| style.radius_in(&content_rect_sub_margins))
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to compute frame radius".into(),
+                    source: err,
+                })?;
+            shadow
+                .draw(
+                    &mut result_image,
+                    &content_rect_sub_margins,
+                    radius,
+                    border_size.min_dim(),
+                )
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to draw shadow".into(),
+                    source: err.into(),
+                })?;
+        }
+
+        match options.frame_style {
+            Some(ref style) => {
+                style
+                    .draw_frame(
+                        &mut result_image,
+                        options.frame_color,
+                        &content_rect_sub_margins,
+                    )
+                    .map_err(|err| error::Arithmetic {
+                        msg: "failed to draw frame".into(),
+                        source: err,
+                    })?;
+            }
+            None => {
+                result_image
+                    .fill_rect(
+                        options.frame_color,
+                        &content_rect_sub_margins,
+                        FillMode::Set,
+                    )
+                    .map_err(img::Error::from)?;
+            }
+        }
+
         #[cfg(feature = "debug")]
         debug_fill(
             &mut result_image,
@@ -171,65 +285,77 @@ impl ImageBorders {
             &border_rect,
         )?;
 
+        let content_corners = options
+            .frame_style
+            .as_ref()
+            .map(|style| style.content_corners(&result_image, &border_rect))
+            .transpose()
+            .map_err(|err| error::Arithmetic {
+                msg: "failed to compute content corners".into(),
+                source: err,
+            })?
+            .flatten();
+
         let primary_component = Rect::from(border_size);
 
         debug!("overlay content");
-        match options.mode {
-            FitMode::Image => {
-                let primary_component = vec![primary_component];
-                let components = match border {
-                    Some(ref mut border) => {
-                        border.resize_and_crop(border_size, ResizeMode::Contain)?;
-                        let default_image = primary.clone();
-                        let components = border.transparent_components();
-                        images.resize(components.len(), default_image);
-                        // border
-                        //     .transparent_components()
-                        components.iter().zip(images.iter_mut())
-                    }
-                    None => primary_component.iter().zip(images.iter_mut()),
-                };
-
-                for (idx, (component_rect, component)) in components.enumerate() {
-                    draw_component(&mut result_image, component, component_rect, &border_rect)
-                        .map_err(|err| RenderComponentError {
-                            idx,
-                            rect: *component_rect,
-                            size: component.size(),
-                            source: err.into(),
-                        })?;
-                }
-
-                if let Some(border) = border {
-                    result_image.overlay(&*border, border_rect.top_left());
-                }
-            }
-            FitMode::Border => {
-                let primary_component_rect = match border {
-                    Some(ref mut border) => {
-                        border.resize_and_crop(border_size, ResizeMode::Contain)?;
-                        border.content_rect().map_err(border::Error::from)?
-                    }
-                    None => &primary_component,
-                };
-
-                draw_component(
-                    &mut result_image,
-                    primary,
-                    primary_component_rect,
-                    &border_rect,
-                )
-                .map_err(|err| RenderComponentError {
-                    idx: 0,
-                    rect: *primary_component_rect,
-                    size: primary.size(),
-                    source: err.into(),
-                })?;
-                if let Some(border) = border {
-                    result_image.overlay(&*border, border_rect.top_left());
-                }
+        // in both modes, the windows of the border are filled in order
+        let (components, masks): (Vec<Rect>, Vec<_>) = match border {
+            Some(ref mut border) => {
+                border.resize_and_crop(border_size, ResizeMode::Contain)?;
+                let default_image = primary.clone();
+                let order = border.window_order(&options.window_order)?;
+                let masks = border.window_masks();
+                let components = border.transparent_components();
+                images.resize(components.len(), default_image);
+                order
+                    .into_iter()
+                    .map(|idx| (components[idx], masks.get(idx).cloned().flatten()))
+                    .unzip()
             }
+            None => (vec![primary_component], vec![None]),
         };
+        let components = components.iter().zip(images.iter_mut());
+
+        for (idx, (component_rect, component)) in components.enumerate() {
+            draw_component(
+                &mut result_image,
+                component,
+                component_rect,
+                &border_rect,
+                self.transform(idx),
+                self.fit(idx).unwrap_or(&options.window_fit),
+                masks.get(idx).and_then(Option::as_ref),
+            )
+            .map_err(|err| RenderComponentError {
+                idx,
+                rect: *component_rect,
+                size: component.size(),
+                source: err.into(),
+            })?;
+        }
+
+        if let Some(border) = border {
+            result_image.overlay(&*border, border_rect.top_left());
+        }
+
+        if let Some(corners) = content_corners {
+            corners
+                .restore(&mut result_image)
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to restore content corners".into(),
+                    source: err,
+                })?;
+        }
+
+        if let Some((caption, text)) = caption {
+            caption.draw(
+                &mut result_image,
+                &text,
+                &content_rect,
+                &content_rect_sub_margins,
+            )?;
+        }
 
         if options.preview {
             overlay_visible_area(&mut result_image)?;
@@ -246,7 +372,6 @@ fn compute_pre_result_size(
     options: &Options,
 ) -> Result<ResultSize, ResultSizeError> {
     let scale_factor = options.scale_factor.clamp(0.0, 1.0);
-    let margin_factor = f64::from(options.margin).max(0.0);
 
     let original_content_size = match border {
         Some(border) => match options.mode {
@@ -270,16 +395,38 @@ fn compute_pre_result_size(
             })?;
     debug!(&frame_width);
 
-    let margin = (|| {
-        let margin = CheckedMul::checked_mul(margin_factor, f64::from(base))?;
-        let margin = margin.cast::<u32>()?;
-        Ok::<_, arithmetic::Error>(margin)
+    // leave enough space for the shadow
+    let margin_factors = match options.shadow {
+        Some(shadow) => {
+            let extent = shadow.extent();
+            SidesPercent {
+                top: options.margin.top.max(extent.top),
+                left: options.margin.left.max(extent.left),
+                bottom: options.margin.bottom.max(extent.bottom),
+                right: options.margin.right.max(extent.right),
+            }
+        }
+        None => options.margin,
+    };
+
+    let margins = (|| {
+        let margin = |factor: f32| {
+            let factor = f64::from(factor).max(0.0);
+            let margin = CheckedMul::checked_mul(factor, f64::from(base))?;
+            let margin = margin.cast::<u32>()?;
+            Ok::<_, arithmetic::Error>(margin)
+        };
+        Ok::<_, arithmetic::Error>(Sides {
+            top: margin(margin_factors.top)?,
+            left: margin(margin_factors.left)?,
+            bottom: margin(margin_factors.bottom)?,
+            right: margin(margin_factors.right)?,
+        })
     })();
-    let margin = margin.map_err(|err| error::Arithmetic {
+    let margins = margins.map_err(|err| error::Arithmetic {
         msg: "failed to compute original margin width".to_string(),
         source: err,
     })?;
-    let margins = Sides::uniform(margin);
     debug!(&margins);
 
     let content_size = original_content_size
@@ -291,6 +438,43 @@ fn compute_pre_result_size(
         })?;
     debug!(&content_size);
 
+    // grow the margins on one axis to match the aspect ratio
+    let (content_size, margins) = match options.aspect_ratio {
+        Some(aspect_ratio) => {
+            let expanded_size =
+                aspect_ratio
+                    .expand(content_size)
+                    .map_err(|err| error::Arithmetic {
+                        msg: "failed to expand content size to aspect ratio".to_string(),
+                        source: err.into(),
+                    })?;
+            let placement = expanded_size
+                .anchor(content_size, options.anchor)
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to place content in expanded size".to_string(),
+                    source: err.into(),
+                })?;
+            let margins = (|| {
+                let top_left = placement.top_left();
+                let bottom_right =
+                    Point::from(expanded_size).checked_sub(placement.bottom_right())?;
+                Ok::<_, arithmetic::Error>(Sides {
+                    top: CheckedAdd::checked_add(margins.top, top_left.y.cast::<u32>()?)?,
+                    left: CheckedAdd::checked_add(margins.left, top_left.x.cast::<u32>()?)?,
+                    bottom: CheckedAdd::checked_add(margins.bottom, bottom_right.y.cast::<u32>()?)?,
+                    right: CheckedAdd::checked_add(margins.right, bottom_right.x.cast::<u32>()?)?,
+                })
+            })();
+            let margins = margins.map_err(|err| error::Arithmetic {
+                msg: "failed to grow margins to aspect ratio".to_string(),
+                source: err,
+            })?;
+            (expanded_size, margins)
+        }
+        None => (content_size, margins),
+    };
+    debug!(&content_size);
+
     let default_output_size = content_size
         .scale_by::<_, Round>(1.0 / scale_factor)
         .map_err(|err| error::Arithmetic {
@@ -319,6 +503,18 @@ fn compute_pre_result_size(
             msg: "failed to bound output size".to_string(),
             source: err.into(),
         })?;
+    // shrink to the largest size with the aspect ratio
+    let output_size = match options.aspect_ratio {
+        Some(aspect_ratio) => {
+            aspect_ratio
+                .fit_within(output_size)
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to fit output size to aspect ratio".to_string(),
+                    source: err.into(),
+                })?
+        }
+        None => output_size,
+    };
 
     debug!(&output_size);
     Ok(ResultSize {
@@ -409,8 +605,7 @@ fn border_for_primary(
 
     if let Some(ref mut border) = border {
         if let FitMode::Border = options.mode {
-            *border = Border::custom(border.clone(), primary.size(), None)
-                .map_err(border::Error::from)?;
+            *border = Border::custom(border.clone(), primary.size(), None)?;
         }
     }
     Ok(border)
@@ -518,6 +713,9 @@ fn draw_component(
     component: &mut img::Image,
     component_rect: &Rect,
     border_rect: &Rect,
+    transform: Option<&Transform>,
+    fit: &window::Fit,
+    mask: Option<&border::Mask>,
 ) -> Result<(), RenderError> {
     debug!("drawing", &component_rect);
 
@@ -581,18 +779,45 @@ fn draw_component(
         image.overlay(&component, uncropped_component_top_left);
     }
 
-    component
-        .resize_and_crop(
-            component_size,
-            ResizeMode::Cover,
-            CropMode::Custom {
-                x: center_offset.x,
-                y: center_offset.y,
-            },
-        )
-        .map_err(img::Error::from)?;
+    let crop_mode = match transform {
+        Some(transform) => {
+            let source_rect = transform
+                .source_rect(component.size(), component_size)
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to compute source rect of component".into(),
+                    source: err.into(),
+                })?;
+            debug!(&source_rect);
+            component
+                .crop(&source_rect)
+                .map_err(img::CropError::from)
+                .map_err(img::Error::from)?;
+            CropMode::Center
+        }
+        None => CropMode::Custom {
+            x: center_offset.x,
+            y: center_offset.y,
+        },
+    };
+
+    fit.apply(component, component_size, crop_mode)?;
     assert_eq!(component_size, component.size());
 
+    // only fill the window's own opening, the border drawn on top
+    // provides the soft edges
+    if let Some(mask) = mask {
+        let offset = component_rect.top_left();
+        let border_offset = border_rect.top_left();
+        for (x, y, pixel) in component.inner.enumerate_pixels_mut() {
+            let point = Point {
+                x: offset.x + i64::from(x) - border_offset.x,
+                y: offset.y + i64::from(y) - border_offset.y,
+            };
+            if mask.coverage_at(point) == 0 {
+                pixel.0[3] = 0;
+            }
+        }
+    }
     image.overlay(component, component_rect.top_left());
     Ok(())
 }
@@ -654,6 +879,34 @@ pub enum RenderError {
 
     #[error(transparent)]
     Border(#[from] border::Error),
+
+    #[error("failed to render background")]
+    Background(
+        #[from]
+        #[source]
+        background::Error,
+    ),
+
+    #[error("failed to fit image into window")]
+    Fit(
+        #[from]
+        #[source]
+        window::Error,
+    ),
+
+    #[error("failed to render film markings")]
+    Markings(
+        #[from]
+        #[source]
+        markings::Error,
+    ),
+
+    #[error("failed to render caption")]
+    Caption(
+        #[from]
+        #[source]
+        caption::Error,
+    ),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -682,11 +935,13 @@ pub enum Error {
 #[cfg(test)]
 mod tests {
     use super::border::{self, Border};
+    use super::{
+        background, frame, markings, procedural, shadow, types, window, ImageBorders, Layout,
+        Options,
+    };
     #[cfg(feature = "builtin")]
     use super::{builtin, ImageFormat};
-    use super::{types, ImageBorders, Options};
     use anyhow::Result;
-    #[cfg(feature = "builtin")]
     use std::io::Cursor;
     use std::path::PathBuf;
 
@@ -702,7 +957,7 @@ mod tests {
             scale_factor: 0.90,
             // frame_width: types::sides::percent::Sides::uniform(0.02),
             frame_width: types::sides::percent::Sides::uniform(0.1),
-            margin: 0.1,
+            margin: types::sides::percent::Sides::uniform(0.1),
             // image_rotation: types::Rotation::Rotate90,
             ..Default::default()
         };
@@ -770,6 +1025,452 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_render_with_transform() -> Result<()> {
+        // left half is red, right half is blue
+        let image = image::RgbaImage::from_fn(200, 100, |x, _| {
+            if x < 100 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        });
+        let image = super::Image::from_image(&image::DynamicImage::ImageRgba8(image));
+        let options = Options {
+            margin: types::sides::percent::Sides::uniform(0.0),
+            frame_width: types::sides::percent::Sides::uniform(0.0),
+            ..Default::default()
+        };
+        let mut borders = ImageBorders::single(image);
+        let center = |borders: &mut ImageBorders| -> Result<image::Rgba<u8>> {
+            let result = borders.render(None, &options)?;
+            Ok(*result.get_pixel(result.width() / 2, result.height() / 2))
+        };
+
+        borders.set_transform(
+            0,
+            types::Transform::Focus {
+                x: 0.25,
+                y: 0.5,
+                zoom: 2.0,
+            },
+        );
+        assert_eq!(center(&mut borders)?, image::Rgba([255, 0, 0, 255]));
+        borders.set_transform(
+            0,
+            types::Transform::Region {
+                x: 0.5,
+                y: 0.0,
+                width: 0.5,
+                height: 1.0,
+            },
+        );
+        assert_eq!(center(&mut borders)?, image::Rgba([0, 0, 255, 255]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_round_windows() -> Result<()> {
+        // two round windows whose bounding boxes overlap
+        let border = image::RgbaImage::from_fn(170, 160, |x, y| {
+            let distance = |cx: f64, cy: f64| (f64::from(x) - cx).hypot(f64::from(y) - cy);
+            let distance = distance(50.0, 50.0).min(distance(110.0, 110.0));
+            let alpha = if distance < 40.0 { 0 } else { 255 };
+            image::Rgba([0, 0, 0, alpha])
+        });
+        let border = Border::from_image(
+            super::Image::from_image(&border.into()),
+            Some(border::Options {
+                transparent_component_threshold: 1,
+                ..border::Options::default()
+            }),
+        )?;
+        let color = |color| {
+            let image = image::RgbaImage::from_pixel(200, 100, image::Rgba(color));
+            super::Image::from_image(&image.into())
+        };
+        let mut borders = ImageBorders::new([color([255, 0, 0, 255]), color([0, 0, 255, 255])])?;
+        let options = Options {
+            margin: types::sides::percent::Sides::uniform(0.0),
+            frame_width: types::sides::percent::Sides::uniform(0.0),
+            ..Default::default()
+        };
+        let result = borders.render(Some(border::Kind::Custom(border)), &options)?;
+
+        // each photo only fills its own opening, so the photos are
+        // separated along the diagonal between the openings
+        let diagonal = |color: [u8; 4]| {
+            let pixels = result.inner.enumerate_pixels();
+            let positions = pixels.filter(|(_, _, pixel)| pixel.0 == color);
+            let positions: Vec<u32> = positions.map(|(x, y, _)| x + y).collect();
+            let min = positions.iter().min().copied().unwrap_or(0);
+            let max = positions.iter().max().copied().unwrap_or(0);
+            (min, max)
+        };
+        let red = diagonal([255, 0, 0, 255]);
+        let blue = diagonal([0, 0, 255, 255]);
+        assert!(red.1 < blue.0 || blue.1 < red.0, "{red:?} {blue:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_all_windows_fit_border() -> Result<()> {
+        let repo: PathBuf = env!("CARGO_MANIFEST_DIR").into();
+        let border_file = repo.join("samples/borders/border_3_areas_vertical.png");
+        let border = border::Kind::Custom(Border::open(&border_file, None)?);
+        let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+        let images = colors.map(|color| {
+            let image = image::RgbaImage::from_pixel(300, 200, image::Rgba(color));
+            super::Image::from_image(&image.into())
+        });
+        let mut borders = ImageBorders::new(images)?;
+        let options = Options {
+            mode: types::FitMode::Border,
+            margin: types::sides::percent::Sides::uniform(0.0),
+            frame_width: types::sides::percent::Sides::uniform(0.0),
+            ..Default::default()
+        };
+        let result = borders.render(Some(border), &options)?;
+
+        // every stretched window shows its own image, the primary
+        // image fills the main window
+        let count = |color: [u8; 4]| result.pixels().filter(|pixel| pixel.0 == color).count();
+        let counts = colors.map(count);
+        assert!(counts.iter().all(|count| *count > 0), "{counts:?}");
+        assert!(counts[0] > counts[1] + counts[2], "{counts:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_contained_window() -> Result<()> {
+        let border = image::RgbaImage::from_fn(100, 100, |x, y| {
+            let inside = (20..80).contains(&x) && (20..80).contains(&y);
+            image::Rgba([0, 0, 0, if inside { 0 } else { 255 }])
+        });
+        let border = Border::from_image(super::Image::from_image(&border.into()), None)?;
+        // 2:3 photo with blue bands at the top and bottom
+        let photo = image::RgbaImage::from_fn(200, 300, |_, y| {
+            if (20..280).contains(&y) {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        });
+        let mut borders = ImageBorders::single(super::Image::from_image(&photo.into()));
+        let mut options = Options {
+            margin: types::sides::percent::Sides::uniform(0.0),
+            frame_width: types::sides::percent::Sides::uniform(0.0),
+            ..Default::default()
+        };
+        let count = |result: &super::Image, color: [u8; 4]| {
+            result.pixels().filter(|pixel| pixel.0 == color).count()
+        };
+        let blue = [0, 0, 255, 255];
+        let white = [255, 255, 255, 255];
+
+        let kind = || Some(border::Kind::Custom(border.clone()));
+        let result = borders.render(kind(), &options)?;
+        assert_eq!(count(&result, blue), 0);
+
+        options.window_fit = window::Fit {
+            mode: window::Mode::Contain,
+            color: types::Color::white(),
+            ..window::Fit::default()
+        };
+        let result = borders.render(kind(), &options)?;
+        assert!(count(&result, blue) > 0);
+        assert!(count(&result, white) > 0);
+
+        // the fit of an image takes precedence over the options
+        borders.set_fit(0, window::Fit::default());
+        let result = borders.render(kind(), &options)?;
+        assert_eq!(count(&result, blue), 0);
+        assert_eq!(count(&result, white), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_frame_style() -> Result<()> {
+        let image = image::RgbaImage::from_pixel(100, 100, image::Rgba([255, 0, 0, 255]));
+        let image = super::Image::from_image(&image::DynamicImage::ImageRgba8(image));
+        let options = Options {
+            margin: types::sides::percent::Sides::uniform(0.1),
+            frame_width: types::sides::percent::Sides::uniform(0.1),
+            background_color: Some(types::Color::white()),
+            frame_style: Some(frame::Style {
+                radius: 0.2,
+                content_radius: 0.2,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut borders = ImageBorders::single(image);
+        let result = borders.render(None, &options)?;
+        let white = image::Rgba([255, 255, 255, 255]);
+        let black = image::Rgba([0, 0, 0, 255]);
+        let red = image::Rgba([255, 0, 0, 255]);
+        let center = result.height() / 2;
+        let frame = (0..result.width())
+            .find(|&x| *result.get_pixel(x, center) != white)
+            .unwrap();
+        let content = (0..result.width())
+            .find(|&x| *result.get_pixel(x, center) == red)
+            .unwrap();
+        assert!(frame > 0 && content > frame);
+        assert_eq!(*result.get_pixel(frame, frame), white);
+        assert_eq!(*result.get_pixel(center, frame), black);
+        assert_eq!(*result.get_pixel(content, content), black);
+        assert_eq!(*result.get_pixel(center, content), red);
+        assert_eq!(*result.get_pixel(center, center), red);
+        Ok(())
+    }
+
+    macro_rules! procedural_border_tests {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<()> {
+                    let (mode, size): (types::FitMode, (u32, u32)) = $values;
+                    let image = image::RgbaImage::from_pixel(size.0, size.1, image::Rgba([255, 0, 0, 255]));
+                    let image = super::Image::from_image(&image::DynamicImage::ImageRgba8(image));
+                    let film = procedural::Film {
+                        irregularity: 0.0,
+                        ..procedural::Film::new(procedural::Format::Film35mm)
+                    };
+                    let options = Options {
+                        mode,
+                        margin: types::sides::percent::Sides::uniform(0.0),
+                        frame_width: types::sides::percent::Sides::uniform(0.0),
+                        ..Default::default()
+                    };
+                    let mut borders = ImageBorders::single(image);
+                    let result = borders.render(border::Kind::Procedural(film), &options)?;
+                    let (width, height) = (result.width(), result.height());
+                    assert_eq!(*result.get_pixel(width / 2, height / 2), image::Rgba([255, 0, 0, 255]));
+                    assert_eq!(*result.get_pixel(0, 0), image::Rgba([0, 0, 0, 255]));
+
+                    // sprocket holes have crisp edges at any size
+                    let rebate = height.min(width) * 8 / 100;
+                    let pixel = |along: u32| if width >= height {
+                        result.get_pixel(along, rebate)[0]
+                    } else {
+                        result.get_pixel(rebate, along)[0]
+                    };
+                    let edge = (0..width.max(height)).find(|&along| pixel(along) > 0).unwrap();
+                    assert_eq!(pixel(edge + 1), 255);
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    procedural_border_tests! {
+        test_render_procedural_border_fit_image: (types::FitMode::Image, (600, 400)),
+        test_render_procedural_border_fit_border: (types::FitMode::Border, (400, 600)),
+    }
+
+    #[test]
+    fn test_render_svg_border() -> Result<()> {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="120" height="80">
+  <rect width="120" height="80" fill="#000000"/>
+  <rect id="window" x="10" y="10" width="100" height="60" fill="#ffffff"/>
+</svg>"##;
+        let border = Border::from_reader(Cursor::new(svg.as_bytes()), None)?;
+        let image = image::RgbaImage::from_pixel(1200, 800, image::Rgba([255, 0, 0, 255]));
+        let image = super::Image::from_image(&image::DynamicImage::ImageRgba8(image));
+        let options = Options {
+            margin: types::sides::percent::Sides::uniform(0.0),
+            frame_width: types::sides::percent::Sides::uniform(0.0),
+            ..Default::default()
+        };
+        let mut borders = ImageBorders::single(image);
+        let result = borders.render(border::Kind::Custom(border), &options)?;
+        assert!(result.width() > 1200 && result.height() > 800);
+
+        // the window edge is as sharp as the vector border
+        let row = result.height() / 2;
+        let edge = (0..result.width())
+            .find(|&x| result.get_pixel(x, row)[0] > 0)
+            .unwrap();
+        assert_eq!(
+            *result.get_pixel(edge - 1, row),
+            image::Rgba([0, 0, 0, 255])
+        );
+        assert_eq!(
+            *result.get_pixel(edge + 1, row),
+            image::Rgba([255, 0, 0, 255])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_shadow() -> Result<()> {
+        let image = image::RgbaImage::from_pixel(100, 100, image::Rgba([255, 0, 0, 255]));
+        let image = super::Image::from_image(&image::DynamicImage::ImageRgba8(image));
+        let options = Options {
+            margin: types::sides::percent::Sides::uniform(0.0),
+            frame_width: types::sides::percent::Sides::uniform(0.0),
+            background_color: Some(types::Color::white()),
+            shadow: Some(shadow::Shadow {
+                offset_x: 0.0,
+                offset_y: 0.1,
+                blur: 0.1,
+                spread: 0.0,
+                color: types::Color::black(),
+                opacity: 1.0,
+            }),
+            ..Default::default()
+        };
+        let mut borders = ImageBorders::single(image);
+        let result = borders.render(None, &options)?;
+        // the shadow is not clipped
+        assert_eq!((result.width(), result.height()), (120, 120));
+        assert_eq!(*result.get_pixel(60, 50), image::Rgba([255, 0, 0, 255]));
+        assert!(result.get_pixel(0, 0)[1] > 250);
+        assert!(result.get_pixel(60, 119)[1] > 250);
+        assert!(result.get_pixel(60, 105)[1] < 64);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_blurred_background() -> Result<()> {
+        let image = image::RgbaImage::from_pixel(100, 100, image::Rgba([200, 0, 0, 255]));
+        let image = super::Image::from_image(&image::DynamicImage::ImageRgba8(image));
+        let options = Options {
+            margin: types::sides::percent::Sides::uniform(0.2),
+            frame_width: types::sides::percent::Sides::uniform(0.0),
+            background: background::Background {
+                mode: background::Mode::Blur,
+                darken: 0.5,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut borders = ImageBorders::single(image);
+        let result = borders.render(None, &options)?;
+        assert_eq!(*result.get_pixel(0, 0), image::Rgba([100, 0, 0, 255]));
+        assert_eq!(*result.get_pixel(70, 70), image::Rgba([200, 0, 0, 255]));
+
+        let options = Options {
+            background: background::Background {
+                mode: background::Mode::Texture,
+                ..Default::default()
+            },
+            ..options
+        };
+        assert!(matches!(
+            borders.render(None, &options),
+            Err(super::RenderError::Background(
+                background::Error::MissingImage(_)
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_markings() -> Result<()> {
+        let image = image::RgbaImage::from_pixel(300, 200, image::Rgba([255, 0, 0, 255]));
+        let image = super::Image::from_image(&image::DynamicImage::ImageRgba8(image));
+        let markings = markings::Markings {
+            stock: "PORTRA 400".to_string(),
+            rebate_color: types::Color::black(),
+            color: types::Color::white(),
+            ..Default::default()
+        };
+        let options = Options {
+            margin: types::sides::percent::Sides::uniform(0.2),
+            frame_width: types::sides::percent::Sides::uniform(0.0),
+            background_color: Some(types::Color::rgb(0, 0, 255)),
+            markings: Some(markings),
+            ..Default::default()
+        };
+        let mut borders = ImageBorders::single(image);
+        let result = borders.render(None, &options)?;
+        assert_eq!((result.width(), result.height()), (380, 280));
+        assert_eq!(*result.get_pixel(190, 140), image::Rgba([255, 0, 0, 255]));
+        // the rebate replaces the background color
+        assert_eq!(*result.get_pixel(2, 2), image::Rgba([0, 0, 0, 255]));
+        let marked = |top: u32| {
+            (top..top + 40)
+                .flat_map(|y| (0..380).map(move |x| (x, y)))
+                .filter(|(x, y)| result.get_pixel(*x, *y)[1] > 128)
+                .count()
+        };
+        assert!(marked(0) > 100);
+        assert!(marked(240) > 100);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_polaroid_layout() -> Result<()> {
+        let image = image::RgbaImage::from_pixel(100, 100, image::Rgba([255, 0, 0, 255]));
+        let image = super::Image::from_image(&image::DynamicImage::ImageRgba8(image));
+        let mut options = Options {
+            frame_width: types::sides::percent::Sides::uniform(0.0),
+            background_color: Some(types::Color::white()),
+            ..Default::default()
+        };
+        options.apply_layout(Layout::Polaroid);
+        let mut borders = ImageBorders::single(image);
+        let result = borders.render(None, &options)?;
+        let white = image::Rgba([255, 255, 255, 255]);
+        let red = image::Rgba([255, 0, 0, 255]);
+        let column = result.width() / 2;
+        let first_red = (0..result.height())
+            .find(|&y| *result.get_pixel(column, y) == red)
+            .unwrap();
+        let last_red = (0..result.height())
+            .rev()
+            .find(|&y| *result.get_pixel(column, y) == red)
+            .unwrap();
+        assert_eq!(*result.get_pixel(column, 0), white);
+        assert!(result.height() - last_red > 3 * first_red);
+        Ok(())
+    }
+
+    macro_rules! aspect_ratio_tests {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<()> {
+                    let (mode, aspect_ratio, output_size, expected): (_, _, _, (u32, u32)) = $values;
+                    let repo: PathBuf = env!("CARGO_MANIFEST_DIR").into();
+                    let input = repo.join("samples/lowres.jpg");
+                    let border = border::Kind::Custom(Border::open(
+                        repo.join("samples/borders/border1.png"),
+                        None,
+                    )?);
+                    let options = Options {
+                        mode,
+                        aspect_ratio: Some(aspect_ratio),
+                        output_size,
+                        margin: types::sides::percent::Sides::uniform(0.05),
+                        ..Default::default()
+                    };
+                    let mut borders = ImageBorders::single(super::Image::open(&input)?);
+                    let result = borders.render(Some(border), &options)?;
+                    assert_eq!((result.width(), result.height()), expected);
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    aspect_ratio_tests! {
+        test_render_portrait_fit_image: (
+            types::FitMode::Image, types::AspectRatio::portrait(),
+            types::BoundedSize { width: Some(1080), height: None }, (1080, 1350)),
+        test_render_portrait_fit_border: (
+            types::FitMode::Border, types::AspectRatio::portrait(),
+            types::BoundedSize { width: Some(1080), height: None }, (1080, 1350)),
+        test_render_story_fit_image: (
+            types::FitMode::Image, types::AspectRatio::story(),
+            types::BoundedSize { width: None, height: Some(1920) }, (1080, 1920)),
+        test_render_square_explicit_size: (
+            types::FitMode::Image, types::AspectRatio::square(),
+            types::BoundedSize { width: Some(1000), height: Some(800) }, (800, 800)),
+    }
+
     #[test]
     fn test_custom_border() -> Result<()> {
         let repo: PathBuf = env!("CARGO_MANIFEST_DIR").into();
@@ -0,0 +1,530 @@
+use super::arithmetic::{self, Cast};
+use super::caption::{self, FontWeight};
+use super::config;
+use super::types::{Color, Point, Rect, Rotation};
+use super::{error, imageops, img};
+use rusttype::Font;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use wasm_bindgen::prelude::*;
+
+/// DX number of a film stock, printed as a barcode on the film edge
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub struct DxNumber {
+    /// Product number (0 to 127)
+    pub product: u8,
+    /// Generation of the product (0 to 15)
+    pub generation: u8,
+}
+
+impl DxNumber {
+    /// Bits of the data track of the barcode
+    ///
+    /// Modeled after the DX film edge barcode: a start pattern, the product
+    /// number, a separator, the generation, the frame number, a half frame
+    /// flag, an even parity bit and a stop pattern.
+    #[inline]
+    #[must_use]
+    pub fn bits(self, frame_number: u32) -> Vec<bool> {
+        let number = |value: u32, len: u32| (0..len).rev().map(move |bit| value >> bit & 1 == 1);
+        let data: Vec<bool> = number(u32::from(self.product), 7)
+            .chain([false])
+            .chain(number(u32::from(self.generation), 4))
+            .chain(number(frame_number % 64, 6))
+            .chain([false])
+            .collect();
+        let parity = data.iter().filter(|bit| **bit).count() % 2 == 1;
+        [true, false, true, false]
+            .into_iter()
+            .chain(data)
+            .chain([parity])
+            .chain([false, true, false, true])
+            .collect()
+    }
+}
+
+impl std::str::FromStr for DxNumber {
+    type Err = ParseError;
+
+    /// Parses `PRODUCT-GENERATION` such as `115-4`
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::InvalidDx(s.to_string());
+        let (product, generation) = s.split_once('-').ok_or_else(invalid)?;
+        let product: u8 = product.trim().parse().map_err(|_| invalid())?;
+        let generation: u8 = generation.trim().parse().map_err(|_| invalid())?;
+        if product > 127 || generation > 15 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            product,
+            generation,
+        })
+    }
+}
+
+/// Film rebate with edge markings drawn into the margins
+///
+/// The markings run along the longer side of the content: the stock
+/// name on one edge, frame numbers with arrows and the DX barcode on
+/// the other.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(default)]
+pub struct Markings {
+    #[wasm_bindgen(getter_with_clone)]
+    pub stock: String,
+    pub frame_number: u32,
+    pub dx: Option<DxNumber>,
+    pub rebate_color: Color,
+    pub color: Color,
+    pub weight: FontWeight,
+}
+
+impl Default for Markings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            stock: String::new(),
+            frame_number: 1,
+            dx: None,
+            rebate_color: Color::rgb(28, 20, 16),
+            color: Color::rgb(240, 160, 72),
+            weight: FontWeight::Bold,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Markings {
+    #[wasm_bindgen(constructor)]
+    #[inline]
+    #[must_use]
+    pub fn new(stock: String) -> Self {
+        Self {
+            stock,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Edge {
+    Stock,
+    Numbers,
+}
+
+impl Markings {
+    /// Markings of the frame `offset` frames after this one
+    #[inline]
+    #[must_use]
+    pub fn for_frame(&self, offset: u32) -> Self {
+        Self {
+            frame_number: self.frame_number.saturating_add(offset),
+            ..self.clone()
+        }
+    }
+
+    /// Uses the name, DX number and colors of `stock`
+    #[inline]
+    pub fn apply_stock(&mut self, stock: &Stock) {
+        self.stock = stock.name.clone();
+        self.dx = stock.dx.or(self.dx);
+        self.rebate_color = stock.rebate_color.unwrap_or(self.rebate_color);
+        self.color = stock.color.unwrap_or(self.color);
+    }
+
+    /// Fills the rebate between `content_rect` and `inner_rect` and
+    /// draws the markings into it
+    #[inline]
+    pub fn draw(
+        &self,
+        image: &mut img::Image,
+        content_rect: &Rect,
+        inner_rect: &Rect,
+    ) -> Result<(), Error> {
+        image.fill_rect(self.rebate_color, content_rect, imageops::FillMode::Set)?;
+        let font = Font::try_from_bytes(self.weight.font_data()).ok_or(Error::Font(self.weight))?;
+
+        let strips = if content_rect.width() >= content_rect.height() {
+            [
+                (
+                    Edge::Stock,
+                    None,
+                    Rect::from_points(
+                        content_rect.top_left(),
+                        Point {
+                            x: content_rect.right,
+                            y: inner_rect.top,
+                        },
+                    ),
+                ),
+                (
+                    Edge::Numbers,
+                    None,
+                    Rect::from_points(
+                        Point {
+                            x: content_rect.left,
+                            y: inner_rect.bottom,
+                        },
+                        content_rect.bottom_right(),
+                    ),
+                ),
+            ]
+        } else {
+            [
+                (
+                    Edge::Stock,
+                    Some(Rotation::Rotate270),
+                    Rect::from_points(
+                        content_rect.top_left(),
+                        Point {
+                            x: inner_rect.left,
+                            y: content_rect.bottom,
+                        },
+                    ),
+                ),
+                (
+                    Edge::Numbers,
+                    Some(Rotation::Rotate90),
+                    Rect::from_points(
+                        Point {
+                            x: inner_rect.right,
+                            y: content_rect.top,
+                        },
+                        content_rect.bottom_right(),
+                    ),
+                ),
+            ]
+        };
+
+        for (edge, rotation, rect) in strips {
+            let (length, thickness) = match rotation {
+                Some(_) => (rect.height(), rect.width()),
+                None => (rect.width(), rect.height()),
+            };
+            let (Ok(length), Ok(thickness)) = (u32::try_from(length), u32::try_from(thickness))
+            else {
+                continue;
+            };
+            let mut strip = self
+                .draw_strip(&font, edge, length, thickness)
+                .map_err(|err| error::Arithmetic {
+                    msg: "failed to draw film markings".into(),
+                    source: err,
+                })?;
+            if let Some(rotation) = rotation {
+                strip.rotate(&rotation);
+            }
+            image.overlay(&strip, rect.top_left());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn draw_strip(
+        &self,
+        font: &Font<'_>,
+        edge: Edge,
+        length: u32,
+        thickness: u32,
+    ) -> Result<img::Image, arithmetic::Error> {
+        let mut strip = img::Image::new(length, thickness);
+        let (length, thickness) = (length.cast::<f32>()?, thickness.cast::<f32>()?);
+        let text_height = thickness * 0.5;
+        // markings would not be legible
+        if text_height < 4.0 {
+            return Ok(strip);
+        }
+        let mut label = |text: &str, x: f32, arrow: bool| {
+            if text.is_empty() {
+                return Ok::<_, arithmetic::Error>(());
+            }
+            let label = caption::render_text(font, text, text_height, self.color)?;
+            let y = (thickness - label.height().cast::<f32>()?) / 2.0;
+            strip.overlay(&label, Point::from((x.cast::<i64>()?, y.cast::<i64>()?)));
+            if arrow {
+                let x = x + label.width().cast::<f32>()? + text_height * 0.2;
+                draw_arrow(
+                    &mut strip,
+                    x,
+                    thickness / 2.0,
+                    text_height * 0.6,
+                    self.color,
+                )?;
+            }
+            Ok(())
+        };
+        match edge {
+            Edge::Stock => {
+                label(&self.stock, length * 0.08, false)?;
+                label(&self.stock, length * 0.58, false)?;
+            }
+            Edge::Numbers => {
+                label(&self.frame_number.to_string(), length * 0.08, true)?;
+                label(&format!("{}A", self.frame_number), length * 0.72, true)?;
+                if let Some(dx) = self.dx {
+                    let bits = dx.bits(self.frame_number);
+                    let module = (thickness * 0.1)
+                        .max(1.0)
+                        .min(length * 0.3 / bits.len().cast::<f32>()?);
+                    draw_barcode(&mut strip, &bits, length * 0.32, module, self.color)?;
+                }
+            }
+        }
+        Ok(strip)
+    }
+}
+
+/// Right-pointing triangle of `size` centered vertically at `y`
+#[inline]
+fn draw_arrow(
+    image: &mut img::Image,
+    x: f32,
+    y: f32,
+    size: f32,
+    color: Color,
+) -> Result<(), arithmetic::Error> {
+    let half = size / 2.0;
+    let left = x.max(0.0).cast::<u32>()?;
+    let right = (x + size * 0.9).max(0.0).ceil().cast::<u32>()?;
+    let top = (y - half).max(0.0).cast::<u32>()?;
+    let bottom = (y + half).max(0.0).ceil().cast::<u32>()?;
+    for py in top..bottom.min(image.height()) {
+        for px in left..right.min(image.width()) {
+            let (cx, cy) = (px.cast::<f32>()? + 0.5 - x, py.cast::<f32>()? + 0.5 - y);
+            // the triangle narrows from its full height at the base to the tip
+            if cx >= 0.0 && cy.abs() <= half * (1.0 - cx / (size * 0.9)) {
+                *image.get_pixel_mut(px, py) = color.into();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Barcode with a clock track on top of the data track
+#[inline]
+fn draw_barcode(
+    image: &mut img::Image,
+    bits: &[bool],
+    x: f32,
+    module: f32,
+    color: Color,
+) -> Result<(), arithmetic::Error> {
+    let height = image.height().cast::<f32>()?;
+    let bar = (module * 0.6).max(1.0).cast::<u32>()?;
+    let track = |top: f32, bottom: f32| {
+        Ok::<_, arithmetic::Error>((
+            (height * top).cast::<u32>()?,
+            (height * bottom).cast::<u32>()?,
+        ))
+    };
+    let clock = track(0.2, 0.45)?;
+    let data = track(0.55, 0.8)?;
+    for (idx, bit) in bits.iter().enumerate() {
+        let left = (x + idx.cast::<f32>()? * module).cast::<u32>()?;
+        let right = left.saturating_add(bar).min(image.width());
+        let tracks: &[(u32, u32)] = if *bit { &[clock, data] } else { &[clock] };
+        for &(top, bottom) in tracks {
+            for py in top..bottom.min(image.height()) {
+                for px in left..right {
+                    *image.get_pixel_mut(px, py) = color.into();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Film stock with defaults for its markings
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Stock {
+    pub name: String,
+    #[serde(default)]
+    pub dx: Option<DxNumber>,
+    #[serde(default)]
+    pub rebate_color: Option<Color>,
+    #[serde(default)]
+    pub color: Option<Color>,
+}
+
+/// Catalog of film stocks
+///
+/// ```toml
+/// [stocks.portra400]
+/// name = "PORTRA 400"
+/// dx = { product = 115, generation = 4 }
+/// rebate_color = "#2b1a12"
+/// color = "#f0a048"
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Catalog {
+    #[serde(default)]
+    pub stocks: HashMap<String, Stock>,
+}
+
+impl Catalog {
+    #[inline]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, config::Error> {
+        config::open(path)
+    }
+
+    #[inline]
+    pub fn parse(content: &str, format: config::Format) -> Result<Self, config::Error> {
+        config::parse(content, format)
+    }
+
+    /// Finds a stock by its key or name, ignoring case
+    #[inline]
+    #[must_use]
+    pub fn stock(&self, name: &str) -> Option<&Stock> {
+        self.stocks.get(name).or_else(|| {
+            self.stocks.iter().find_map(|(key, stock)| {
+                (key.eq_ignore_ascii_case(name) || stock.name.eq_ignore_ascii_case(name))
+                    .then_some(stock)
+            })
+        })
+    }
+}
+
+#[derive(thiserror::Error, PartialEq, Eq, Clone, Debug)]
+pub enum ParseError {
+    #[error("invalid DX number `{0}` (expected `PRODUCT-GENERATION` such as `115-4`)")]
+    InvalidDx(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to load Inter font with weight {0:?}")]
+    Font(FontWeight),
+
+    #[error("failed to fill film rebate")]
+    Fill(
+        #[from]
+        #[source]
+        img::FillError,
+    ),
+
+    #[error(transparent)]
+    Arithmetic(#[from] error::Arithmetic),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Catalog, DxNumber, Markings};
+    use crate::config::Format;
+    use crate::img::Image;
+    use crate::types::{Color, Rect};
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_dx_number() {
+        assert_eq!(
+            DxNumber::from_str("115-4").ok(),
+            Some(DxNumber {
+                product: 115,
+                generation: 4
+            })
+        );
+        assert!(DxNumber::from_str("128-4").is_err());
+        assert!(DxNumber::from_str("115-16").is_err());
+        assert!(DxNumber::from_str("115").is_err());
+    }
+
+    #[test]
+    fn test_dx_bits() {
+        let dx = DxNumber {
+            product: 115,
+            generation: 4,
+        };
+        let bits = dx.bits(12);
+        assert_eq!(bits.len(), 28);
+        assert_eq!(bits[..4], [true, false, true, false]);
+        assert_eq!(bits[24..], [false, true, false, true]);
+        // product 115 = 0b1110011
+        assert_eq!(bits[4..11], [true, true, true, false, false, true, true]);
+        // frame 12 = 0b001100
+        assert_eq!(bits[16..22], [false, false, true, true, false, false]);
+        let ones = bits[4..24].iter().filter(|bit| **bit).count();
+        assert_eq!(ones % 2, 0);
+        assert_ne!(dx.bits(12), dx.bits(13));
+    }
+
+    #[test]
+    fn test_catalog() -> Result<()> {
+        let catalog = Catalog::parse(
+            r##"
+[stocks.portra400]
+name = "PORTRA 400"
+dx = { product = 115, generation = 4 }
+rebate_color = "#2b1a12"
+
+[stocks.hp5]
+name = "HP5 PLUS"
+"##,
+            Format::Toml,
+        )?;
+        let stock = catalog.stock("Portra400").unwrap();
+        assert_eq!(stock.name, "PORTRA 400");
+        assert_eq!(catalog.stock("hp5 plus").map(|s| s.dx), Some(None));
+        assert!(catalog.stock("ektar").is_none());
+
+        let mut markings = Markings {
+            color: Color::white(),
+            ..Markings::default()
+        };
+        markings.apply_stock(stock);
+        assert_eq!(markings.stock, "PORTRA 400");
+        assert_eq!(markings.dx, DxNumber::from_str("115-4").ok());
+        assert_eq!(markings.rebate_color, Color::rgb(0x2b, 0x1a, 0x12));
+        assert_eq!(markings.color, Color::white());
+        assert_eq!(markings.for_frame(3).frame_number, 4);
+        Ok(())
+    }
+
+    macro_rules! draw_tests {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<()> {
+                    let ((width, height), inner, strips): ((u32, u32), ((i64, i64), (u32, u32)), [Rect; 2]) = $values;
+                    let mut image = Image::new(width, height);
+                    let markings = Markings {
+                        stock: "PORTRA 400".to_string(),
+                        frame_number: 12,
+                        dx: DxNumber::from_str("115-4").ok(),
+                        rebate_color: Color::black(),
+                        color: Color::white(),
+                        ..Markings::default()
+                    };
+                    let content_rect = Rect::from(image.size());
+                    let inner_rect = Rect::new(inner.0, inner.1)?;
+                    markings.draw(&mut image, &content_rect, &inner_rect)?;
+                    assert_eq!(*image.get_pixel(width / 2, height / 2), image::Rgba([0, 0, 0, 255]));
+                    for strip in strips {
+                        let marked = image
+                            .enumerate_pixels()
+                            .filter(|(x, y, _)| strip.contains(&(i64::from(*x), i64::from(*y)).into()))
+                            .filter(|(_, _, pixel)| pixel[0] > 128)
+                            .count();
+                        assert!(marked > 100, "only {} marked pixels in {:?}", marked, strip);
+                    }
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    draw_tests! {
+        test_draw_markings_landscape: (
+            (600, 400), ((0, 40), (600, 320)),
+            [Rect::new((0, 0), (600, 40))?, Rect::new((0, 360), (600, 40))?]),
+        test_draw_markings_portrait: (
+            (400, 600), ((40, 0), (320, 600)),
+            [Rect::new((0, 0), (40, 600))?, Rect::new((360, 0), (40, 600))?]),
+    }
+}
@@ -0,0 +1,783 @@
+use super::error;
+use super::types::{Rotation, Size};
+use exif::{Context, Field, In, Tag, Value};
+use image::ImageFormat;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Write};
+use wasm_bindgen::prelude::*;
+
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+const JPEG_APP0: u8 = 0xE0;
+const JPEG_APP1: u8 = 0xE1;
+const JPEG_APP2: u8 = 0xE2;
+const JPEG_SOS: u8 = 0xDA;
+const JPEG_EOI: u8 = 0xD9;
+const JPEG_EXIF_HEADER: &[u8] = b"Exif\0\0";
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const JPEG_ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+const JPEG_MAX_SEGMENT_SIZE: usize = u16::MAX as usize - 2;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+const PNG_ICC_PROFILE_NAME: &[u8] = b"ICC Profile";
+
+const TIFF_XMP_TAG: u16 = 700;
+const TIFF_ICC_TAG: u16 = 34675;
+const TIFF_EXTRA_SAMPLES_TAG: u16 = 338;
+
+// tags that describe the pixel layout of the source image
+const TIFF_LAYOUT_TAGS: &[u16] = &[
+    254, 255, 256, 257, 258, 259, 262, 266, 273, 277, 278, 279, 284, 317, 320, 322, 323, 324, 325,
+    330, 338, 339, 513, 514, 530, 532,
+];
+
+// tags that identify the owner of the camera or lens
+const EXIF_PRIVATE_TAGS: &[Tag] = &[
+    Tag::CameraOwnerName,
+    Tag::BodySerialNumber,
+    Tag::LensSerialNumber,
+];
+
+/// EXIF orientation of the stored pixels
+///
+/// Each orientation is the transformation that must be applied
+/// to the decoded pixels in order to display the image upright.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum Orientation {
+    Normal,
+    FlipHorizontal,
+    Rotate180,
+    FlipVertical,
+    Rotate90FlipHorizontal,
+    Rotate90,
+    Rotate270FlipHorizontal,
+    Rotate270,
+}
+
+impl Default for Orientation {
+    #[inline]
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl Orientation {
+    #[inline]
+    #[must_use]
+    pub fn from_exif(value: u32) -> Option<Self> {
+        match value {
+            1 => Some(Self::Normal),
+            2 => Some(Self::FlipHorizontal),
+            3 => Some(Self::Rotate180),
+            4 => Some(Self::FlipVertical),
+            5 => Some(Self::Rotate90FlipHorizontal),
+            6 => Some(Self::Rotate90),
+            7 => Some(Self::Rotate270FlipHorizontal),
+            8 => Some(Self::Rotate270),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn to_exif(self) -> u32 {
+        match self {
+            Self::Normal => 1,
+            Self::FlipHorizontal => 2,
+            Self::Rotate180 => 3,
+            Self::FlipVertical => 4,
+            Self::Rotate90FlipHorizontal => 5,
+            Self::Rotate90 => 6,
+            Self::Rotate270FlipHorizontal => 7,
+            Self::Rotate270 => 8,
+        }
+    }
+
+    /// Clockwise rotation that is applied before mirroring
+    #[inline]
+    #[must_use]
+    pub fn rotation(self) -> Rotation {
+        match self {
+            Self::Normal | Self::FlipHorizontal => Rotation::Rotate0,
+            Self::Rotate180 | Self::FlipVertical => Rotation::Rotate180,
+            Self::Rotate90 | Self::Rotate90FlipHorizontal => Rotation::Rotate90,
+            Self::Rotate270 | Self::Rotate270FlipHorizontal => Rotation::Rotate270,
+        }
+    }
+
+    /// Whether the rotated pixels must be mirrored horizontally
+    #[inline]
+    #[must_use]
+    pub fn is_mirrored(self) -> bool {
+        matches!(
+            self,
+            Self::FlipHorizontal
+                | Self::FlipVertical
+                | Self::Rotate90FlipHorizontal
+                | Self::Rotate270FlipHorizontal
+        )
+    }
+}
+
+/// Which metadata of the source image is kept in the output
+///
+/// `StripPrivate` removes GPS data, owner names and serial numbers
+/// as well as the XMP packet, which may repeat the location.
+/// `Strip` removes all EXIF and XMP data.
+/// The ICC profile is always kept, since it is required to
+/// display the colors correctly.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum Policy {
+    Preserve,
+    StripPrivate,
+    Strip,
+}
+
+impl Default for Policy {
+    #[inline]
+    fn default() -> Self {
+        Self::Preserve
+    }
+}
+
+impl std::str::FromStr for Policy {
+    type Err = error::ParseEnum;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "preserve" | "keep" => Ok(Self::Preserve),
+            "strip-private" | "private" => Ok(Self::StripPrivate),
+            "strip" | "none" => Ok(Self::Strip),
+            _ => Err(error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+/// EXIF, XMP and ICC metadata of an image
+#[derive(Clone, Debug, Default)]
+pub struct Metadata {
+    exif: Vec<Field>,
+    xmp: Option<Vec<u8>>,
+    icc_profile: Option<Vec<u8>>,
+    /// Whether the EXIF orientation was applied to the pixels
+    orientation_applied: bool,
+}
+
+impl Metadata {
+    /// Reads the metadata of an encoded JPEG, PNG or TIFF image
+    ///
+    /// Missing or malformed metadata is not an error, since it
+    /// must never prevent the image itself from being read.
+    #[inline]
+    #[must_use]
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let mut metadata = Self::default();
+        if let Ok(exif) = exif::Reader::new().read_from_container(&mut Cursor::new(data)) {
+            for field in exif.fields() {
+                if field.ifd_num != In::PRIMARY {
+                    // thumbnails are outdated once the image is rendered
+                    continue;
+                }
+                match field.tag {
+                    Tag(Context::Tiff, TIFF_XMP_TAG) => metadata.xmp = value_bytes(&field.value),
+                    Tag(Context::Tiff, TIFF_ICC_TAG) => {
+                        metadata.icc_profile = value_bytes(&field.value);
+                    }
+                    Tag(Context::Tiff, number) if TIFF_LAYOUT_TAGS.contains(&number) => {}
+                    _ => {
+                        if !matches!(field.value, Value::Unknown(..)) {
+                            metadata.exif.push(field.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        if data.starts_with(&JPEG_SOI) {
+            metadata.read_jpeg_segments(data);
+        } else if data.starts_with(PNG_SIGNATURE) {
+            metadata.read_png_chunks(data);
+        }
+        metadata
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn from_exif_fields(exif: Vec<Field>) -> Self {
+        Self {
+            exif,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.exif.is_empty() && self.xmp.is_none() && self.icc_profile.is_none()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn exif_fields(&self) -> &[Field] {
+        &self.exif
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn exif_field(&self, tag: Tag) -> Option<&Field> {
+        self.exif.iter().find(|field| field.tag == tag)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn xmp(&self) -> Option<&[u8]> {
+        self.xmp.as_deref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.icc_profile.as_deref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn orientation(&self) -> Option<Orientation> {
+        let field = self.exif_field(Tag::Orientation)?;
+        let value = field.value.get_uint(0)?;
+        Orientation::from_exif(value)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn orientation_applied(&self) -> bool {
+        self.orientation_applied
+    }
+
+    /// Marks the EXIF orientation as applied to the pixels
+    ///
+    /// The orientation is only reset when saving if it was applied,
+    /// otherwise viewers still need it to display the image upright.
+    #[inline]
+    pub fn set_orientation_applied(&mut self, applied: bool) {
+        self.orientation_applied = applied;
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        match policy {
+            Policy::Preserve => {}
+            Policy::StripPrivate => {
+                self.exif.retain(|field| {
+                    field.tag.context() != Context::Gps && !EXIF_PRIVATE_TAGS.contains(&field.tag)
+                });
+                self.xmp = None;
+            }
+            Policy::Strip => {
+                self.exif.clear();
+                self.xmp = None;
+            }
+        }
+        self
+    }
+
+    /// Embeds the metadata into an encoded JPEG or PNG image
+    ///
+    /// The EXIF dimensions are updated to `size` and the orientation
+    /// is reset if it was applied to the pixels.
+    #[inline]
+    pub fn embed(&self, encoded: &[u8], format: ImageFormat, size: Size) -> Result<Vec<u8>, Error> {
+        match format {
+            ImageFormat::Jpeg => self.embed_jpeg(encoded, size),
+            ImageFormat::Png => self.embed_png(encoded, size),
+            _ => Ok(encoded.to_vec()),
+        }
+    }
+
+    /// Encodes RGBA8 pixels as an uncompressed TIFF including the metadata
+    #[inline]
+    pub fn encode_tiff(&self, data: &[u8], size: Size) -> Result<Vec<u8>, Error> {
+        let tiff_field = |number: u16, value: Value| Field {
+            tag: Tag(Context::Tiff, number),
+            ifd_num: In::PRIMARY,
+            value,
+        };
+        let mut fields = vec![
+            tiff_field(256, Value::Long(vec![size.width])),
+            tiff_field(257, Value::Long(vec![size.height])),
+            tiff_field(258, Value::Short(vec![8, 8, 8, 8])),
+            tiff_field(259, Value::Short(vec![1])),
+            tiff_field(262, Value::Short(vec![2])),
+            tiff_field(277, Value::Short(vec![4])),
+            tiff_field(278, Value::Long(vec![size.height])),
+            tiff_field(284, Value::Short(vec![1])),
+            // unassociated alpha
+            tiff_field(TIFF_EXTRA_SAMPLES_TAG, Value::Short(vec![2])),
+        ];
+        fields.extend(self.exif_fields_for(size));
+        if let Some(xmp) = &self.xmp {
+            fields.push(tiff_field(TIFF_XMP_TAG, Value::Byte(xmp.clone())));
+        }
+        if let Some(icc_profile) = &self.icc_profile {
+            fields.push(tiff_field(
+                TIFF_ICC_TAG,
+                Value::Undefined(icc_profile.clone(), 0),
+            ));
+        }
+
+        let strips = [data];
+        let mut writer = exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        writer.set_strips(&strips, In::PRIMARY);
+        let mut buffer = Cursor::new(Vec::new());
+        writer.write(&mut buffer, cfg!(target_endian = "little"))?;
+        Ok(buffer.into_inner())
+    }
+
+    fn exif_fields_for(&self, size: Size) -> Vec<Field> {
+        if self.exif.is_empty() {
+            return Vec::new();
+        }
+        let mut updated = vec![Tag::PixelXDimension, Tag::PixelYDimension];
+        if self.orientation_applied {
+            updated.push(Tag::Orientation);
+        }
+        let mut fields: Vec<Field> = self
+            .exif
+            .iter()
+            .filter(|field| !updated.contains(&field.tag))
+            .cloned()
+            .collect();
+        if self.orientation_applied {
+            fields.push(Field {
+                tag: Tag::Orientation,
+                ifd_num: In::PRIMARY,
+                value: Value::Short(vec![1]),
+            });
+        }
+        fields.extend([
+            Field {
+                tag: Tag::PixelXDimension,
+                ifd_num: In::PRIMARY,
+                value: Value::Long(vec![size.width]),
+            },
+            Field {
+                tag: Tag::PixelYDimension,
+                ifd_num: In::PRIMARY,
+                value: Value::Long(vec![size.height]),
+            },
+        ]);
+        fields
+    }
+
+    fn encode_exif(&self, size: Size) -> Result<Option<Vec<u8>>, Error> {
+        let fields = self.exif_fields_for(size);
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        let mut writer = exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut buffer = Cursor::new(Vec::new());
+        writer.write(&mut buffer, false)?;
+        Ok(Some(buffer.into_inner()))
+    }
+
+    fn read_jpeg_segments(&mut self, data: &[u8]) {
+        let mut icc_chunks: Vec<(u8, &[u8])> = Vec::new();
+        let mut pos = JPEG_SOI.len();
+        while let Some(&[0xFF, marker]) = data.get(pos..pos + 2) {
+            match marker {
+                // fill byte
+                0xFF => {
+                    pos += 1;
+                    continue;
+                }
+                JPEG_SOS | JPEG_EOI => break,
+                // markers without a payload
+                0x01 | 0xD0..=0xD7 => {
+                    pos += 2;
+                    continue;
+                }
+                _ => {}
+            }
+            let length = match data.get(pos + 2..pos + 4) {
+                Some(&[high, low]) => usize::from(u16::from_be_bytes([high, low])),
+                _ => break,
+            };
+            let payload = match data.get(pos + 4..pos + 2 + length) {
+                Some(payload) if length >= 2 => payload,
+                _ => break,
+            };
+            match marker {
+                JPEG_APP1 if payload.starts_with(JPEG_XMP_HEADER) => {
+                    self.xmp = Some(payload[JPEG_XMP_HEADER.len()..].to_vec());
+                }
+                JPEG_APP2 if payload.starts_with(JPEG_ICC_HEADER) => {
+                    // the header is followed by the sequence number and chunk count
+                    if let Some(&sequence) = payload.get(JPEG_ICC_HEADER.len()) {
+                        if let Some(chunk) = payload.get(JPEG_ICC_HEADER.len() + 2..) {
+                            icc_chunks.push((sequence, chunk));
+                        }
+                    }
+                }
+                _ => {}
+            }
+            pos += 2 + length;
+        }
+
+        if !icc_chunks.is_empty() {
+            icc_chunks.sort_by_key(|(sequence, _)| *sequence);
+            self.icc_profile = Some(
+                icc_chunks
+                    .into_iter()
+                    .flat_map(|(_, c)| c)
+                    .copied()
+                    .collect(),
+            );
+        }
+    }
+
+    fn read_png_chunks(&mut self, data: &[u8]) {
+        let mut pos = PNG_SIGNATURE.len();
+        while let Some(header) = data.get(pos..pos + 8) {
+            let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+            let Ok(length) = usize::try_from(length) else {
+                break;
+            };
+            let Some(chunk) = data.get(pos + 8..pos + 8 + length) else {
+                break;
+            };
+            match &header[4..] {
+                b"iCCP" => {
+                    // profile name, compression method and zlib compressed profile
+                    if let Some((_, compressed)) = split_null(chunk) {
+                        self.icc_profile = compressed.get(1..).and_then(inflate);
+                    }
+                }
+                b"iTXt" => {
+                    if let Some(xmp) = read_png_xmp(chunk) {
+                        self.xmp = Some(xmp);
+                    }
+                }
+                b"IEND" => break,
+                _ => {}
+            }
+            // length, chunk type, data and crc
+            pos += 12 + length;
+        }
+    }
+
+    fn embed_jpeg(&self, encoded: &[u8], size: Size) -> Result<Vec<u8>, Error> {
+        if !encoded.starts_with(&JPEG_SOI) {
+            return Err(Error::InvalidContainer(ImageFormat::Jpeg));
+        }
+
+        let mut segments = Vec::new();
+        if let Some(exif) = self.encode_exif(size)? {
+            segments.extend(jpeg_segment(JPEG_APP1, &[JPEG_EXIF_HEADER, &exif], "EXIF")?);
+        }
+        if let Some(xmp) = &self.xmp {
+            segments.extend(jpeg_segment(JPEG_APP1, &[JPEG_XMP_HEADER, xmp], "XMP")?);
+        }
+        if let Some(icc_profile) = &self.icc_profile {
+            let max_chunk_size = JPEG_MAX_SEGMENT_SIZE - JPEG_ICC_HEADER.len() - 2;
+            let chunks: Vec<&[u8]> = icc_profile.chunks(max_chunk_size).collect();
+            let count = u8::try_from(chunks.len()).map_err(|_| Error::TooLarge {
+                kind: "ICC",
+                size: icc_profile.len(),
+                max: max_chunk_size * usize::from(u8::MAX),
+            })?;
+            for (sequence, chunk) in (1..=count).zip(chunks) {
+                let header = [sequence, count];
+                segments.extend(jpeg_segment(
+                    JPEG_APP2,
+                    &[JPEG_ICC_HEADER, &header, chunk],
+                    "ICC",
+                )?);
+            }
+        }
+
+        // keep the JFIF header in front of the metadata
+        let mut insert_at = JPEG_SOI.len();
+        if let Some(&[0xFF, JPEG_APP0, high, low]) = encoded.get(insert_at..insert_at + 4) {
+            insert_at += 2 + usize::from(u16::from_be_bytes([high, low]));
+        }
+        let insert_at = insert_at.min(encoded.len());
+
+        let mut output = Vec::with_capacity(encoded.len() + segments.len());
+        output.extend_from_slice(&encoded[..insert_at]);
+        output.extend(segments);
+        output.extend_from_slice(&encoded[insert_at..]);
+        Ok(output)
+    }
+
+    fn embed_png(&self, encoded: &[u8], size: Size) -> Result<Vec<u8>, Error> {
+        // signature, followed by the IHDR chunk with 13 bytes of data
+        let ihdr_end = PNG_SIGNATURE.len() + 12 + 13;
+        if !encoded.starts_with(PNG_SIGNATURE)
+            || encoded.get(PNG_SIGNATURE.len() + 4..PNG_SIGNATURE.len() + 8) != Some(b"IHDR")
+            || encoded.len() < ihdr_end
+        {
+            return Err(Error::InvalidContainer(ImageFormat::Png));
+        }
+
+        let mut chunks = Vec::new();
+        if let Some(icc_profile) = &self.icc_profile {
+            let mut data = PNG_ICC_PROFILE_NAME.to_vec();
+            // null separator and zlib compression method
+            data.extend([0, 0]);
+            data.extend(deflate(icc_profile)?);
+            chunks.extend(png_chunk(*b"iCCP", &data)?);
+        }
+        if let Some(exif) = self.encode_exif(size)? {
+            chunks.extend(png_chunk(*b"eXIf", &exif)?);
+        }
+        if let Some(xmp) = &self.xmp {
+            let mut data = PNG_XMP_KEYWORD.to_vec();
+            // uncompressed, without language tag and translated keyword
+            data.extend([0, 0, 0, 0, 0]);
+            data.extend(xmp);
+            chunks.extend(png_chunk(*b"iTXt", &data)?);
+        }
+
+        let mut output = Vec::with_capacity(encoded.len() + chunks.len());
+        output.extend_from_slice(&encoded[..ihdr_end]);
+        output.extend(chunks);
+        output.extend_from_slice(&encoded[ihdr_end..]);
+        Ok(output)
+    }
+}
+
+fn value_bytes(value: &Value) -> Option<Vec<u8>> {
+    match value {
+        Value::Byte(bytes) | Value::Undefined(bytes, _) => Some(bytes.clone()),
+        _ => None,
+    }
+}
+
+fn split_null(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let pos = data.iter().position(|&b| b == 0)?;
+    Some((&data[..pos], &data[pos + 1..]))
+}
+
+fn read_png_xmp(chunk: &[u8]) -> Option<Vec<u8>> {
+    let (keyword, rest) = split_null(chunk)?;
+    if keyword != PNG_XMP_KEYWORD {
+        return None;
+    }
+    let compressed = *rest.first()? == 1;
+    // skip the compression method, language tag and translated keyword
+    let (_, rest) = split_null(rest.get(2..)?)?;
+    let (_, text) = split_null(rest)?;
+    if compressed {
+        inflate(text)
+    } else {
+        Some(text.to_vec())
+    }
+}
+
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut inflated = Vec::new();
+    flate2::read::ZlibDecoder::new(data)
+        .read_to_end(&mut inflated)
+        .ok()?;
+    Some(inflated)
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+fn jpeg_segment(marker: u8, parts: &[&[u8]], kind: &'static str) -> Result<Vec<u8>, Error> {
+    let size: usize = parts.iter().map(|part| part.len()).sum();
+    if size > JPEG_MAX_SEGMENT_SIZE {
+        return Err(Error::TooLarge {
+            kind,
+            size,
+            max: JPEG_MAX_SEGMENT_SIZE,
+        });
+    }
+    // the length includes the two length bytes
+    let length = u16::try_from(size + 2).unwrap_or(u16::MAX);
+    let mut segment = vec![0xFF, marker];
+    segment.extend(length.to_be_bytes());
+    for part in parts {
+        segment.extend_from_slice(part);
+    }
+    Ok(segment)
+}
+
+fn png_chunk(kind: [u8; 4], data: &[u8]) -> Result<Vec<u8>, Error> {
+    let length = u32::try_from(data.len()).map_err(|_| Error::TooLarge {
+        kind: "PNG chunk",
+        size: data.len(),
+        max: u32::MAX as usize,
+    })?;
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&kind);
+    hasher.update(data);
+
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend(length.to_be_bytes());
+    chunk.extend(kind);
+    chunk.extend_from_slice(data);
+    chunk.extend(hasher.finalize().to_be_bytes());
+    Ok(chunk)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("{kind} metadata of {size} bytes exceeds the maximum of {max} bytes")]
+    TooLarge {
+        kind: &'static str,
+        size: usize,
+        max: usize,
+    },
+
+    #[error("encoded image is not a valid {0:?} container")]
+    InvalidContainer(ImageFormat),
+
+    #[error(transparent)]
+    Exif(#[from] exif::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metadata, Orientation, Policy};
+    use crate::img::{Image, ImageFormat};
+    use anyhow::Result;
+    use exif::{Field, In, Tag, Value};
+    use std::io::Cursor;
+
+    fn ascii(tag: Tag, value: &str) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        }
+    }
+
+    fn sample_metadata() -> Metadata {
+        Metadata {
+            exif: vec![
+                ascii(Tag::Make, "Leica"),
+                ascii(Tag::Model, "M6"),
+                ascii(Tag::DateTimeOriginal, "2022:06:01 12:00:00"),
+                ascii(Tag::BodySerialNumber, "1234567"),
+                ascii(Tag::GPSLatitudeRef, "N"),
+                Field {
+                    tag: Tag::Orientation,
+                    ifd_num: In::PRIMARY,
+                    value: Value::Short(vec![6]),
+                },
+                Field {
+                    tag: Tag::PixelXDimension,
+                    ifd_num: In::PRIMARY,
+                    value: Value::Long(vec![4000]),
+                },
+            ],
+            xmp: Some(b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"></x:xmpmeta>".to_vec()),
+            icc_profile: Some(vec![7; 600]),
+            orientation_applied: true,
+        }
+    }
+
+    fn round_trip(metadata: Metadata, format: ImageFormat) -> Result<(Image, Metadata)> {
+        let mut img = Image::new(16, 8);
+        img.set_metadata(metadata);
+        let mut encoded = Cursor::new(Vec::new());
+        img.encode_to(&mut encoded, format, None)?;
+        encoded.set_position(0);
+        let decoded = Image::from_reader(encoded)?;
+        let metadata = decoded.metadata().clone();
+        Ok((decoded, metadata))
+    }
+
+    fn field_string(metadata: &Metadata, tag: Tag) -> Option<String> {
+        metadata
+            .exif_field(tag)
+            .map(|field| field.display_value().to_string())
+    }
+
+    macro_rules! round_trip_tests {
+        ($($name:ident: $format:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<()> {
+                    let (img, metadata) = round_trip(sample_metadata(), $format)?;
+                    assert_eq!(img.size(), (16, 8).into());
+                    assert_eq!(field_string(&metadata, Tag::Make), Some("\"Leica\"".into()));
+                    assert_eq!(field_string(&metadata, Tag::Model), Some("\"M6\"".into()));
+                    assert!(metadata.exif_field(Tag::DateTimeOriginal).is_some());
+                    assert!(metadata.exif_field(Tag::GPSLatitudeRef).is_some());
+                    assert_eq!(metadata.orientation(), Some(Orientation::Normal));
+                    assert_eq!(field_string(&metadata, Tag::PixelXDimension), Some("16".into()));
+                    assert_eq!(field_string(&metadata, Tag::PixelYDimension), Some("8".into()));
+                    assert_eq!(metadata.xmp(), sample_metadata().xmp());
+                    assert_eq!(metadata.icc_profile(), sample_metadata().icc_profile());
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    round_trip_tests! {
+        test_round_trip_jpeg: ImageFormat::Jpeg,
+        test_round_trip_png: ImageFormat::Png,
+        test_round_trip_tiff: ImageFormat::Tiff,
+    }
+
+    #[test]
+    fn test_keep_orientation_not_applied() -> Result<()> {
+        let mut metadata = sample_metadata();
+        metadata.set_orientation_applied(false);
+        let (img, metadata) = round_trip(metadata, ImageFormat::Jpeg)?;
+        // the saved image still needs to be rotated when reading it
+        assert_eq!(metadata.orientation(), Some(Orientation::Rotate90));
+        assert_eq!(img.size(), (8, 16).into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_private() -> Result<()> {
+        let metadata = sample_metadata().with_policy(Policy::StripPrivate);
+        let (_, metadata) = round_trip(metadata, ImageFormat::Jpeg)?;
+        assert!(metadata.exif_field(Tag::Make).is_some());
+        assert!(metadata.exif_field(Tag::DateTimeOriginal).is_some());
+        assert!(metadata.exif_field(Tag::GPSLatitudeRef).is_none());
+        assert!(metadata.exif_field(Tag::BodySerialNumber).is_none());
+        assert_eq!(metadata.xmp(), None);
+        assert_eq!(metadata.icc_profile(), sample_metadata().icc_profile());
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip() -> Result<()> {
+        let metadata = sample_metadata().with_policy(Policy::Strip);
+        let (_, metadata) = round_trip(metadata, ImageFormat::Png)?;
+        assert!(metadata.exif_fields().is_empty());
+        assert_eq!(metadata.xmp(), None);
+        assert_eq!(metadata.icc_profile(), sample_metadata().icc_profile());
+        Ok(())
+    }
+
+    #[test]
+    fn test_without_metadata() -> Result<()> {
+        let (_, metadata) = round_trip(Metadata::default(), ImageFormat::Jpeg)?;
+        assert!(metadata.is_empty());
+        Ok(())
+    }
+}
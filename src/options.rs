@@ -2,31 +2,112 @@ use super::types;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+/// Presets for the margins and the position of the content
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum Layout {
+    /// Uniform margins with centered content
+    Classic,
+    /// Thick bottom margin like an instant photo
+    Polaroid,
+    /// Wide margins with the content scaled down and placed
+    /// slightly above the center of the canvas
+    Gallery,
+}
+
+impl std::str::FromStr for Layout {
+    type Err = super::error::ParseEnum;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "classic" => Ok(Layout::Classic),
+            "polaroid" => Ok(Layout::Polaroid),
+            "gallery" => Ok(Layout::Gallery),
+            _ => Err(super::error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+impl Layout {
+    #[inline]
+    #[must_use]
+    pub fn margin(self) -> types::sides::percent::Sides {
+        match self {
+            Layout::Classic => types::sides::percent::Sides::uniform(0.05),
+            Layout::Polaroid => types::sides::percent::Sides {
+                top: 0.06,
+                left: 0.06,
+                right: 0.06,
+                bottom: 0.24,
+            },
+            Layout::Gallery => types::sides::percent::Sides::uniform(0.15),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn anchor(self) -> types::Anchor {
+        match self {
+            Layout::Classic | Layout::Polaroid => types::Anchor::center(),
+            Layout::Gallery => types::Anchor::new(0.5, 0.4),
+        }
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Options {
     pub output_size: super::BoundedSize,
     pub output_size_bounds: super::BoundedSize,
     pub scale_factor: f32,
-    pub margin: f32,
+    pub margin: super::sides::percent::Sides,
+    #[serde(default)]
+    pub anchor: super::Anchor,
+    #[serde(default)]
+    pub aspect_ratio: Option<super::AspectRatio>,
     pub mode: super::FitMode,
     pub crop: Option<super::sides::percent::Sides>,
     pub frame_width: super::sides::percent::Sides,
     pub image_rotation: super::Rotation,
     pub border_rotation: super::Rotation,
     pub frame_color: super::Color,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub frame_style: Option<super::frame::Style>,
     pub background_color: Option<super::Color>,
+    #[serde(default)]
+    pub background: super::background::Background,
+    #[serde(default)]
+    pub shadow: Option<super::shadow::Shadow>,
+    #[wasm_bindgen(getter_with_clone)]
+    #[serde(default)]
+    pub markings: Option<super::markings::Markings>,
     pub preview: bool,
+    #[serde(default)]
+    pub metadata: super::metadata::Policy,
+    #[wasm_bindgen(getter_with_clone)]
+    #[serde(default)]
+    pub caption: Option<super::caption::Caption>,
+    /// Order in which the images are drawn into the windows of the border
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub window_order: super::border::WindowOrder,
+    /// Fitting of the images into the windows of the border
+    #[serde(default)]
+    pub window_fit: super::window::Fit,
 }
 
 impl Default for Options {
     #[inline]
-    #[must_use]
     fn default() -> Self {
         Self {
             output_size: super::BoundedSize::default(),
             output_size_bounds: super::BoundedSize::default(),
-            margin: 0.0,
+            margin: super::sides::percent::Sides::default(),
+            anchor: super::Anchor::default(),
+            aspect_ratio: None,
             scale_factor: 1.0,
             mode: super::FitMode::default(),
             crop: None,
@@ -34,8 +115,16 @@ impl Default for Options {
             image_rotation: super::Rotation::default(),
             border_rotation: super::Rotation::default(),
             frame_color: super::Color::black(),
+            frame_style: None,
             background_color: None,
+            background: super::background::Background::default(),
+            shadow: None,
+            markings: None,
             preview: false,
+            metadata: super::metadata::Policy::default(),
+            caption: None,
+            window_order: super::border::WindowOrder::default(),
+            window_fit: super::window::Fit::default(),
         }
     }
 }
@@ -55,6 +144,33 @@ impl Options {
         Ok(options)
     }
 
+    #[inline]
+    pub fn apply_layout(&mut self, layout: Layout) {
+        self.margin = layout.margin();
+        self.anchor = layout.anchor();
+        if layout == Layout::Gallery {
+            self.scale_factor = self.scale_factor.min(0.9);
+        }
+    }
+
+    /// Sets the frame style from its JSON representation
+    ///
+    /// `null` resets the frame to a solid fill with the frame color.
+    #[inline]
+    pub fn set_frame_style(&mut self, style: &str) -> Result<(), JsError> {
+        self.frame_style = serde_json::from_str(style)?;
+        Ok(())
+    }
+
+    /// Sets the window order from its JSON representation
+    ///
+    /// For example `"reading"` or `{"indices": [2, 0, 1]}`.
+    #[inline]
+    pub fn set_window_order(&mut self, order: &str) -> Result<(), JsError> {
+        self.window_order = serde_json::from_str(order)?;
+        Ok(())
+    }
+
     #[inline]
     pub fn serialize(&self) -> Result<String, JsError> {
         let json = serde_json::to_string(&self)?;
@@ -72,3 +188,44 @@ impl Options {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Options;
+    use crate::types;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_deserialize_baseline_options() -> Result<(), serde_json::Error> {
+        // options serialized before layout, styling and metadata were added
+        let json = r#"{
+            "output_size": { "width": 1080, "height": null },
+            "output_size_bounds": { "width": null, "height": null },
+            "scale_factor": 0.5,
+            "margin": 0.1,
+            "mode": "Image",
+            "crop": null,
+            "frame_width": { "top": 0.0, "left": 0.0, "bottom": 0.0, "right": 0.0 },
+            "image_rotation": "Rotate90",
+            "border_rotation": "Rotate0",
+            "frame_color": { "rgba": [255, 255, 255, 255] },
+            "background_color": null,
+            "preview": false
+        }"#;
+        let options: Options = serde_json::from_str(json)?;
+        let defaults = Options::default();
+        assert_eq!(options.output_size.width, Some(1080));
+        assert_eq!(options.scale_factor, 0.5);
+        assert_eq!(options.margin, types::sides::percent::Sides::uniform(0.1));
+        assert_eq!(options.image_rotation, types::Rotation::Rotate90);
+        assert_eq!(options.anchor, defaults.anchor);
+        assert_eq!(options.aspect_ratio, None);
+        assert!(options.frame_style.is_none());
+        assert_eq!(options.background, defaults.background);
+        assert!(options.shadow.is_none());
+        assert!(options.markings.is_none());
+        assert_eq!(options.metadata, defaults.metadata);
+        assert!(options.caption.is_none());
+        Ok(())
+    }
+}
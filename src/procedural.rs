@@ -0,0 +1,572 @@
+use super::arithmetic::{self, Cast};
+use super::types::{Color, Rect, Size};
+use super::{error, frame, img};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum Format {
+    /// 35mm film strip with sprocket holes along the long sides
+    Film35mm,
+    /// 120 medium format film strip
+    Film120,
+    /// Sheet film with a rebate on all sides
+    Sheet,
+}
+
+impl Default for Format {
+    #[inline]
+    fn default() -> Self {
+        Format::Film35mm
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = error::ParseEnum;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "35mm" | "135" => Ok(Format::Film35mm),
+            "120" | "120mm" | "medium-format" => Ok(Format::Film120),
+            "sheet" | "4x5" | "large-format" => Ok(Format::Sheet),
+            _ => Err(error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+impl Format {
+    /// Aspect ratio of the window
+    #[inline]
+    #[must_use]
+    pub fn window_aspect(self) -> (u32, u32) {
+        match self {
+            Format::Film35mm => (3, 2),
+            Format::Film120 => (1, 1),
+            Format::Sheet => (5, 4),
+        }
+    }
+
+    /// Gap between two frames on the strip relative to the film width
+    ///
+    /// Sheet film has no neighbouring frames and uses its rebate instead.
+    #[inline]
+    #[must_use]
+    pub fn frame_gap(self) -> Option<f32> {
+        match self {
+            Format::Film35mm => Some(0.06),
+            Format::Film120 => Some(0.07),
+            Format::Sheet => None,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum HoleShape {
+    Rect,
+    Rounded,
+    Round,
+}
+
+impl Default for HoleShape {
+    #[inline]
+    fn default() -> Self {
+        HoleShape::Rounded
+    }
+}
+
+impl std::str::FromStr for HoleShape {
+    type Err = error::ParseEnum;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "rect" | "square" => Ok(HoleShape::Rect),
+            "rounded" => Ok(HoleShape::Rounded),
+            "round" => Ok(HoleShape::Round),
+            _ => Err(error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+/// Film border generated at the exact size it is rendered at
+///
+/// Widths and the edge irregularity are relative to the shorter side of
+/// the border, the window radius is relative to the shorter side of the
+/// window.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(default)]
+pub struct Film {
+    pub format: Format,
+    /// Sprocket holes along each side of the window
+    ///
+    /// Limited to the number of holes that fit along the rebate.
+    pub holes: u32,
+    pub hole_shape: HoleShape,
+    /// Width of the rebate on the sides of the window
+    pub rebate: f32,
+    /// Corner radius of the window
+    pub radius: f32,
+    /// Amplitude of the wobble of the window edges
+    pub irregularity: f32,
+    pub seed: u32,
+    pub color: Color,
+    pub hole_color: Color,
+}
+
+impl Default for Film {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Format::default())
+    }
+}
+
+#[wasm_bindgen]
+impl Film {
+    #[wasm_bindgen(constructor)]
+    #[inline]
+    #[must_use]
+    pub fn new(format: Format) -> Self {
+        let (holes, rebate, radius) = match format {
+            Format::Film35mm => (8, 0.16, 0.02),
+            Format::Film120 => (0, 0.07, 0.015),
+            Format::Sheet => (0, 0.05, 0.005),
+        };
+        Self {
+            format,
+            holes,
+            hole_shape: HoleShape::default(),
+            rebate,
+            radius,
+            irregularity: 0.002,
+            seed: 0,
+            color: Color::black(),
+            hole_color: Color::white(),
+        }
+    }
+}
+
+/// Widths of the film around the window in pixels
+#[derive(Debug, Copy, Clone)]
+struct Insets {
+    /// Rebate along the long sides
+    across: f64,
+    /// Gap at the short ends
+    ends: f64,
+}
+
+impl Film {
+    #[inline]
+    fn rebate_factor(&self) -> f64 {
+        // the two rebates must leave space for the window
+        f64::from(self.rebate).clamp(0.0, 0.45)
+    }
+
+    #[inline]
+    fn ends_factor(&self) -> f64 {
+        self.format
+            .frame_gap()
+            .map_or_else(|| self.rebate_factor(), f64::from)
+    }
+
+    #[inline]
+    fn insets(&self, size: Size) -> Insets {
+        let min_dim = f64::from(size.min_dim());
+        Insets {
+            across: self.rebate_factor() * min_dim,
+            ends: self.ends_factor() * min_dim,
+        }
+    }
+
+    /// Window of a border with `size`
+    #[inline]
+    pub fn window(&self, size: Size) -> Result<Rect, RenderError> {
+        let Insets { across, ends } = self.insets(size);
+        let (horizontal, vertical) = if size.width >= size.height {
+            (ends, across)
+        } else {
+            (across, ends)
+        };
+        let (width, height) = (f64::from(size.width), f64::from(size.height));
+        let window = (|| {
+            Ok::<_, arithmetic::Error>(Rect::from_points(
+                (
+                    horizontal.round().cast::<i64>()?,
+                    vertical.round().cast::<i64>()?,
+                ),
+                (
+                    (width - horizontal).round().cast::<i64>()?,
+                    (height - vertical).round().cast::<i64>()?,
+                ),
+            ))
+        })();
+        window.map_err(|err| RenderError {
+            film: *self,
+            size,
+            source: err,
+        })
+    }
+
+    /// Size of the border around a window with size `window`
+    #[inline]
+    pub fn size_for_window(&self, window: Size) -> Result<Size, SizeError> {
+        let size = (|| {
+            let (long, short) = if window.width >= window.height {
+                (window.width, window.height)
+            } else {
+                (window.height, window.width)
+            };
+            let across = f64::from(short) / (1.0 - 2.0 * self.rebate_factor());
+            let along = f64::from(long) + 2.0 * self.ends_factor() * across;
+            let across = across.round().cast::<u32>()?;
+            let along = along.round().cast::<u32>()?;
+            Ok::<_, arithmetic::Error>(if window.width >= window.height {
+                Size {
+                    width: along,
+                    height: across,
+                }
+            } else {
+                Size {
+                    width: across,
+                    height: along,
+                }
+            })
+        })();
+        size.map_err(|err| SizeError {
+            film: *self,
+            window,
+            source: err,
+        })
+    }
+
+    /// Size the border is generated at before it is fitted to the content
+    #[inline]
+    pub fn default_size(&self) -> Result<Size, SizeError> {
+        let (width, height) = self.format.window_aspect();
+        self.size_for_window(Size {
+            width: width * 500,
+            height: height * 500,
+        })
+    }
+
+    /// Sprocket holes of a border with `size`
+    #[inline]
+    fn holes(&self, size: Size) -> Result<Vec<(Rect, f64)>, arithmetic::Error> {
+        if self.holes == 0 || self.format.frame_gap().is_none() {
+            return Ok(Vec::new());
+        }
+        let Insets { across: rebate, .. } = self.insets(size);
+        let landscape = size.width >= size.height;
+        let length = f64::from(size.max_dim());
+        let thickness = f64::from(size.min_dim());
+        // proportions of 35mm perforations
+        let hole_across = (0.057 * thickness).min(0.8 * rebate);
+        if hole_across < 1.0 {
+            return Ok(Vec::new());
+        }
+        // holes are at least as long as they are wide, which bounds
+        // the number of holes that fit along the rebate
+        let count = f64::from(self.holes)
+            .min((0.7 * length / hole_across).floor())
+            .cast::<u32>()?;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let pitch = length / f64::from(count);
+        let hole_along = (0.08 * thickness).min(0.7 * pitch);
+        let radius = match self.hole_shape {
+            HoleShape::Rect => 0.0,
+            HoleShape::Rounded => 0.25 * hole_across.min(hole_along),
+            HoleShape::Round => 0.5 * hole_across.min(hole_along),
+        };
+        let mut holes = Vec::new();
+        for center_across in [rebate / 2.0, thickness - rebate / 2.0] {
+            for idx in 0..count {
+                let center_along = (f64::from(idx) + 0.5) * pitch;
+                let along = (
+                    (center_along - hole_along / 2.0).round().cast::<i64>()?,
+                    (center_along + hole_along / 2.0).round().cast::<i64>()?,
+                );
+                let across = (
+                    (center_across - hole_across / 2.0).round().cast::<i64>()?,
+                    (center_across + hole_across / 2.0).round().cast::<i64>()?,
+                );
+                let rect = if landscape {
+                    Rect::from_points((along.0, across.0), (along.1, across.1))
+                } else {
+                    Rect::from_points((across.0, along.0), (across.1, along.1))
+                };
+                holes.push((rect, radius));
+            }
+        }
+        Ok(holes)
+    }
+
+    /// Generates the border with `size`
+    ///
+    /// The window is transparent so it is detected as the content area of
+    /// the border.
+    #[inline]
+    pub fn render(&self, size: Size) -> Result<img::Image, RenderError> {
+        let window = self.window(size)?;
+        self.render_window(size, &window)
+            .map_err(|err| RenderError {
+                film: *self,
+                size,
+                source: err,
+            })
+    }
+
+    #[inline]
+    fn render_window(&self, size: Size, window: &Rect) -> Result<img::Image, arithmetic::Error> {
+        let radius = f64::from(self.radius).max(0.0)
+            * window.width().min(window.height()).max(0).cast::<f64>()?;
+        let min_dim = f64::from(size.min_dim());
+        let amplitude = f64::from(self.irregularity).max(0.0) * min_dim;
+        let wavelength = (0.05 * min_dim).max(1.0);
+        let (left, right) = (window.left.cast::<f64>()?, window.right.cast::<f64>()?);
+        let (top, bottom) = (window.top.cast::<f64>()?, window.bottom.cast::<f64>()?);
+        let center = ((left + right) / 2.0, (top + bottom) / 2.0);
+        let margin = radius + amplitude + 2.0;
+        let interior = (left + margin, right - margin, top + margin, bottom - margin);
+
+        let holes = self
+            .holes(size)?
+            .into_iter()
+            .map(|(rect, radius)| {
+                let bounds = (
+                    rect.left.cast::<f64>()? - 1.0,
+                    rect.right.cast::<f64>()? + 1.0,
+                    rect.top.cast::<f64>()? - 1.0,
+                    rect.bottom.cast::<f64>()? + 1.0,
+                );
+                Ok::<_, arithmetic::Error>((rect, radius, bounds))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let film: image::Rgba<u8> = self.color.into();
+        let hole: image::Rgba<u8> = self.hole_color.into();
+
+        let mut image = img::Image::with_size(size);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let (fx, fy) = (f64::from(x), f64::from(y));
+            if fx > interior.0 && fx < interior.1 && fy > interior.2 && fy < interior.3 {
+                *pixel = image::Rgba([0, 0, 0, 0]);
+                continue;
+            }
+            let mut color = film;
+            for (rect, radius, bounds) in &holes {
+                if fx < bounds.0 || fx > bounds.1 || fy < bounds.2 || fy > bounds.3 {
+                    continue;
+                }
+                let coverage = frame::coverage(rect, *radius, fx, fy)?;
+                if coverage > 0.0 {
+                    color = mix(color, hole, coverage)?;
+                }
+            }
+            // each edge of the window wobbles independently
+            let dx = amplitude * noise(self.seed ^ u32::from(fx < center.0), fy / wavelength)?;
+            let dy =
+                amplitude * noise(self.seed ^ (2 + u32::from(fy < center.1)), fx / wavelength)?;
+            let coverage = frame::coverage(window, radius, fx + dx, fy + dy)?;
+            color.0[3] = (f64::from(color.0[3]) * (1.0 - coverage))
+                .round()
+                .cast::<u8>()?;
+            *pixel = color;
+        }
+        Ok(image)
+    }
+}
+
+#[inline]
+fn mix(
+    a: image::Rgba<u8>,
+    b: image::Rgba<u8>,
+    t: f64,
+) -> Result<image::Rgba<u8>, arithmetic::Error> {
+    let mut color = a;
+    for (channel, (a, b)) in color.0.iter_mut().zip(a.0.iter().zip(b.0.iter())) {
+        *channel = (f64::from(*a) * (1.0 - t) + f64::from(*b) * t)
+            .round()
+            .cast::<u8>()?;
+    }
+    Ok(color)
+}
+
+/// Pseudo random value in `[-1, 1]` for a lattice point
+#[inline]
+fn hash(seed: u32, x: i64) -> f64 {
+    let x = u64::from_ne_bytes(x.to_ne_bytes());
+    let mut h = u64::from(seed) ^ x.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+    // the top 52 bits as the mantissa of a float in [1, 2)
+    let unit = f64::from_bits(0x3ff0_0000_0000_0000 | (h >> 12)) - 1.0;
+    unit * 2.0 - 1.0
+}
+
+/// Smooth value noise in `[-1, 1]`
+#[inline]
+fn noise(seed: u32, t: f64) -> Result<f64, arithmetic::Error> {
+    let cell = t.floor();
+    let frac = t - cell;
+    let smooth = frac * frac * (3.0 - 2.0 * frac);
+    let cell = cell.cast::<i64>()?;
+    Ok(hash(seed, cell) * (1.0 - smooth) + hash(seed, cell.saturating_add(1)) * smooth)
+}
+
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+#[error("failed to compute size of {film:?} for window of size {window:#?}")]
+pub struct SizeError {
+    film: Film,
+    window: Size,
+    source: arithmetic::Error,
+}
+
+impl arithmetic::error::Arithmetic for SizeError {}
+
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+#[error("failed to render {film:?} with size {size:#?}")]
+pub struct RenderError {
+    film: Film,
+    size: Size,
+    source: arithmetic::Error,
+}
+
+impl arithmetic::error::Arithmetic for RenderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Film, Format, HoleShape};
+    use crate::types::{Rect, Size};
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(Format::from_str("35mm").ok(), Some(Format::Film35mm));
+        assert_eq!(Format::from_str("120").ok(), Some(Format::Film120));
+        assert_eq!(Format::from_str("4x5").ok(), Some(Format::Sheet));
+        assert_eq!(HoleShape::from_str("Round").ok(), Some(HoleShape::Round));
+        assert!(Format::from_str("110").is_err());
+    }
+
+    macro_rules! window_tests {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<()> {
+                    let (film, window): (Film, Size) = $values;
+                    let size = film.size_for_window(window)?;
+                    let rect = film.window(size)?;
+                    assert!((rect.width() - i64::from(window.width)).abs() <= 1);
+                    assert!((rect.height() - i64::from(window.height)).abs() <= 1);
+                    // the window is centered
+                    assert_eq!(rect.left, i64::from(size.width) - rect.right);
+                    assert_eq!(rect.top, i64::from(size.height) - rect.bottom);
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    window_tests! {
+        test_window_35mm_landscape: (Film::new(Format::Film35mm), Size { width: 3000, height: 2000 }),
+        test_window_35mm_portrait: (Film::new(Format::Film35mm), Size { width: 800, height: 1200 }),
+        test_window_120_square: (Film::new(Format::Film120), Size { width: 1000, height: 1000 }),
+        test_window_sheet: (Film::new(Format::Sheet), Size { width: 1250, height: 1000 }),
+    }
+
+    #[test]
+    fn test_render_film() -> Result<()> {
+        let film = Film {
+            irregularity: 0.0,
+            ..Film::new(Format::Film35mm)
+        };
+        let size = film.default_size()?;
+        let size = Size {
+            width: size.width / 10,
+            height: size.height / 10,
+        };
+        let image = film.render(size)?;
+        assert_eq!(image.size(), size);
+        let window = film.window(size)?;
+        let (cx, cy) = (
+            u32::try_from((window.left + window.right) / 2)?,
+            u32::try_from((window.top + window.bottom) / 2)?,
+        );
+        assert_eq!(image.get_pixel(cx, cy)[3], 0);
+        assert_eq!(*image.get_pixel(0, 0), image::Rgba([0, 0, 0, 255]));
+
+        // eight holes in the top rebate
+        let rebate = u32::try_from(window.top / 2)?;
+        let row: Vec<bool> = (0..size.width)
+            .map(|x| image.get_pixel(x, rebate)[0] > 128)
+            .collect();
+        let holes = row.windows(2).filter(|w| !w[0] && w[1]).count();
+        assert_eq!(holes, 8);
+
+        // the window is the only transparent component
+        let components = crate::imageops::find_transparent_components(&image, 0.95, 8)?;
+        assert_eq!(components.len(), 1);
+        let expected = Rect::from_points((window.left, window.top), (window.right, window.bottom));
+        assert!((components[0].width() - expected.width()).abs() <= 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hole_count_is_bounded() -> Result<()> {
+        let film = Film {
+            holes: 1_000_000_000,
+            ..Film::new(Format::Film35mm)
+        };
+        let size = Size {
+            width: 360,
+            height: 240,
+        };
+        let holes = film.holes(size)?;
+        assert!(!holes.is_empty());
+        assert!(holes.len() < 100);
+        // holes are at least as long as they are wide
+        assert!(holes
+            .iter()
+            .all(|(rect, _)| rect.width() + 1 >= rect.height()));
+        assert_eq!(film.render(size)?.size(), size);
+        Ok(())
+    }
+
+    #[test]
+    fn test_irregular_edges() -> Result<()> {
+        let film = Film {
+            irregularity: 0.01,
+            seed: 7,
+            ..Film::new(Format::Sheet)
+        };
+        let size = Size {
+            width: 500,
+            height: 400,
+        };
+        let image = film.render(size)?;
+        let window = film.window(size)?;
+        // the left edge of the window is not a straight line
+        let edge = |y: u32| {
+            (0..size.width / 2)
+                .find(|x| image.get_pixel(*x, y)[3] < 128)
+                .unwrap_or(0)
+        };
+        let top = u32::try_from(window.top).unwrap_or(0) + 20;
+        let bottom = u32::try_from(window.bottom).unwrap_or(0) - 20;
+        let edges: Vec<u32> = (top..bottom).step_by(5).map(edge).collect();
+        assert!(edges.iter().min() != edges.iter().max());
+        assert_eq!(film.render(size)?.as_raw(), image.as_raw());
+        Ok(())
+    }
+}
@@ -0,0 +1,457 @@
+use super::border::{self, Border};
+#[cfg(feature = "builtin")]
+use super::builtin::Builtin;
+use super::config;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// File names of the manifest of a border pack
+pub const MANIFEST_NAMES: [&str; 4] = ["pack.toml", "pack.json", "pack.yaml", "pack.yml"];
+
+#[derive(Clone)]
+enum Source {
+    #[cfg(feature = "builtin")]
+    Builtin(Builtin),
+    File(PathBuf),
+    /// Border file and its sidecar read from a pack archive
+    Data {
+        data: Arc<[u8]>,
+        sidecar: Option<Arc<[u8]>>,
+    },
+}
+
+impl std::fmt::Debug for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "builtin")]
+            Source::Builtin(builtin) => write!(f, "Builtin({:?})", builtin),
+            Source::File(path) => write!(f, "File({:?})", path),
+            Source::Data { data, .. } => write!(f, "Data({} bytes)", data.len()),
+        }
+    }
+}
+
+/// Border listed in the registry
+#[derive(Serialize, Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub description: String,
+    /// Film format, such as `35mm` or `120`
+    pub format: Option<String>,
+    pub license: Option<String>,
+    /// Name of the pack the border was installed from
+    pub pack: Option<String>,
+    #[serde(skip)]
+    source: Source,
+}
+
+impl Entry {
+    /// Checks if the border is called `name`, ignoring case
+    #[inline]
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        std::iter::once(&self.name)
+            .chain(&self.aliases)
+            .any(|candidate| candidate.eq_ignore_ascii_case(name))
+    }
+
+    /// Builtin border of the entry, if it is not from a pack
+    #[cfg(feature = "builtin")]
+    #[inline]
+    #[must_use]
+    pub fn builtin(&self) -> Option<Builtin> {
+        match self.source {
+            Source::Builtin(builtin) => Some(builtin),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn border(&self) -> Result<border::Kind, border::Error> {
+        match &self.source {
+            #[cfg(feature = "builtin")]
+            Source::Builtin(builtin) => Ok(border::Kind::Builtin(*builtin)),
+            Source::File(path) => Border::open(path, None).map(border::Kind::Custom),
+            Source::Data { data, sidecar } => {
+                let manifest = sidecar
+                    .as_deref()
+                    .map(border::Manifest::from_data)
+                    .transpose()?;
+                let border = Border::from_reader_with_manifest(
+                    Cursor::new(&**data),
+                    None,
+                    manifest.as_ref(),
+                )?;
+                Ok(border::Kind::Custom(border))
+            }
+        }
+    }
+}
+
+/// Manifest of a border pack
+///
+/// ```toml
+/// name = "darkroom"
+///
+/// [[borders]]
+/// name = "portra-35mm"
+/// aliases = ["portra"]
+/// description = "Kodak Portra 400 35mm negative"
+/// format = "35mm"
+/// license = "CC-BY-4.0"
+/// file = "borders/portra.png"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+pub struct Manifest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+    pub borders: Vec<ManifestEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ManifestEntry {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub format: Option<String>,
+    /// License of the border, defaults to the license of the pack
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Border image relative to the manifest
+    pub file: PathBuf,
+}
+
+/// Builtin borders and borders installed from packs
+///
+/// Borders from packs that were loaded later take precedence over
+/// borders with the same name.
+#[derive(Clone, Debug, Default)]
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    /// Registry of the builtin borders
+    #[inline]
+    #[must_use]
+    pub fn builtin() -> Self {
+        #[cfg(feature = "builtin")]
+        let entries = Builtin::ALL
+            .into_iter()
+            .map(|builtin| Entry {
+                name: builtin.name().to_string(),
+                aliases: builtin.aliases().iter().map(ToString::to_string).collect(),
+                description: builtin.description().to_string(),
+                format: Some(builtin.format().to_string()),
+                license: Some("MIT".to_string()),
+                pack: None,
+                source: Source::Builtin(builtin),
+            })
+            .collect();
+        #[cfg(not(feature = "builtin"))]
+        let entries = Vec::new();
+        Self { entries }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().rev().find(|entry| entry.matches(name))
+    }
+
+    #[inline]
+    pub fn border(&self, name: &str) -> Result<border::Kind, Error> {
+        let entry = self
+            .find(name)
+            .ok_or_else(|| Error::Unknown(name.to_string()))?;
+        entry.border().map_err(|source| Error::Border {
+            name: entry.name.clone(),
+            source: Box::new(source),
+        })
+    }
+
+    /// Loads a border pack from a directory or a `.tar`, `.tar.gz` or
+    /// `.tgz` archive
+    ///
+    /// The manifest is the `pack.toml`, `pack.json` or `pack.yaml` file
+    /// closest to the root of the pack.
+    #[inline]
+    pub fn load_pack(&mut self, path: impl AsRef<Path>) -> Result<&[Entry], Error> {
+        let path = path.as_ref();
+        let start = self.entries.len();
+        let entries = if path.is_dir() {
+            load_directory(path)?
+        } else {
+            load_archive(path)?
+        };
+        self.entries.extend(entries);
+        Ok(&self.entries[start..])
+    }
+}
+
+impl ManifestEntry {
+    fn into_entry(self, manifest: &Manifest, source: Source) -> Entry {
+        Entry {
+            name: self.name,
+            aliases: self.aliases,
+            description: self.description,
+            format: self.format,
+            license: self.license.or_else(|| manifest.license.clone()),
+            pack: manifest.name.clone(),
+            source,
+        }
+    }
+}
+
+fn load_directory(dir: &Path) -> Result<Vec<Entry>, Error> {
+    let manifest_path = MANIFEST_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| Error::MissingManifest(dir.to_path_buf()))?;
+    let manifest: Manifest = config::open(&manifest_path).map_err(|source| Error::Manifest {
+        path: manifest_path.clone(),
+        source,
+    })?;
+    manifest
+        .borders
+        .iter()
+        .cloned()
+        .map(|border| {
+            let file = dir.join(&border.file);
+            if !file.is_file() {
+                return Err(Error::MissingFile {
+                    pack: dir.to_path_buf(),
+                    name: border.name,
+                    file: border.file,
+                });
+            }
+            Ok(border.into_entry(&manifest, Source::File(file)))
+        })
+        .collect()
+}
+
+/// Normalizes a path inside an archive, such as `./pack/../a.png`
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => {}
+        }
+    }
+    normalized
+}
+
+fn load_archive(path: &Path) -> Result<Vec<Entry>, Error> {
+    let read_error = |source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    };
+    let data = std::fs::read(path).map_err(read_error)?;
+    let reader: Box<dyn Read> = if data.starts_with(&[0x1f, 0x8b]) {
+        Box::new(flate2::read::GzDecoder::new(Cursor::new(data)))
+    } else {
+        Box::new(Cursor::new(data))
+    };
+
+    let mut files: HashMap<PathBuf, Arc<[u8]>> = HashMap::new();
+    let mut archive = tar::Archive::new(reader);
+    for file in archive.entries().map_err(read_error)? {
+        let mut file = file.map_err(read_error)?;
+        if !file.header().entry_type().is_file() {
+            continue;
+        }
+        let name = normalize(&file.path().map_err(read_error)?);
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).map_err(read_error)?;
+        files.insert(name, content.into());
+    }
+
+    let manifest_path = files
+        .keys()
+        .filter(|file| {
+            file.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| MANIFEST_NAMES.contains(&name))
+        })
+        .min_by_key(|file| (file.components().count(), file.to_path_buf()))
+        .cloned()
+        .ok_or_else(|| Error::MissingManifest(path.to_path_buf()))?;
+    let manifest_error = |source| Error::Manifest {
+        path: path.join(&manifest_path),
+        source,
+    };
+    let format = config::Format::from_path(&manifest_path)
+        .ok_or_else(|| config::Error::UnknownFormat(manifest_path.clone()))
+        .map_err(manifest_error)?;
+    let content = String::from_utf8_lossy(&files[&manifest_path]).into_owned();
+    let manifest: Manifest = config::parse(&content, format).map_err(manifest_error)?;
+
+    let root = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+    manifest
+        .borders
+        .iter()
+        .cloned()
+        .map(|border| {
+            let file = normalize(&root.join(&border.file));
+            let sidecar = files.get(&border::manifest::sidecar_path(&file)).cloned();
+            let data = files
+                .get(&file)
+                .cloned()
+                .ok_or_else(|| Error::MissingFile {
+                    pack: path.to_path_buf(),
+                    name: border.name.clone(),
+                    file: border.file.clone(),
+                })?;
+            Ok(border.into_entry(&manifest, Source::Data { data, sidecar }))
+        })
+        .collect()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("unknown border `{0}`")]
+    Unknown(String),
+
+    #[error("failed to load border `{name}`")]
+    Border {
+        name: String,
+        source: Box<border::Error>,
+    },
+
+    #[error("failed to read border pack {path:?}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("border pack {0:?} has no manifest (expected one of {MANIFEST_NAMES:?})")]
+    MissingManifest(PathBuf),
+
+    #[error("invalid manifest {path:?}")]
+    Manifest {
+        path: PathBuf,
+        source: config::Error,
+    },
+
+    #[error("border `{name}` of pack {pack:?} is missing its file {file:?}")]
+    MissingFile {
+        pack: PathBuf,
+        name: String,
+        file: PathBuf,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Registry};
+    use crate::border;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="60" height="40">
+  <rect width="60" height="40" fill="#000000"/>
+  <rect id="window" x="5" y="5" width="50" height="30" fill="#ffffff"/>
+</svg>"##;
+
+    const MANIFEST: &str = r#"
+name = "test-pack"
+license = "CC0-1.0"
+
+[[borders]]
+name = "plain"
+aliases = ["Simple"]
+description = "Plain black border"
+format = "35mm"
+file = "borders/plain.svg"
+"#;
+
+    fn pack_dir(name: &str) -> Result<PathBuf> {
+        let dir = std::env::temp_dir().join("filmborders").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("borders"))?;
+        fs::write(dir.join("pack.toml"), MANIFEST)?;
+        fs::write(dir.join("borders/plain.svg"), SVG)?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn test_builtin_registry() {
+        let registry = Registry::builtin();
+        #[cfg(feature = "builtin")]
+        {
+            let entry = registry.find("120MM1").unwrap();
+            assert_eq!(entry.name, "120mm");
+            assert_eq!(entry.format.as_deref(), Some("120"));
+        }
+        assert!(matches!(
+            registry.border("missing"),
+            Err(Error::Unknown(name)) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn test_load_pack_directory() -> Result<()> {
+        let dir = pack_dir("registry_pack_directory")?;
+        let mut registry = Registry::builtin();
+        let entries = registry.load_pack(&dir)?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].license.as_deref(), Some("CC0-1.0"));
+        assert_eq!(entries[0].pack.as_deref(), Some("test-pack"));
+        assert!(matches!(
+            registry.border("simple")?,
+            border::Kind::Custom(_)
+        ));
+
+        fs::remove_file(dir.join("borders/plain.svg"))?;
+        assert!(matches!(
+            Registry::default().load_pack(&dir),
+            Err(Error::MissingFile { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_pack_archive() -> Result<()> {
+        let dir = pack_dir("registry_pack_archive")?;
+        let archive = dir.with_extension("tar.gz");
+        let file = fs::File::create(&archive)?;
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        builder.append_dir_all("pack", &dir)?;
+        builder.into_inner()?.finish()?;
+
+        let mut registry = Registry::default();
+        let entries = registry.load_pack(&archive)?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "plain");
+        match registry.border("plain")? {
+            border::Kind::Custom(border) => {
+                assert_eq!(border.transparent_components().len(), 1);
+            }
+            kind => panic!("expected custom border, got {:?}", kind),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+use super::arithmetic::{self, Cast};
+use super::types::{sides::percent::Sides, Color, Point, Rect};
+use super::{frame, imageops, img};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Soft shadow under the framed content
+///
+/// Offsets, blur and spread are relative to the shorter side of the content,
+/// just like the margins.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(default)]
+pub struct Shadow {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    /// Distance over which the shadow fades out
+    pub blur: f32,
+    /// Growth of the shadow beyond the frame before blurring
+    pub spread: f32,
+    pub color: Color,
+    pub opacity: f32,
+}
+
+impl Default for Shadow {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            offset_x: 0.01,
+            offset_y: 0.02,
+            blur: 0.04,
+            spread: 0.0,
+            color: Color::black(),
+            opacity: 0.5,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Shadow {
+    #[wasm_bindgen(constructor)]
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Shadow {
+    /// Space the shadow needs around the frame on each side
+    #[inline]
+    #[must_use]
+    pub fn extent(&self) -> Sides {
+        let reach = self.spread.max(0.0) + self.blur.max(0.0);
+        Sides {
+            top: (reach - self.offset_y).max(0.0),
+            left: (reach - self.offset_x).max(0.0),
+            bottom: (reach + self.offset_y).max(0.0),
+            right: (reach + self.offset_x).max(0.0),
+        }
+    }
+
+    /// Draws the shadow of a frame in `rect` with corner `radius`
+    ///
+    /// `base` is the length of the shorter side of the content in pixels.
+    #[inline]
+    pub fn draw(
+        &self,
+        image: &mut img::Image,
+        rect: &Rect,
+        radius: f64,
+        base: u32,
+    ) -> Result<(), DrawError> {
+        let pixels = |value: f32| (f64::from(value) * f64::from(base)).round();
+        let opacity = f64::from(self.opacity).clamp(0.0, 1.0);
+        if opacity <= 0.0 {
+            return Ok(());
+        }
+        match (|| {
+            let spread = pixels(self.spread).max(0.0);
+            let blur = pixels(self.blur).max(0.0);
+            let offset_x = pixels(self.offset_x).cast::<i64>()?;
+            let offset_y = pixels(self.offset_y).cast::<i64>()?;
+            let spread_px = spread.cast::<i64>()?;
+            let padding = blur.cast::<i64>()?;
+
+            let shape = Rect::from_points(
+                Point {
+                    x: rect.left + offset_x - spread_px,
+                    y: rect.top + offset_y - spread_px,
+                },
+                Point {
+                    x: rect.right + offset_x + spread_px,
+                    y: rect.bottom + offset_y + spread_px,
+                },
+            );
+            let local = Rect::from_points(
+                Point {
+                    x: padding,
+                    y: padding,
+                },
+                Point {
+                    x: padding + shape.width(),
+                    y: padding + shape.height(),
+                },
+            );
+            let width = (shape.width() + 2 * padding).cast::<u32>()?;
+            let height = (shape.height() + 2 * padding).cast::<u32>()?;
+            let mut mask = image::GrayImage::new(width, height);
+            for (x, y, pixel) in mask.enumerate_pixels_mut() {
+                let coverage =
+                    frame::coverage(&local, radius + spread, f64::from(x), f64::from(y))?;
+                *pixel = image::Luma([(coverage * 255.0).round().cast::<u8>()?]);
+            }
+            // the gaussian fades out after about three standard deviations
+            let mask = imageops::blur_mask(&mask, blur.cast::<f32>()? / 3.0);
+
+            let mut color: image::Rgba<u8> = self.color.into();
+            color.0[3] = (f64::from(color.0[3]) * opacity).round().cast::<u8>()?;
+            let offset = Point {
+                x: shape.left - padding,
+                y: shape.top - padding,
+            };
+            imageops::blend_mask(image, &mask, offset, color);
+            Ok::<_, arithmetic::Error>(())
+        })() {
+            Ok(()) => Ok(()),
+            Err(err) => Err(DrawError {
+                shadow: *self,
+                rect: *rect,
+                source: err,
+            }),
+        }
+    }
+}
+
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+#[error("failed to draw {shadow:?} for frame {rect:#?}")]
+pub struct DrawError {
+    shadow: Shadow,
+    rect: Rect,
+    source: arithmetic::Error,
+}
+
+impl arithmetic::error::Arithmetic for DrawError {}
+
+#[cfg(test)]
+mod tests {
+    use super::Shadow;
+    use crate::img::Image;
+    use crate::types::{sides::percent::Sides, Color, Rect};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_shadow_extent() {
+        let shadow = Shadow {
+            offset_x: 0.02,
+            offset_y: -0.05,
+            blur: 0.04,
+            spread: 0.01,
+            ..Shadow::default()
+        };
+        let extent = shadow.extent();
+        approx::assert_abs_diff_eq!(extent.top, 0.1);
+        approx::assert_abs_diff_eq!(extent.left, 0.03);
+        approx::assert_abs_diff_eq!(extent.bottom, 0.0);
+        approx::assert_abs_diff_eq!(extent.right, 0.07);
+        assert_eq!(
+            Shadow {
+                blur: 0.0,
+                spread: 0.0,
+                offset_x: 0.0,
+                offset_y: 0.0,
+                ..Shadow::default()
+            }
+            .extent(),
+            Sides::uniform(0.0)
+        );
+    }
+
+    #[test]
+    fn test_draw_shadow() -> anyhow::Result<()> {
+        let mut image = Image::new(100, 100);
+        image.fill(Color::white(), crate::FillMode::Set)?;
+        let rect = Rect::new((30, 30), (40, 40))?;
+        let shadow = Shadow {
+            offset_x: 0.25,
+            offset_y: 0.25,
+            blur: 0.25,
+            spread: 0.0,
+            color: Color::black(),
+            opacity: 1.0,
+        };
+        shadow.draw(&mut image, &rect, 0.0, 40)?;
+        let value = |x, y| image.get_pixel(x, y)[0];
+        // dark under the offset frame, fading out with the blur
+        assert!(value(60, 60) < 16);
+        assert_eq!(value(5, 5), 255);
+        assert!(value(85, 60) > value(75, 60));
+        assert!(value(85, 60) < 255);
+        assert_eq!(value(99, 20), 255);
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use super::arithmetic::{self, Cast};
+use super::imageops::FillMode;
+use super::types::{Color, Rect, Size};
+use super::{error, img};
+use resvg::{tiny_skia, usvg};
+use std::sync::Arc;
+
+/// Elements with an id starting with this prefix mark windows of the border
+pub const WINDOW_ID_PREFIX: &str = "window";
+
+/// Vector border that is rasterized at the size it is rendered at
+#[derive(Clone)]
+pub struct Svg {
+    tree: Arc<usvg::Tree>,
+}
+
+impl std::fmt::Debug for Svg {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let size = self.tree.size();
+        write!(f, "Svg({}x{})", size.width(), size.height())
+    }
+}
+
+/// Checks if `data` looks like an SVG document
+///
+/// Raster formats never start with markup, compressed data is assumed
+/// to be an SVGZ document.
+#[inline]
+#[must_use]
+pub fn is_svg(data: &[u8]) -> bool {
+    let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+    let start = data.iter().position(|c| !c.is_ascii_whitespace());
+    match start.map(|start| &data[start..]) {
+        Some([b'<', ..]) => true,
+        Some(data) => data.starts_with(&[0x1f, 0x8b]),
+        None => false,
+    }
+}
+
+impl Svg {
+    #[inline]
+    pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+        let tree = usvg::Tree::from_data(data, &usvg::Options::default())?;
+        Ok(Self {
+            tree: Arc::new(tree),
+        })
+    }
+
+    /// Size of the document
+    #[inline]
+    #[must_use]
+    pub fn size(&self) -> Size {
+        let size = self.tree.size().to_int_size();
+        Size {
+            width: size.width(),
+            height: size.height(),
+        }
+    }
+
+    /// Windows marked in the document, scaled to an image with `size`
+    ///
+    /// Like detected windows, the corners of the windows are inclusive.
+    /// Windows that cover no pixels are skipped.
+    #[inline]
+    pub fn windows(&self, size: Size) -> Result<Vec<Rect>, Error> {
+        let document = self.tree.size();
+        let scale_x = f64::from(size.width) / f64::from(document.width());
+        let scale_y = f64::from(size.height) / f64::from(document.height());
+        let mut windows = Vec::new();
+        marked_windows(self.tree.root(), &mut windows);
+        let scaled = windows.into_iter().filter_map(|window| {
+            let scale = |value: f32, scale: f64| (f64::from(value) * scale).round().cast::<i64>();
+            (|| {
+                let (left, top) = (
+                    scale(window.left(), scale_x)?,
+                    scale(window.top(), scale_y)?,
+                );
+                // usvg rects end at the exclusive right and bottom edges
+                let right = scale(window.right(), scale_x)?;
+                let bottom = scale(window.bottom(), scale_y)?;
+                if right <= left || bottom <= top {
+                    return Ok(None);
+                }
+                Ok::<_, arithmetic::Error>(Some(Rect::from_points(
+                    (left, top),
+                    (right - 1, bottom - 1),
+                )))
+            })()
+            .transpose()
+        });
+        scaled
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| error::Arithmetic {
+                msg: format!("failed to scale SVG windows to {size:#?}"),
+                source: err,
+            })
+            .map_err(Error::from)
+    }
+
+    /// Rasterizes the document stretched to `size`
+    ///
+    /// Marked windows are cleared, so they are found like the
+    /// transparent windows of raster borders.
+    #[inline]
+    pub fn render(&self, size: Size) -> Result<img::Image, Error> {
+        let mut pixmap =
+            tiny_skia::Pixmap::new(size.width, size.height).ok_or(Error::Rasterize(size))?;
+        let document = self.tree.size();
+        let (width, height) = (|| {
+            Ok::<_, arithmetic::Error>((size.width.cast::<f32>()?, size.height.cast::<f32>()?))
+        })()
+        .map_err(|err| error::Arithmetic {
+            msg: format!("failed to compute scale of SVG document for {size:#?}"),
+            source: err,
+        })?;
+        let transform =
+            tiny_skia::Transform::from_scale(width / document.width(), height / document.height());
+        resvg::render(&self.tree, transform, &mut pixmap.as_mut());
+
+        let pixels = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = pixel.demultiply();
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect();
+        let inner = image::RgbaImage::from_vec(size.width, size.height, pixels)
+            .ok_or(Error::Rasterize(size))?;
+        let mut image = img::Image::from_image(&image::DynamicImage::ImageRgba8(inner));
+
+        let bounds = Rect::from(size);
+        for window in self.windows(size)? {
+            let window =
+                Rect::from_points(window.top_left(), (window.right + 1, window.bottom + 1));
+            let window = window.clamp(&bounds);
+            if window.width() > 0 && window.height() > 0 {
+                image.fill_rect(Color::clear(), &window, FillMode::Set)?;
+            }
+        }
+        Ok(image)
+    }
+}
+
+fn marked_windows(group: &usvg::Group, windows: &mut Vec<usvg::Rect>) {
+    for node in group.children() {
+        if node.id().starts_with(WINDOW_ID_PREFIX) {
+            windows.push(node.abs_bounding_box());
+        } else if let usvg::Node::Group(group) = node {
+            marked_windows(group, windows);
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to read SVG document")]
+    Read(
+        #[from]
+        #[source]
+        std::io::Error,
+    ),
+
+    #[error("failed to parse SVG document")]
+    Parse(
+        #[from]
+        #[source]
+        usvg::Error,
+    ),
+
+    #[error("failed to rasterize SVG document with size {0:#?}")]
+    Rasterize(Size),
+
+    #[error("failed to clear window of SVG document")]
+    Window(
+        #[from]
+        #[source]
+        img::FillError,
+    ),
+
+    #[error(transparent)]
+    Arithmetic(#[from] error::Arithmetic),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_svg, Svg};
+    use crate::types::{Rect, Size};
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    const BORDER: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50" viewBox="0 0 100 50">
+  <rect width="100" height="50" fill="#000000"/>
+  <rect id="window" x="10" y="10" width="80" height="30" fill="#202020"/>
+</svg>"##;
+
+    #[test]
+    fn test_is_svg() {
+        assert!(is_svg(BORDER.as_bytes()));
+        assert!(is_svg(b"\xef\xbb\xbf  <svg></svg>"));
+        assert!(!is_svg(b"\x89PNG\r\n\x1a\n"));
+        assert!(!is_svg(b""));
+    }
+
+    #[test]
+    fn test_svg_windows() -> Result<()> {
+        let svg = Svg::from_data(BORDER.as_bytes())?;
+        assert_eq!(
+            svg.size(),
+            Size {
+                width: 100,
+                height: 50
+            }
+        );
+        let size = Size {
+            width: 1000,
+            height: 500,
+        };
+        assert_eq!(
+            svg.windows(size)?,
+            vec![Rect::from_points((100, 100), (899, 399))]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_svg() -> Result<()> {
+        let svg = Svg::from_data(BORDER.as_bytes())?;
+        let image = svg.render(Size {
+            width: 1000,
+            height: 500,
+        })?;
+        assert_eq!(image.size(), Size::from((1000, 500)));
+        assert_eq!(*image.get_pixel(50, 50), image::Rgba([0, 0, 0, 255]));
+        // the marked window is cleared
+        assert_eq!(image.get_pixel(500, 250)[3], 0);
+        // edges stay sharp when rasterized at a large size
+        assert_eq!(image.get_pixel(99, 250)[3], 255);
+        assert_eq!(image.get_pixel(100, 250)[3], 0);
+        Ok(())
+    }
+}
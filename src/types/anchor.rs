@@ -0,0 +1,91 @@
+use crate::error;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Position of the content inside the output canvas
+///
+/// `horizontal` and `vertical` range from 0 (left, top)
+/// over 0.5 (center) to 1 (right, bottom).
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+pub struct Anchor {
+    pub horizontal: f32,
+    pub vertical: f32,
+}
+
+impl Default for Anchor {
+    #[inline]
+    fn default() -> Self {
+        Self::center()
+    }
+}
+
+#[wasm_bindgen]
+impl Anchor {
+    #[wasm_bindgen(constructor)]
+    #[inline]
+    #[must_use]
+    pub fn new(horizontal: f32, vertical: f32) -> Self {
+        Self {
+            horizontal,
+            vertical,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn center() -> Self {
+        Self::new(0.5, 0.5)
+    }
+}
+
+impl std::str::FromStr for Anchor {
+    type Err = error::ParseEnum;
+
+    /// Parses a named anchor such as `top-left` or a `HORIZONTAL,VERTICAL` pair
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        if let Some((horizontal, vertical)) = s.split_once(',') {
+            if let (Ok(horizontal), Ok(vertical)) =
+                (horizontal.trim().parse(), vertical.trim().parse())
+            {
+                return Ok(Self::new(horizontal, vertical));
+            }
+        }
+        match s.as_str() {
+            "center" => Ok(Self::new(0.5, 0.5)),
+            "top" => Ok(Self::new(0.5, 0.0)),
+            "bottom" => Ok(Self::new(0.5, 1.0)),
+            "left" => Ok(Self::new(0.0, 0.5)),
+            "right" => Ok(Self::new(1.0, 0.5)),
+            "top-left" => Ok(Self::new(0.0, 0.0)),
+            "top-right" => Ok(Self::new(1.0, 0.0)),
+            "bottom-left" => Ok(Self::new(0.0, 1.0)),
+            "bottom-right" => Ok(Self::new(1.0, 1.0)),
+            _ => Err(error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Anchor;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_anchor() {
+        assert_eq!(Anchor::from_str("center").ok(), Some(Anchor::center()));
+        assert_eq!(
+            Anchor::from_str("Bottom-Left").ok(),
+            Some(Anchor::new(0.0, 1.0))
+        );
+        assert_eq!(
+            Anchor::from_str("0.5, 0.4").ok(),
+            Some(Anchor::new(0.5, 0.4))
+        );
+        assert!(Anchor::from_str("middle").is_err());
+        assert!(Anchor::from_str("0.5,up").is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use super::Size;
+use crate::arithmetic::{self, ops::CheckedMul, Cast};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Exact aspect ratio of the output image
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[serde(try_from = "AspectRatioRepr")]
+pub struct AspectRatio {
+    width: u32,
+    height: u32,
+}
+
+/// Aspect ratios can be deserialized from their components or a string
+/// such as `4:5` or `portrait`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AspectRatioRepr {
+    Components { width: u32, height: u32 },
+    Named(String),
+}
+
+impl TryFrom<AspectRatioRepr> for AspectRatio {
+    type Error = ParseError;
+
+    #[inline]
+    fn try_from(repr: AspectRatioRepr) -> Result<Self, Self::Error> {
+        match repr {
+            AspectRatioRepr::Components { width, height } => Self::new(width, height)
+                .ok_or_else(|| ParseError::Invalid(format!("{width}:{height}"))),
+            AspectRatioRepr::Named(name) => name.parse(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl AspectRatio {
+    /// Instagram portrait (4:5)
+    #[inline]
+    #[must_use]
+    pub fn portrait() -> Self {
+        Self {
+            width: 4,
+            height: 5,
+        }
+    }
+
+    /// Square (1:1)
+    #[inline]
+    #[must_use]
+    pub fn square() -> Self {
+        Self {
+            width: 1,
+            height: 1,
+        }
+    }
+
+    /// Stories and reels (9:16)
+    #[inline]
+    #[must_use]
+    pub fn story() -> Self {
+        Self {
+            width: 9,
+            height: 16,
+        }
+    }
+
+    /// 35mm film and prints (3:2)
+    #[inline]
+    #[must_use]
+    pub fn landscape() -> Self {
+        Self {
+            width: 3,
+            height: 2,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl AspectRatio {
+    /// Aspect ratio of `width:height` in lowest terms
+    #[inline]
+    #[must_use]
+    pub fn new(width: u32, height: u32) -> Option<Self> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let gcd = num::integer::gcd(width, height);
+        Some(Self {
+            width: width / gcd,
+            height: height / gcd,
+        })
+    }
+
+    /// Smallest size of this aspect ratio that contains `size`
+    #[inline]
+    pub fn expand(self, size: Size) -> Result<Size, ResizeError> {
+        let units = u64::from(size.width)
+            .div_ceil(u64::from(self.width))
+            .max(u64::from(size.height).div_ceil(u64::from(self.height)))
+            .max(1);
+        self.scale(units).map_err(|err| ResizeError {
+            ratio: self,
+            size,
+            source: err,
+        })
+    }
+
+    /// Largest size of this aspect ratio that fits into `size`
+    ///
+    /// The constrained axis is rounded down, so the size never exceeds
+    /// `size` even if the ratio can not be matched exactly.
+    #[inline]
+    pub fn fit_within(self, size: Size) -> Result<Size, ResizeError> {
+        let fitted = (|| {
+            let (width, height) = (u64::from(size.width), u64::from(size.height));
+            let (ratio_width, ratio_height) = (u64::from(self.width), u64::from(self.height));
+            let width_height = CheckedMul::checked_mul(width, ratio_height)?;
+            let height_width = CheckedMul::checked_mul(height, ratio_width)?;
+            if width_height <= height_width {
+                let fitted = width_height / ratio_width;
+                Ok::<_, arithmetic::Error>(Size {
+                    width: size.width,
+                    height: fitted.max(1).min(height).cast::<u32>()?,
+                })
+            } else {
+                let fitted = height_width / ratio_height;
+                Ok(Size {
+                    width: fitted.max(1).min(width).cast::<u32>()?,
+                    height: size.height,
+                })
+            }
+        })();
+        fitted.map_err(|err| ResizeError {
+            ratio: self,
+            size,
+            source: err,
+        })
+    }
+
+    #[inline]
+    fn scale(self, units: u64) -> Result<Size, arithmetic::Error> {
+        let width = CheckedMul::checked_mul(u64::from(self.width), units)?;
+        let height = CheckedMul::checked_mul(u64::from(self.height), units)?;
+        Ok(Size {
+            width: width.cast::<u32>()?,
+            height: height.cast::<u32>()?,
+        })
+    }
+}
+
+impl std::fmt::Display for AspectRatio {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.width, self.height)
+    }
+}
+
+impl std::str::FromStr for AspectRatio {
+    type Err = ParseError;
+
+    /// Parses a preset name or a `WIDTH:HEIGHT` ratio such as `1.91:1`
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::Invalid(s.to_string());
+        match s.trim().to_ascii_lowercase().as_str() {
+            "portrait" | "instagram" => return Ok(Self::portrait()),
+            "square" => return Ok(Self::square()),
+            "story" | "reel" => return Ok(Self::story()),
+            "landscape" | "35mm" => return Ok(Self::landscape()),
+            _ => {}
+        }
+        let (width, height) = s
+            .split_once(':')
+            .or_else(|| s.split_once('/'))
+            .ok_or_else(invalid)?;
+        // allow up to three decimal places
+        let component = |value: &str| {
+            let value = value.trim().parse::<f64>().map_err(|_| invalid())?;
+            let value = (value * 1000.0).round();
+            if value.is_finite() && value >= 1.0 && value <= f64::from(u32::MAX) {
+                value.cast::<u32>().map_err(|_| invalid())
+            } else {
+                Err(invalid())
+            }
+        };
+        Self::new(component(width)?, component(height)?).ok_or_else(invalid)
+    }
+}
+
+#[derive(thiserror::Error, PartialEq, Eq, Clone, Debug)]
+pub enum ParseError {
+    #[error("invalid aspect ratio `{0}` (expected `WIDTH:HEIGHT` or one of portrait, square, story, landscape)")]
+    Invalid(String),
+}
+
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+#[error("failed to resize {size} to aspect ratio {ratio}")]
+pub struct ResizeError {
+    ratio: AspectRatio,
+    size: Size,
+    source: arithmetic::Error,
+}
+
+impl arithmetic::error::Arithmetic for ResizeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::AspectRatio;
+    use crate::types::Size;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_aspect_ratio() {
+        assert_eq!(AspectRatio::from_str("4:5").ok(), AspectRatio::new(4, 5));
+        assert_eq!(AspectRatio::from_str("8/10").ok(), AspectRatio::new(4, 5));
+        assert_eq!(
+            AspectRatio::from_str("1.91:1").ok(),
+            AspectRatio::new(191, 100)
+        );
+        assert_eq!(
+            AspectRatio::from_str("Story").ok(),
+            Some(AspectRatio::story())
+        );
+        assert!(AspectRatio::from_str("0:1").is_err());
+        assert!(AspectRatio::from_str("-4:5").is_err());
+        assert!(AspectRatio::from_str("wide").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_aspect_ratio() -> anyhow::Result<()> {
+        let ratio: AspectRatio = serde_json::from_str(r#""16:9""#)?;
+        assert_eq!(Some(ratio), AspectRatio::new(16, 9));
+        let ratio: AspectRatio = serde_json::from_str(r#"{"width": 6, "height": 4}"#)?;
+        assert_eq!(ratio, AspectRatio::landscape());
+        assert!(serde_json::from_str::<AspectRatio>(r#"{"width": 0, "height": 4}"#).is_err());
+        Ok(())
+    }
+
+    macro_rules! resize_tests {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (ratio, size, expand, fit_within): (AspectRatio, (u32, u32), (u32, u32), (u32, u32)) = $values;
+                    assert_eq!(ratio.expand(Size::from(size)).ok(), Some(Size::from(expand)));
+                    assert_eq!(ratio.fit_within(Size::from(size)).ok(), Some(Size::from(fit_within)));
+                }
+            )*
+        }
+    }
+
+    resize_tests! {
+        test_portrait_from_landscape: (AspectRatio::portrait(), (1200, 800), (1200, 1500), (640, 800)),
+        test_portrait_from_portrait: (AspectRatio::portrait(), (1001, 1500), (1200, 1500), (1001, 1251)),
+        test_square: (AspectRatio::square(), (300, 200), (300, 300), (200, 200)),
+        test_story: (AspectRatio::story(), (1080, 1080), (1080, 1920), (607, 1080)),
+        test_landscape_exact: (AspectRatio::landscape(), (600, 400), (600, 400), (600, 400)),
+        test_empty: (AspectRatio::landscape(), (0, 0), (3, 2), (0, 0)),
+    }
+
+    #[test]
+    fn test_fit_within_decimal_ratio() {
+        let ratio = AspectRatio::from_str("1.91:1").unwrap();
+        assert_eq!(
+            ratio.fit_within(Size::from((1080, 1080))).ok(),
+            Some(Size::from((1080, 565)))
+        );
+        assert_eq!(
+            ratio.fit_within(Size::from((1080, 500))).ok(),
+            Some(Size::from((955, 500)))
+        );
+    }
+
+    #[test]
+    fn test_fit_within_smaller_than_ratio() {
+        let ratio = AspectRatio::from_str("1.91:1").unwrap();
+        assert_eq!(
+            ratio.fit_within(Size::from((100, 50))).ok(),
+            Some(Size::from((95, 50)))
+        );
+        assert_eq!(
+            ratio.fit_within(Size::from((1, 1))).ok(),
+            Some(Size::from((1, 1)))
+        );
+        assert_eq!(
+            AspectRatio::story().fit_within(Size::from((5, 3))).ok(),
+            Some(Size::from((1, 3)))
+        );
+    }
+}
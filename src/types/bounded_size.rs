@@ -22,7 +22,6 @@ impl From<Size> for BoundedSize {
 
 impl ClampMin for BoundedSize {
     #[inline]
-    #[must_use]
     fn clamp_min<MIN>(self, min: MIN) -> Self
     where
         MIN: Into<Self>,
@@ -41,10 +41,31 @@ fn hex_to_color(hex: &str) -> Result<Color, Error> {
 
 #[wasm_bindgen]
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default, Copy, Clone)]
+#[serde(try_from = "ColorRepr")]
 pub struct Color {
     rgba: [u8; 4],
 }
 
+/// Colors can be deserialized from their RGBA components or a HEX string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorRepr {
+    Rgba { rgba: [u8; 4] },
+    Hex(String),
+}
+
+impl TryFrom<ColorRepr> for Color {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(repr: ColorRepr) -> Result<Self, Self::Error> {
+        match repr {
+            ColorRepr::Rgba { rgba } => Ok(Self { rgba }),
+            ColorRepr::Hex(hex) => hex_to_color(&hex),
+        }
+    }
+}
+
 #[wasm_bindgen]
 impl Color {
     #[cfg(target_arch = "wasm32")]
@@ -149,4 +170,13 @@ mod tests {
         test_parse_invalid_hex_color_2: ("487f5", None),
         test_parse_invalid_hex_color_3: ("#e942g5", None),
     }
+
+    #[test]
+    fn test_deserialize_color() {
+        let rgba: Color = serde_json::from_str(r#"{"rgba":[1,2,3,4]}"#).unwrap();
+        assert_eq!(rgba, Color::rgba(1, 2, 3, 4));
+        let hex: Color = serde_json::from_str(r##""#4287f5""##).unwrap();
+        assert_eq!(hex, Color::rgba(66, 135, 245, 255));
+        assert!(serde_json::from_str::<Color>(r#""blue""#).is_err());
+    }
 }
@@ -1,3 +1,5 @@
+pub mod anchor;
+pub mod aspect_ratio;
 pub mod bounded_size;
 pub mod color;
 pub mod point;
@@ -5,7 +7,10 @@ pub mod rect;
 pub mod rotation;
 pub mod sides;
 pub mod size;
+pub mod transform;
 
+pub use anchor::Anchor;
+pub use aspect_ratio::AspectRatio;
 pub use bounded_size::BoundedSize;
 pub use color::Color;
 pub use point::Point;
@@ -13,6 +18,7 @@ pub use rect::Rect;
 pub use rotation::Rotation;
 pub use sides::{abs::Sides, percent::Sides as SidesPercent};
 pub use size::Size;
+pub use transform::Transform;
 
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
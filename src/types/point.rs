@@ -68,7 +68,6 @@ impl Default for Point {
 
 impl From<(i64, i64)> for Point {
     #[inline]
-    #[must_use]
     fn from(coords: (i64, i64)) -> Self {
         Self {
             x: coords.0,
@@ -79,7 +78,6 @@ impl From<(i64, i64)> for Point {
 
 impl From<Size> for Point {
     #[inline]
-    #[must_use]
     fn from(size: Size) -> Self {
         Self {
             x: i64::from(size.width),
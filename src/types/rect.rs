@@ -279,6 +279,96 @@ impl Rect {
         contains_tl || contains_br
     }
 
+    /// Maps the rect in an image of `size` into the image rotated by `rotation`
+    #[inline]
+    pub fn rotate(self, size: Size, rotation: super::Rotation) -> Result<Self, RotateError> {
+        use super::Rotation;
+        match (|| {
+            let width = i64::from(size.width);
+            let height = i64::from(size.height);
+            let rect = match rotation {
+                Rotation::Rotate0 => self,
+                Rotation::Rotate90 => Self::from_points(
+                    (CheckedSub::checked_sub(height, self.bottom)?, self.left),
+                    (CheckedSub::checked_sub(height, self.top)?, self.right),
+                ),
+                Rotation::Rotate180 => Self::from_points(
+                    (
+                        CheckedSub::checked_sub(width, self.right)?,
+                        CheckedSub::checked_sub(height, self.bottom)?,
+                    ),
+                    (
+                        CheckedSub::checked_sub(width, self.left)?,
+                        CheckedSub::checked_sub(height, self.top)?,
+                    ),
+                ),
+                Rotation::Rotate270 => Self::from_points(
+                    (self.top, CheckedSub::checked_sub(width, self.right)?),
+                    (self.bottom, CheckedSub::checked_sub(width, self.left)?),
+                ),
+            };
+            Ok::<_, arithmetic::Error>(rect)
+        })() {
+            Ok(rect) => Ok(rect),
+            Err(err) => Err(RotateError {
+                rect: self,
+                size,
+                rotation,
+                source: err,
+            }),
+        }
+    }
+
+    /// Maps the rect in an image of `size` into the image resized to `scaled`
+    ///
+    /// The bottom right corner is exclusive, and the mapped rect spans
+    /// every pixel that the rect covers at least partially after resizing.
+    #[inline]
+    pub fn scale(self, size: Size, scaled: Size) -> Result<Self, ScaleError> {
+        self.scale_with(size, scaled, Rounding::Down, Rounding::Up)
+    }
+
+    /// Maps the rect in an image of `size` into the image resized to `scaled`,
+    /// rounding its edges to the nearest pixel boundary
+    ///
+    /// Unlike [`Rect::scale`], the size of the rect is kept as close as
+    /// possible, which suits rects declared in pixel coordinates.
+    #[inline]
+    pub fn scale_nearest(self, size: Size, scaled: Size) -> Result<Self, ScaleError> {
+        self.scale_with(size, scaled, Rounding::Nearest, Rounding::Nearest)
+    }
+
+    #[inline]
+    fn scale_with(
+        self,
+        size: Size,
+        scaled: Size,
+        start: Rounding,
+        end: Rounding,
+    ) -> Result<Self, ScaleError> {
+        match (|| {
+            let rect = Self::from_points(
+                (
+                    scale_coordinate(self.left, size.width, scaled.width, start)?,
+                    scale_coordinate(self.top, size.height, scaled.height, start)?,
+                ),
+                (
+                    scale_coordinate(self.right, size.width, scaled.width, end)?,
+                    scale_coordinate(self.bottom, size.height, scaled.height, end)?,
+                ),
+            );
+            Ok::<_, arithmetic::Error>(rect)
+        })() {
+            Ok(rect) => Ok(rect),
+            Err(err) => Err(ScaleError {
+                rect: self,
+                size,
+                scaled,
+                source: err,
+            }),
+        }
+    }
+
     #[inline]
     pub fn extend_to(&mut self, point: &Point) {
         self.top = self.top.min(point.y);
@@ -288,6 +378,40 @@ impl Rect {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+enum Rounding {
+    Down,
+    Up,
+    Nearest,
+}
+
+/// Maps `value` in an image of `size` into the image resized to `scaled`
+#[inline]
+fn scale_coordinate(
+    value: i64,
+    size: u32,
+    scaled: u32,
+    rounding: Rounding,
+) -> Result<i64, arithmetic::Error> {
+    let product = CheckedMul::checked_mul(value, i64::from(scaled))?;
+    let size = i64::from(size);
+    let product = match rounding {
+        // rounds half up after flooring
+        Rounding::Nearest => CheckedAdd::checked_add(product, size / 2)?,
+        Rounding::Down | Rounding::Up => product,
+    };
+    let quotient = CheckedDiv::checked_div(product, size)?;
+    if CheckedMul::checked_mul(quotient, size)? == product {
+        return Ok(quotient);
+    }
+    // the quotient is rounded towards zero
+    match (rounding, product > 0) {
+        (Rounding::Up, true) => Ok(CheckedAdd::checked_add(quotient, 1)?),
+        (Rounding::Down | Rounding::Nearest, false) => Ok(CheckedSub::checked_sub(quotient, 1)?),
+        _ => Ok(quotient),
+    }
+}
+
 impl From<Size> for Rect {
     fn from(size: Size) -> Self {
         Self::from_points((0, 0), size)
@@ -467,6 +591,28 @@ pub struct PadError {
 
 impl arithmetic::error::Arithmetic for PadError {}
 
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+#[error("failed to rotate {rect:#?} in {size:#?} by {rotation:?}")]
+pub struct RotateError {
+    rect: Rect,
+    size: Size,
+    rotation: super::Rotation,
+    source: arithmetic::Error,
+}
+
+impl arithmetic::error::Arithmetic for RotateError {}
+
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+#[error("failed to scale {rect:#?} from {size:#?} to {scaled:#?}")]
+pub struct ScaleError {
+    rect: Rect,
+    size: Size,
+    scaled: Size,
+    source: arithmetic::Error,
+}
+
+impl arithmetic::error::Arithmetic for ScaleError {}
+
 #[derive(thiserror::Error, PartialEq, Clone, Debug)]
 pub enum SubSidesError {
     #[error("subtracting {sides:#?} from {rect:#?} exceeds bounds")]
@@ -498,4 +644,79 @@ mod tests {
             .checked_mul(u64::from(u32::MAX))
             .is_some());
     }
+
+    #[test]
+    fn test_rotate() -> Result<(), RotateError> {
+        use crate::types::{Rotation, Size};
+        let size = Size {
+            width: 100,
+            height: 50,
+        };
+        let rect = Rect::from_points((10, 5), (30, 15));
+        assert_eq!(
+            rect.rotate(size, Rotation::Rotate90)?,
+            Rect::from_points((35, 10), (45, 30))
+        );
+        assert_eq!(
+            rect.rotate(size, Rotation::Rotate180)?,
+            Rect::from_points((70, 35), (90, 45))
+        );
+        assert_eq!(
+            rect.rotate(size, Rotation::Rotate270)?,
+            Rect::from_points((5, 70), (15, 90))
+        );
+        // rotating back and forth is lossless
+        let rotated = rect.rotate(size, Rotation::Rotate90)?;
+        let size = Size {
+            width: 50,
+            height: 100,
+        };
+        assert_eq!(rotated.rotate(size, Rotation::Rotate270)?, rect);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scale() -> Result<(), ScaleError> {
+        use crate::types::Size;
+        let rect = Rect::from_points((10, 5), (30, 15));
+        let size = Size {
+            width: 100,
+            height: 50,
+        };
+        let scaled = Size {
+            width: 250,
+            height: 100,
+        };
+        assert_eq!(
+            rect.scale(size, scaled)?,
+            Rect::from_points((25, 10), (75, 30))
+        );
+        let pixel = Rect::from_points((1, 1), (2, 2));
+        let scaled = Size {
+            width: 200,
+            height: 100,
+        };
+        assert_eq!(
+            pixel.scale(scaled, size)?,
+            Rect::from_points((0, 0), (1, 1))
+        );
+        let size = Size {
+            width: 3,
+            height: 3,
+        };
+        let scaled = Size {
+            width: 2,
+            height: 2,
+        };
+        // a single pixel covers parts of two pixels when shrinking by 2/3
+        assert_eq!(
+            pixel.scale(size, scaled)?,
+            Rect::from_points((0, 0), (2, 2))
+        );
+        assert_eq!(
+            pixel.scale_nearest(size, scaled)?,
+            Rect::from_points((1, 1), (1, 1))
+        );
+        Ok(())
+    }
 }
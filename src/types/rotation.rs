@@ -18,6 +18,31 @@ impl Default for Rotation {
     }
 }
 
+impl Rotation {
+    #[inline]
+    #[must_use]
+    pub fn quarter_turns(self) -> u8 {
+        match self {
+            Rotation::Rotate0 => 0,
+            Rotation::Rotate90 => 1,
+            Rotation::Rotate180 => 2,
+            Rotation::Rotate270 => 3,
+        }
+    }
+
+    /// Rotation by `self` followed by `other`
+    #[inline]
+    #[must_use]
+    pub fn then(self, other: Self) -> Self {
+        match (self.quarter_turns() + other.quarter_turns()) % 4 {
+            1 => Rotation::Rotate90,
+            2 => Rotation::Rotate180,
+            3 => Rotation::Rotate270,
+            _ => Rotation::Rotate0,
+        }
+    }
+}
+
 impl std::str::FromStr for Rotation {
     type Err = error::ParseEnum;
 
@@ -10,6 +10,7 @@ pub mod percent {
 
     #[wasm_bindgen]
     #[derive(Serialize, Deserialize, PartialEq, Debug, Default, Copy, Clone)]
+    #[serde(from = "SidesRepr")]
     pub struct Sides {
         pub top: f32,
         pub left: f32,
@@ -17,6 +18,39 @@ pub mod percent {
         pub right: f32,
     }
 
+    /// Sides can be deserialized from a single uniform value
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SidesRepr {
+        Uniform(f32),
+        Sides {
+            top: f32,
+            left: f32,
+            bottom: f32,
+            right: f32,
+        },
+    }
+
+    impl From<SidesRepr> for Sides {
+        #[inline]
+        fn from(repr: SidesRepr) -> Self {
+            match repr {
+                SidesRepr::Uniform(side) => Self::uniform(side),
+                SidesRepr::Sides {
+                    top,
+                    left,
+                    bottom,
+                    right,
+                } => Self {
+                    top,
+                    left,
+                    bottom,
+                    right,
+                },
+            }
+        }
+    }
+
     impl arithmetic::Type for Sides {}
 
     #[wasm_bindgen]
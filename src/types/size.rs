@@ -138,6 +138,34 @@ impl Size {
         })
     }
 
+    /// Positions a child of `size` inside `self` according to `anchor`
+    #[inline]
+    pub fn anchor(self, size: Self, anchor: super::Anchor) -> Result<super::Rect, AnchorError> {
+        let parent: Point = self.into();
+        let child: Point = size.into();
+        match (|| {
+            let free = parent.checked_sub(child)?;
+            let horizontal = f64::from(anchor.horizontal).clamp(0.0, 1.0);
+            let vertical = f64::from(anchor.vertical).clamp(0.0, 1.0);
+            let x = CheckedMul::checked_mul(free.x.cast::<f64>()?, horizontal)?;
+            let y = CheckedMul::checked_mul(free.y.cast::<f64>()?, vertical)?;
+            let top_left = Point {
+                x: x.round().cast::<i64>()?,
+                y: y.round().cast::<i64>()?,
+            };
+            let bottom_right = top_left.checked_add(child)?;
+            Ok::<_, arithmetic::Error>(Rect::from_points(top_left, bottom_right))
+        })() {
+            Ok(rect) => Ok(rect),
+            Err(err) => Err(AnchorError {
+                child: size,
+                parent: self,
+                anchor,
+                source: err,
+            }),
+        }
+    }
+
     #[inline]
     pub fn center(self, size: Self) -> Result<super::Rect, CenterError> {
         let parent: Point = self.into();
@@ -600,10 +628,21 @@ pub struct CenterError {
 
 impl arithmetic::error::Arithmetic for CenterError {}
 
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+#[error("failed to anchor {child:#?} in {parent:#?} at {anchor:?}")]
+pub struct AnchorError {
+    child: Size,
+    parent: Size,
+    anchor: super::Anchor,
+    source: arithmetic::Error,
+}
+
+impl arithmetic::error::Arithmetic for AnchorError {}
+
 #[cfg(test)]
 mod tests {
     use super::Size;
-    use crate::types::{CropMode, Point, Rect};
+    use crate::types::{Anchor, CropMode, Point, Rect};
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -825,6 +864,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn anchor_smaller_size() {
+        let parent = Size {
+            width: 10,
+            height: 20,
+        };
+        let child = Size {
+            width: 4,
+            height: 4,
+        };
+        let anchored = |horizontal, vertical| {
+            parent
+                .anchor(child, Anchor::new(horizontal, vertical))
+                .ok()
+                .map(|rect| rect.top_left())
+        };
+        assert_eq!(anchored(0.5, 0.5), Some(Point { x: 3, y: 8 }));
+        assert_eq!(anchored(0.0, 0.0), Some(Point { x: 0, y: 0 }));
+        assert_eq!(anchored(1.0, 1.0), Some(Point { x: 6, y: 16 }));
+        assert_eq!(anchored(0.5, 0.25), Some(Point { x: 3, y: 4 }));
+        assert_eq!(anchored(-1.0, 2.0), Some(Point { x: 0, y: 16 }));
+        assert_eq!(
+            parent.anchor(child, Anchor::center()).ok(),
+            parent.center(child).ok()
+        );
+    }
+
     #[test]
     fn center_equal_size() {
         assert_eq!(
@@ -0,0 +1,275 @@
+use super::{Point, Rect, Size};
+use crate::arithmetic::{self, Cast};
+use serde::{Deserialize, Serialize};
+
+/// Framing of an image inside its border window
+///
+/// All coordinates are normalized to the size of the image.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+pub enum Transform {
+    /// Keep the focal point `(x, y)` as close to the center of the
+    /// window as possible
+    ///
+    /// A `zoom` of 1 covers the window with the whole image,
+    /// larger values zoom in.
+    Focus { x: f32, y: f32, zoom: f32 },
+    /// Cover the window with an explicit region of the image
+    Region {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+}
+
+impl Default for Transform {
+    #[inline]
+    fn default() -> Self {
+        Self::Focus {
+            x: 0.5,
+            y: 0.5,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Transform {
+    #[inline]
+    #[must_use]
+    pub fn is_finite(&self) -> bool {
+        match *self {
+            Self::Focus { x, y, zoom } => [x, y, zoom].iter().all(|v| v.is_finite()),
+            Self::Region {
+                x,
+                y,
+                width,
+                height,
+            } => [x, y, width, height].iter().all(|v| v.is_finite()),
+        }
+    }
+
+    /// Region of an image of `size` that is drawn into `window`
+    #[inline]
+    pub fn source_rect(&self, size: Size, window: Size) -> Result<Rect, SourceRectError> {
+        if !self.is_finite() {
+            return Err(SourceRectError::NonFinite(*self));
+        }
+        match (|| {
+            let width = f64::from(size.width);
+            let height = f64::from(size.height);
+            let (left, top, right, bottom) = match *self {
+                Self::Focus { x, y, zoom } => {
+                    let window_width = f64::from(window.width.max(1));
+                    let window_height = f64::from(window.height.max(1));
+                    let scale = (width / window_width).min(height / window_height)
+                        / f64::from(zoom).max(1.0);
+                    let region_width = (window_width * scale).round().clamp(1.0, width.max(1.0));
+                    let region_height = (window_height * scale).round().clamp(1.0, height.max(1.0));
+
+                    let center_x = f64::from(x).clamp(0.0, 1.0) * width;
+                    let center_y = f64::from(y).clamp(0.0, 1.0) * height;
+                    let left = (center_x - region_width / 2.0)
+                        .round()
+                        .clamp(0.0, (width - region_width).max(0.0));
+                    let top = (center_y - region_height / 2.0)
+                        .round()
+                        .clamp(0.0, (height - region_height).max(0.0));
+                    (left, top, left + region_width, top + region_height)
+                }
+                Self::Region {
+                    x,
+                    y,
+                    width: region_width,
+                    height: region_height,
+                } => {
+                    let left = (f64::from(x).clamp(0.0, 1.0) * width).round();
+                    let top = (f64::from(y).clamp(0.0, 1.0) * height).round();
+                    let right = (f64::from(x + region_width) * width)
+                        .round()
+                        .clamp(left + 1.0, width.max(left + 1.0));
+                    let bottom = (f64::from(y + region_height) * height)
+                        .round()
+                        .clamp(top + 1.0, height.max(top + 1.0));
+                    (left.min(width - 1.0), top.min(height - 1.0), right, bottom)
+                }
+            };
+            let top_left = Point {
+                x: left.max(0.0).cast::<i64>()?,
+                y: top.max(0.0).cast::<i64>()?,
+            };
+            let bottom_right = Point {
+                x: right.min(width).cast::<i64>()?,
+                y: bottom.min(height).cast::<i64>()?,
+            };
+            Ok::<_, arithmetic::Error>(Rect::from_points(top_left, bottom_right))
+        })() {
+            Ok(rect) => Ok(rect),
+            Err(err) => Err(SourceRectError::Arithmetic {
+                transform: *self,
+                size,
+                window,
+                source: err,
+            }),
+        }
+    }
+}
+
+impl std::str::FromStr for Transform {
+    type Err = ParseError;
+
+    /// Parses `focus:X,Y[,ZOOM]` or `region:X,Y,WIDTH,HEIGHT`
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, values) = s
+            .split_once(':')
+            .ok_or_else(|| ParseError::Invalid(s.to_string()))?;
+        let values = values
+            .split(',')
+            .map(|value| value.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ParseError::Invalid(s.to_string()))?;
+        let transform = match (kind.trim().to_ascii_lowercase().as_str(), values.as_slice()) {
+            ("focus", &[x, y]) => Self::Focus { x, y, zoom: 1.0 },
+            ("focus", &[x, y, zoom]) => Self::Focus { x, y, zoom },
+            ("region", &[x, y, width, height]) => Self::Region {
+                x,
+                y,
+                width,
+                height,
+            },
+            _ => return Err(ParseError::Invalid(s.to_string())),
+        };
+        if !transform.is_finite() {
+            return Err(ParseError::NonFinite(s.to_string()));
+        }
+        Ok(transform)
+    }
+}
+
+#[derive(thiserror::Error, PartialEq, Eq, Clone, Debug)]
+pub enum ParseError {
+    #[error("invalid transform `{0}` (expected `focus:X,Y[,ZOOM]` or `region:X,Y,WIDTH,HEIGHT`)")]
+    Invalid(String),
+
+    #[error("transform `{0}` has non-finite values")]
+    NonFinite(String),
+}
+
+#[derive(thiserror::Error, PartialEq, Clone, Debug)]
+pub enum SourceRectError {
+    #[error("transform {0:?} has non-finite values")]
+    NonFinite(Transform),
+
+    #[error("failed to compute source rect of {transform:?} for image of size {size} in window of size {window}")]
+    Arithmetic {
+        transform: Transform,
+        size: Size,
+        window: Size,
+        source: arithmetic::Error,
+    },
+}
+
+impl arithmetic::error::Arithmetic for SourceRectError {}
+
+#[cfg(test)]
+mod tests {
+    use super::Transform;
+    use crate::types::{Rect, Size};
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_transform() {
+        assert_eq!(
+            Transform::from_str("focus:0.3, 0.6").ok(),
+            Some(Transform::Focus {
+                x: 0.3,
+                y: 0.6,
+                zoom: 1.0
+            })
+        );
+        assert_eq!(
+            Transform::from_str("Focus:0.3,0.6,2").ok(),
+            Some(Transform::Focus {
+                x: 0.3,
+                y: 0.6,
+                zoom: 2.0
+            })
+        );
+        assert_eq!(
+            Transform::from_str("region:0,0.1,0.5,0.5").ok(),
+            Some(Transform::Region {
+                x: 0.0,
+                y: 0.1,
+                width: 0.5,
+                height: 0.5
+            })
+        );
+        assert!(Transform::from_str("region:0,0.1").is_err());
+        assert!(Transform::from_str("zoom:2").is_err());
+        assert!(Transform::from_str("0.5,0.5").is_err());
+        assert!(Transform::from_str("focus:NaN,0.5").is_err());
+        assert!(Transform::from_str("region:0,0,inf,1").is_err());
+    }
+
+    #[test]
+    fn test_non_finite_source_rect() {
+        let size = Size::from((100, 100));
+        for transform in [
+            Transform::Focus {
+                x: f32::NAN,
+                y: 0.5,
+                zoom: 1.0,
+            },
+            Transform::Region {
+                x: 0.0,
+                y: f32::NEG_INFINITY,
+                width: 1.0,
+                height: f32::NAN,
+            },
+        ] {
+            assert!(transform.source_rect(size, size).is_err());
+        }
+    }
+
+    macro_rules! source_rect_tests {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (transform, size, window, expected): (Transform, (u32, u32), (u32, u32), _) = $values;
+                    let rect = transform.source_rect(Size::from(size), Size::from(window));
+                    assert_eq!(rect.ok(), Some(Rect::new(expected.0, expected.1).unwrap()));
+                }
+            )*
+        }
+    }
+
+    source_rect_tests! {
+        test_focus_center: (
+            Transform::default(), (300, 200), (100, 100), ((50, 0), (200, 200))),
+        test_focus_left: (
+            Transform::Focus { x: 0.0, y: 0.5, zoom: 1.0 },
+            (300, 200), (100, 100), ((0, 0), (200, 200))),
+        test_focus_right_of_center: (
+            Transform::Focus { x: 0.6, y: 0.5, zoom: 1.0 },
+            (300, 200), (100, 100), ((80, 0), (200, 200))),
+        test_focus_zoom: (
+            Transform::Focus { x: 0.25, y: 0.25, zoom: 2.0 },
+            (400, 400), (100, 50), ((0, 50), (200, 100))),
+        test_focus_zoom_out_is_clamped: (
+            Transform::Focus { x: 0.5, y: 0.5, zoom: 0.5 },
+            (300, 200), (100, 100), ((50, 0), (200, 200))),
+        test_region: (
+            Transform::Region { x: 0.1, y: 0.2, width: 0.5, height: 0.5 },
+            (100, 200), (10, 10), ((10, 40), (50, 100))),
+        test_region_out_of_bounds: (
+            Transform::Region { x: 0.8, y: -0.5, width: 0.5, height: 2.0 },
+            (100, 100), (10, 10), ((80, 0), (20, 100))),
+        test_region_empty: (
+            Transform::Region { x: 1.0, y: 1.0, width: 0.0, height: 0.0 },
+            (100, 100), (10, 10), ((99, 99), (1, 1))),
+        test_focus_empty_image: (
+            Transform::default(), (0, 0), (10, 10), ((0, 0), (0, 0))),
+    }
+}
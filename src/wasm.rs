@@ -1,4 +1,4 @@
-use crate::{border, builtin, error::Report, img, options};
+use crate::{border, builtin, error::Report, img, options, procedural, registry, types};
 use image::{DynamicImage, ImageBuffer};
 use wasm_bindgen::{prelude::*, Clamped};
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
@@ -28,6 +28,7 @@ fn image_from_canvas(
 pub struct Border {
     #[cfg(feature = "builtin")]
     builtin: Option<builtin::Builtin>,
+    procedural: Option<procedural::Film>,
     custom: Option<ImageData>,
 }
 
@@ -37,7 +38,11 @@ impl Border {
     #[must_use]
     #[inline]
     pub fn new(custom: Option<ImageData>, builtin: Option<builtin::Builtin>) -> Border {
-        Border { builtin, custom }
+        Border {
+            builtin,
+            custom,
+            ..Default::default()
+        }
     }
 
     #[must_use]
@@ -56,6 +61,33 @@ impl Border {
             ..Default::default()
         }
     }
+
+    /// Builtin border by its name or alias in the catalog
+    #[inline]
+    pub fn named(name: &str) -> Result<Border, JsError> {
+        let builtin = registry::Registry::builtin()
+            .find(name)
+            .and_then(registry::Entry::builtin)
+            .ok_or_else(|| JsError::new(&registry::Error::Unknown(name.to_string()).report()))?;
+        Ok(Self::builtin(builtin))
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn procedural(film: procedural::Film) -> Border {
+        Border {
+            procedural: Some(film),
+            ..Default::default()
+        }
+    }
+}
+
+/// Catalog of the builtin borders as JSON for a border picker
+#[wasm_bindgen]
+#[inline]
+pub fn border_catalog() -> Result<String, JsError> {
+    let registry = registry::Registry::builtin();
+    serde_json::to_string(registry.entries()).map_err(|err| JsError::new(&err.to_string()))
 }
 
 #[wasm_bindgen]
@@ -70,17 +102,13 @@ impl Image {
         canvas: &HtmlCanvasElement,
         ctx: &CanvasRenderingContext2d,
     ) -> Result<Image, JsValue> {
-        let inner = image_from_canvas(canvas, ctx)?.to_rgba8();
-        Ok(Image {
-            inner: img::Image { inner, path: None },
-        })
+        let inner = img::Image::from_image(&image_from_canvas(canvas, ctx)?);
+        Ok(Image { inner })
     }
 
     pub fn from_image_data(data: &ImageData) -> Result<Image, JsError> {
-        let inner = image_from_image_data(data)?.to_rgba8();
-        Ok(Image {
-            inner: img::Image { inner, path: None },
-        })
+        let inner = img::Image::from_image(&image_from_image_data(data)?);
+        Ok(Image { inner })
     }
 }
 
@@ -126,6 +154,38 @@ impl ImageBorders {
         Ok(data)
     }
 
+    #[inline]
+    pub fn set_focus(&mut self, idx: usize, x: f32, y: f32, zoom: f32) {
+        self.inner
+            .set_transform(idx, types::Transform::Focus { x, y, zoom });
+    }
+
+    #[inline]
+    pub fn set_region(&mut self, idx: usize, x: f32, y: f32, width: f32, height: f32) {
+        let region = types::Transform::Region {
+            x,
+            y,
+            width,
+            height,
+        };
+        self.inner.set_transform(idx, region);
+    }
+
+    #[inline]
+    pub fn clear_transform(&mut self, idx: usize) {
+        self.inner.set_transform(idx, None);
+    }
+
+    #[inline]
+    pub fn set_background_image(&mut self, data: Option<ImageData>) -> Result<(), JsError> {
+        let image = match data {
+            Some(data) => Some(Image::from_image_data(&data)?.inner),
+            None => None,
+        };
+        self.inner.set_background_image(image);
+        Ok(())
+    }
+
     #[inline]
     pub fn render(
         &mut self,
@@ -135,7 +195,10 @@ impl ImageBorders {
         println!("border: {:?}", &border);
         crate::debug!(&options);
         let border = match border.custom {
-            None => border.builtin.map(border::Kind::Builtin),
+            None => border
+                .procedural
+                .map(border::Kind::Procedural)
+                .or_else(|| border.builtin.map(border::Kind::Builtin)),
             Some(data) => {
                 let image = Image::from_image_data(&data)?;
                 let border = border::Border::from_image(image.inner, None)
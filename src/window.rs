@@ -0,0 +1,217 @@
+use super::background::{self, Background};
+use super::types::{Color, CropMode, ResizeMode, Size};
+use super::{error, img, FillMode};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum Mode {
+    /// Fill the window, cropping the parts of the image that do not fit
+    Cover,
+    /// Show the whole image on the fill color
+    Contain,
+    /// Show the whole image on a blurred copy of itself
+    Blur,
+}
+
+impl Default for Mode {
+    #[inline]
+    fn default() -> Self {
+        Mode::Cover
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = error::ParseEnum;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "cover" => Ok(Mode::Cover),
+            "contain" | "letterbox" => Ok(Mode::Contain),
+            "blur" => Ok(Mode::Blur),
+            _ => Err(error::ParseEnum::Unknown(s.to_string())),
+        }
+    }
+}
+
+/// Fitting of an image into a window of the border
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(default)]
+pub struct Fit {
+    pub mode: Mode,
+    /// Fill color around the image in the `Contain` mode
+    pub color: Color,
+    /// Blur radius relative to the shorter side of the window
+    pub blur: f32,
+    /// Darkening of the blurred fill between 0 and 1
+    pub darken: f32,
+}
+
+impl Default for Fit {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            mode: Mode::default(),
+            color: Color::black(),
+            blur: 0.1,
+            darken: 0.2,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Fit {
+    #[wasm_bindgen(constructor)]
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Fit {
+    /// Fits `image` into a window of `size`
+    ///
+    /// `crop_mode` positions the image in the `Cover` mode, the other
+    /// modes center the whole image.
+    #[inline]
+    pub fn apply(
+        &self,
+        image: &mut img::Image,
+        size: Size,
+        crop_mode: CropMode,
+    ) -> Result<(), Error> {
+        if self.mode == Mode::Cover {
+            image.resize_and_crop(size, ResizeMode::Cover, crop_mode)?;
+            return Ok(());
+        }
+        let mut window = img::Image::with_size(size);
+        window.fill(self.color, FillMode::Set)?;
+        if self.mode == Mode::Blur {
+            let fill = Background {
+                mode: background::Mode::Blur,
+                blur: self.blur,
+                darken: self.darken,
+                ..Background::default()
+            };
+            fill.draw(&mut window, image, None)?;
+        }
+        image.resize(size, ResizeMode::Contain)?;
+        let offset = size.center(image.size()).map_err(|err| error::Arithmetic {
+            msg: "failed to compute offset of contained image".into(),
+            source: err.into(),
+        })?;
+        window.overlay(image, offset.top_left());
+        image.inner = window.inner;
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to resize image")]
+    Resize(
+        #[from]
+        #[source]
+        img::ResizeError,
+    ),
+
+    #[error("failed to resize and crop image")]
+    ResizeAndCrop(
+        #[from]
+        #[source]
+        img::ResizeAndCropError,
+    ),
+
+    #[error("failed to fill window")]
+    Fill(
+        #[from]
+        #[source]
+        img::FillError,
+    ),
+
+    #[error("failed to draw blurred fill")]
+    Background(
+        #[from]
+        #[source]
+        background::Error,
+    ),
+
+    #[error(transparent)]
+    Arithmetic(#[from] error::Arithmetic),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fit, Mode};
+    use crate::img::Image;
+    use crate::types::{Color, CropMode, Size};
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn portrait() -> Image {
+        // 2:3 photo with a red top and a blue bottom row
+        let image = image::RgbaImage::from_fn(40, 60, |_, y| match y {
+            0 => image::Rgba([255, 0, 0, 255]),
+            59 => image::Rgba([0, 0, 255, 255]),
+            _ => image::Rgba([0, 255, 0, 255]),
+        });
+        Image::from_image(&image::DynamicImage::ImageRgba8(image))
+    }
+
+    const SQUARE: Size = Size {
+        width: 60,
+        height: 60,
+    };
+
+    #[test]
+    fn test_fit_cover() -> Result<()> {
+        let mut image = portrait();
+        Fit::default().apply(&mut image, SQUARE, CropMode::Center)?;
+        assert_eq!(image.size(), SQUARE);
+        // the top and bottom of the photo are cut off
+        assert_eq!(*image.get_pixel(30, 0), image::Rgba([0, 255, 0, 255]));
+        assert_eq!(*image.get_pixel(30, 59), image::Rgba([0, 255, 0, 255]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fit_contain() -> Result<()> {
+        let mut image = portrait();
+        let fit = Fit {
+            mode: Mode::Contain,
+            color: Color::white(),
+            ..Fit::default()
+        };
+        fit.apply(&mut image, SQUARE, CropMode::Center)?;
+        assert_eq!(image.size(), SQUARE);
+        // the whole photo is visible and letterboxed on the sides
+        assert_eq!(*image.get_pixel(30, 0), image::Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(30, 59), image::Rgba([0, 0, 255, 255]));
+        assert_eq!(*image.get_pixel(0, 30), image::Rgba([255, 255, 255, 255]));
+        assert_eq!(*image.get_pixel(59, 30), image::Rgba([255, 255, 255, 255]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fit_blur() -> Result<()> {
+        let mut image = portrait();
+        let fit = Fit {
+            mode: Mode::Blur,
+            darken: 0.5,
+            ..Fit::default()
+        };
+        fit.apply(&mut image, SQUARE, CropMode::Center)?;
+        assert_eq!(image.size(), SQUARE);
+        assert_eq!(*image.get_pixel(30, 0), image::Rgba([255, 0, 0, 255]));
+        // the sides are filled with the darkened photo
+        let side = image.get_pixel(0, 30);
+        assert!(side[1] > 100 && side[1] < 150, "{side:?}");
+        assert_eq!(side[3], 255);
+        Ok(())
+    }
+}
@@ -206,7 +206,7 @@ export default class App extends React.Component<AppProps, AppState> {
     options.preview = this.state.preview;
 
     // margin
-    options.margin = (this.state.margin ?? 0.0) / 100.0;
+    options.margin = Sides.uniform((this.state.margin ?? 0.0) / 100.0);
 
     // crop
     let crop = new Sides();